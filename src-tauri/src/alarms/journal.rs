@@ -0,0 +1,143 @@
+use super::types::AlarmTransition;
+use crate::data_manager::import_export::timestamps::write_timestamp;
+use crate::errors::{FileError, Result};
+use chrono_tz::Tz;
+use log::{info, warn};
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, XlsxError};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+const ALARM_JOURNAL_FILE: &str = "alarms.jsonl";
+
+/// Every alarm transition of every run, one JSON line each. The file outlives
+/// the runs and is only ever appended to.
+pub struct AlarmJournal {
+    path: PathBuf,
+}
+
+impl AlarmJournal {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn get_journal_path(app_handle: &AppHandle) -> PathBuf {
+        app_handle
+            .path()
+            .app_data_dir()
+            .expect("Failed to get app data directory")
+            .join(ALARM_JOURNAL_FILE)
+    }
+
+    pub fn append(&self, transitions: &[AlarmTransition]) -> Result<()> {
+        if transitions.is_empty() {
+            return Ok(());
+        }
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory).map_err(|e| FileError::CreateDirError(e.to_string()))?;
+        }
+
+        let mut lines = String::new();
+        for transition in transitions {
+            let line = serde_json::to_string(transition)
+                .map_err(|e| FileError::SerializeError(e.to_string()))?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| FileError::WriteError(e.to_string()))?;
+        file.write_all(lines.as_bytes())
+            .map_err(|e| FileError::WriteError(e.to_string()))?;
+        file.sync_data()
+            .map_err(|e| FileError::WriteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Transitions with a timestamp in `from..=to`, oldest first
+    pub fn read(&self, from: u64, to: u64) -> Result<Vec<AlarmTransition>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let file = File::open(&self.path).map_err(|e| FileError::ReadError(e.to_string()))?;
+
+        let mut transitions = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| FileError::ReadError(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<AlarmTransition>(&line) {
+                Ok(transition) if (from..=to).contains(&transition.timestamp) => {
+                    transitions.push(transition)
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Skipping alarm journal line {}: {}", index + 1, e),
+            }
+        }
+        Ok(transitions)
+    }
+
+    pub fn export(&self, transitions: &[AlarmTransition], path: &str, time_zone: Tz) -> Result<()> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        let datetime_format = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss.000");
+
+        let headers = [
+            format!("Timestamp ({})", time_zone.name()),
+            "Alarm".to_string(),
+            "Id".to_string(),
+            "Priority".to_string(),
+            "From".to_string(),
+            "To".to_string(),
+            "Value".to_string(),
+        ];
+        for (column, header) in headers.iter().enumerate() {
+            worksheet
+                .write(0, column as u16, header)
+                .map_err(xlsx_error)?;
+        }
+        worksheet.set_column_width(0, 24).map_err(xlsx_error)?;
+        worksheet.set_column_width(1, 30).map_err(xlsx_error)?;
+
+        for (row, transition) in transitions.iter().enumerate() {
+            let row = (row + 1) as u32;
+            write_timestamp(
+                worksheet,
+                row,
+                0,
+                transition.timestamp,
+                time_zone,
+                &datetime_format,
+            )
+            .map_err(xlsx_error)?;
+            let texts = [
+                transition.name.clone(),
+                transition.rule_id.clone(),
+                format!("{:?}", transition.priority),
+                format!("{:?}", transition.from),
+                format!("{:?}", transition.to),
+            ];
+            for (column, text) in texts.iter().enumerate() {
+                worksheet
+                    .write(row, (column + 1) as u16, text)
+                    .map_err(xlsx_error)?;
+            }
+            worksheet
+                .write(row, 6, transition.value)
+                .map_err(xlsx_error)?;
+        }
+
+        workbook.save(path).map_err(xlsx_error)?;
+        info!("Alarm journal exported to {}", path);
+        Ok(())
+    }
+}
+
+fn xlsx_error(e: XlsxError) -> FileError {
+    FileError::WriteError(format!("Xlsx error: {}", e))
+}
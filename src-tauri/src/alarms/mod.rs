@@ -0,0 +1,7 @@
+pub mod journal;
+pub mod service;
+pub mod tests;
+pub mod types;
+
+pub use journal::AlarmJournal;
+pub use service::AlarmEngine;
@@ -0,0 +1,277 @@
+use super::types::{
+    AlarmCondition, AlarmInput, AlarmRule, AlarmState, AlarmStatus, AlarmTransition, KpiField,
+    Phase,
+};
+use crate::data_manager::types::{ColumnEntry, Quality, QualityReason};
+use crate::errors::{AlarmError, Result};
+use crate::kpi::types::KpiSnapshot;
+use crate::settings::types::AlarmSettings;
+use log::{info, warn};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Default)]
+struct RuleState {
+    state: AlarmState,
+    since: Option<u64>,
+    /// Since when the condition disagrees with the state, for the delays
+    pending_since: Option<u64>,
+    /// Last value of the input, for rates
+    previous: Option<(u64, f64)>,
+    shelved_until: Option<u64>,
+}
+
+impl RuleState {
+    fn is_raised(&self) -> bool {
+        matches!(self.state, AlarmState::Active | AlarmState::Acknowledged)
+    }
+}
+
+/// Evaluates the alarm rules against every entry of a run
+#[derive(Debug, Clone, Default)]
+pub struct AlarmEngine {
+    rules: Vec<AlarmRule>,
+    states: Vec<RuleState>,
+    last_timestamp: Option<u64>,
+    /// Playback runs back in time, so timestamps fall from one entry to the next
+    reverse: bool,
+}
+
+impl AlarmEngine {
+    pub fn new(settings: &AlarmSettings) -> Self {
+        Self {
+            rules: settings.rules.clone(),
+            states: vec![RuleState::default(); settings.rules.len()],
+            last_timestamp: None,
+            reverse: false,
+        }
+    }
+
+    /// Delays and rates start over, raised alarms stay. Called when playback moves.
+    pub fn restart_delays(&mut self) {
+        for state in self.states.iter_mut() {
+            state.pending_since = None;
+            state.previous = None;
+        }
+        self.last_timestamp = None;
+    }
+
+    /// Follows the direction of playback, turning around restarts the delays
+    pub fn set_reverse(&mut self, reverse: bool) {
+        if self.reverse != reverse {
+            self.reverse = reverse;
+            self.restart_delays();
+        }
+    }
+
+    /// Returns the transitions caused by the entry
+    pub fn evaluate(&mut self, entry: &ColumnEntry, kpi: &KpiSnapshot) -> Vec<AlarmTransition> {
+        let timestamp = entry.timestamp;
+        // An entry against the direction of playback means it wrapped around
+        let wrapped = self.last_timestamp.is_some_and(|last| match self.reverse {
+            true => timestamp > last,
+            false => timestamp < last,
+        });
+        if wrapped {
+            self.restart_delays();
+        }
+        self.last_timestamp = Some(timestamp);
+
+        let mut transitions = Vec::new();
+        for index in 0..self.rules.len() {
+            let rule = &self.rules[index];
+            let state = &mut self.states[index];
+
+            let (condition, value) = check(rule, state, entry, kpi);
+
+            if state.state == AlarmState::Shelved {
+                // Shelved without a duration until the operator unshelves it
+                if state.shelved_until.is_none_or(|until| timestamp < until) {
+                    continue;
+                }
+                transitions.push(transition(rule, state, timestamp, AlarmState::Normal, None));
+            }
+            let Some(condition) = condition else {
+                state.pending_since = None;
+                continue;
+            };
+
+            if condition == state.is_raised() {
+                state.pending_since = None;
+                continue;
+            }
+            let delay = if condition {
+                rule.on_delay_ms
+            } else {
+                rule.off_delay_ms
+            };
+            let pending_since = *state.pending_since.get_or_insert(timestamp);
+            if timestamp.abs_diff(pending_since) < delay {
+                continue;
+            }
+
+            let to = match (condition, state.state) {
+                (true, _) => AlarmState::Active,
+                (false, AlarmState::Acknowledged) => AlarmState::Normal,
+                (false, _) => AlarmState::Cleared,
+            };
+            if condition {
+                warn!("Alarm {} raised at {:?}", rule.name, value);
+            }
+            transitions.push(transition(rule, state, timestamp, to, value));
+        }
+
+        transitions
+    }
+
+    pub fn acknowledge(&mut self, rule_id: &str) -> Result<AlarmTransition> {
+        let now = self.now();
+        let (rule, state) = self.find(rule_id)?;
+        let to = match state.state {
+            AlarmState::Active => AlarmState::Acknowledged,
+            AlarmState::Cleared => AlarmState::Normal,
+            _ => return Err(AlarmError::NothingToAcknowledge(rule_id.to_string()).into()),
+        };
+        info!("Alarm {} acknowledged", rule.name);
+        Ok(transition(rule, state, now, to, None))
+    }
+
+    /// Suppresses the alarm for `duration_ms` of the run, or until unshelved
+    pub fn shelve(&mut self, rule_id: &str, duration_ms: Option<u64>) -> Result<AlarmTransition> {
+        let now = self.now();
+        let (rule, state) = self.find(rule_id)?;
+        info!("Alarm {} shelved for {:?} ms", rule.name, duration_ms);
+        state.shelved_until = duration_ms.map(|duration| now.saturating_add(duration));
+        Ok(transition(rule, state, now, AlarmState::Shelved, None))
+    }
+
+    /// Returns the alarm to normal, the next entry raises it again if needed
+    pub fn unshelve(&mut self, rule_id: &str) -> Result<Option<AlarmTransition>> {
+        let now = self.now();
+        let (rule, state) = self.find(rule_id)?;
+        if state.state != AlarmState::Shelved {
+            return Ok(None);
+        }
+        Ok(Some(transition(rule, state, now, AlarmState::Normal, None)))
+    }
+
+    pub fn alarms(&self) -> Vec<AlarmStatus> {
+        self.rules
+            .iter()
+            .zip(&self.states)
+            .map(|(rule, state)| AlarmStatus {
+                rule_id: rule.id.clone(),
+                name: rule.name.clone(),
+                priority: rule.priority,
+                state: state.state,
+                since: state.since,
+                shelved_until: state.shelved_until,
+            })
+            .collect()
+    }
+
+    /// Operator actions happen at the newest entry, or now before the first one
+    fn now(&self) -> u64 {
+        self.last_timestamp.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64
+        })
+    }
+
+    fn find(&mut self, rule_id: &str) -> Result<(&AlarmRule, &mut RuleState)> {
+        self.rules
+            .iter()
+            .zip(self.states.iter_mut())
+            .find(|(rule, _)| rule.id == rule_id)
+            .ok_or_else(|| AlarmError::UnknownAlarm(rule_id.to_string()).into())
+    }
+}
+
+fn transition(
+    rule: &AlarmRule,
+    state: &mut RuleState,
+    timestamp: u64,
+    to: AlarmState,
+    value: Option<f64>,
+) -> AlarmTransition {
+    let from = state.state;
+    state.state = to;
+    state.since = Some(timestamp);
+    state.pending_since = None;
+    if to != AlarmState::Shelved {
+        state.shelved_until = None;
+    }
+
+    AlarmTransition {
+        timestamp,
+        rule_id: rule.id.clone(),
+        name: rule.name.clone(),
+        priority: rule.priority,
+        from,
+        to,
+        value,
+    }
+}
+
+/// Whether the condition of the rule holds for the entry, with the deadband
+/// applied to raised alarms. `None` when the entry lacks the input.
+fn check(
+    rule: &AlarmRule,
+    state: &mut RuleState,
+    entry: &ColumnEntry,
+    kpi: &KpiSnapshot,
+) -> (Option<bool>, Option<f64>) {
+    let raised = state.is_raised();
+    let band = if raised { rule.deadband } else { 0.0 };
+
+    match rule.condition {
+        AlarmCondition::High { input, limit } => {
+            let value = input_value(input, entry, kpi);
+            (value.map(|value| value > limit - band), value)
+        }
+        AlarmCondition::Low { input, limit } => {
+            let value = input_value(input, entry, kpi);
+            (value.map(|value| value < limit + band), value)
+        }
+        AlarmCondition::RateOfChange { input, max_rate } => {
+            let Some(value) = input_value(input, entry, kpi) else {
+                return (None, None);
+            };
+            let rate = state.previous.and_then(|(timestamp, previous)| {
+                let minutes = entry.timestamp.abs_diff(timestamp) as f64 / 60_000.0;
+                (minutes > 0.0).then(|| (value - previous).abs() / minutes)
+            });
+            state.previous = Some((entry.timestamp, value));
+            (rate.map(|rate| rate > max_rate - band), rate)
+        }
+        AlarmCondition::CommunicationLoss => {
+            let lost = (0..entry.temperatures.len()).any(|plate| {
+                entry.temperature_quality(plate)
+                    == Quality::Bad(QualityReason::CommunicationFailure)
+            });
+            (Some(lost), None)
+        }
+    }
+}
+
+fn input_value(input: AlarmInput, entry: &ColumnEntry, kpi: &KpiSnapshot) -> Option<f64> {
+    match input {
+        AlarmInput::Temperature { plate } => entry.temperatures.get(plate as usize).copied(),
+        AlarmInput::Composition { plate, phase } => {
+            let composition = entry.compositions.get(plate as usize)?;
+            match phase {
+                Phase::Liquid => composition.x_1,
+                Phase::Vapour => composition.y_1,
+            }
+        }
+        AlarmInput::Kpi { field } => match field {
+            KpiField::StillComposition => kpi.still_composition,
+            KpiField::DistillateComposition => kpi.distillate_composition,
+            KpiField::DistilledMass => kpi.distilled_mass,
+            KpiField::FlowRate => kpi.flow_rate,
+            KpiField::Recovery => kpi.recovery,
+            KpiField::TimeToTarget => kpi.time_to_target,
+        },
+    }
+}
@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod tests {
+    use crate::alarms::types::{
+        AlarmCondition, AlarmInput, AlarmPriority, AlarmRule, AlarmState, AlarmTransition,
+    };
+    use crate::alarms::{AlarmEngine, AlarmJournal};
+    use crate::data_manager::types::{ColumnEntry, EntryQuality, Quality, QualityReason};
+    use crate::kpi::types::KpiSnapshot;
+    use crate::settings::types::AlarmSettings;
+
+    fn engine() -> AlarmEngine {
+        AlarmEngine::new(&AlarmSettings {
+            rules: vec![
+                AlarmRule {
+                    id: "top-high".to_string(),
+                    name: "Top temperature high".to_string(),
+                    condition: AlarmCondition::High {
+                        input: AlarmInput::Temperature { plate: 1 },
+                        limit: 80.0,
+                    },
+                    deadband: 1.0,
+                    on_delay_ms: 2000,
+                    off_delay_ms: 1000,
+                    priority: AlarmPriority::High,
+                },
+                AlarmSettings::default().rules[0].clone(),
+            ],
+        })
+    }
+
+    fn evaluate(engine: &mut AlarmEngine, second: u64, top: f64) -> Vec<(String, AlarmState)> {
+        let entry = ColumnEntry {
+            timestamp: second * 1000,
+            temperatures: vec![90.0, top],
+            ..ColumnEntry::default()
+        };
+        engine
+            .evaluate(&entry, &KpiSnapshot::default())
+            .into_iter()
+            .map(|transition| (transition.rule_id, transition.to))
+            .collect()
+    }
+
+    fn top_high(state: AlarmState) -> Vec<(String, AlarmState)> {
+        vec![("top-high".to_string(), state)]
+    }
+
+    #[test]
+    fn test_delays_deadband_and_acknowledgement() {
+        let mut engine = engine();
+        assert!(evaluate(&mut engine, 0, 81.0).is_empty());
+        assert!(evaluate(&mut engine, 1, 81.0).is_empty());
+        assert_eq!(evaluate(&mut engine, 2, 81.0), top_high(AlarmState::Active));
+
+        // Within the deadband the alarm stays raised
+        assert!(evaluate(&mut engine, 3, 79.5).is_empty());
+        assert!(evaluate(&mut engine, 4, 78.5).is_empty());
+        assert_eq!(
+            evaluate(&mut engine, 5, 78.5),
+            top_high(AlarmState::Cleared)
+        );
+
+        assert_eq!(
+            engine.acknowledge("top-high").unwrap().to,
+            AlarmState::Normal
+        );
+        assert!(engine.acknowledge("top-high").is_err());
+        assert!(engine.acknowledge("missing").is_err());
+
+        for second in 6..8 {
+            evaluate(&mut engine, second, 85.0);
+        }
+        assert_eq!(evaluate(&mut engine, 8, 85.0), top_high(AlarmState::Active));
+        engine.acknowledge("top-high").unwrap();
+        evaluate(&mut engine, 9, 70.0);
+        assert_eq!(
+            evaluate(&mut engine, 10, 70.0),
+            top_high(AlarmState::Normal)
+        );
+    }
+
+    #[test]
+    fn test_shelving_and_communication_loss() {
+        let mut engine = engine();
+        evaluate(&mut engine, 0, 70.0);
+        assert_eq!(
+            engine.shelve("top-high", Some(5000)).unwrap().to,
+            AlarmState::Shelved
+        );
+        for second in 1..5 {
+            assert!(evaluate(&mut engine, second, 85.0).is_empty());
+        }
+        // The shelf runs out, the alarm returns to normal and starts its delay
+        assert_eq!(evaluate(&mut engine, 5, 85.0), top_high(AlarmState::Normal));
+
+        // Without a duration it stays shelved until unshelved
+        engine.shelve("top-high", None).unwrap();
+        for second in 6..60 {
+            assert!(evaluate(&mut engine, second, 85.0).is_empty());
+        }
+        assert_eq!(
+            engine.unshelve("top-high").unwrap().map(|t| t.to),
+            Some(AlarmState::Normal)
+        );
+
+        let lost = ColumnEntry {
+            timestamp: 60_000,
+            temperatures: vec![90.0, 70.0],
+            quality: EntryQuality {
+                temperatures: vec![Quality::Bad(QualityReason::CommunicationFailure); 2],
+                compositions: Vec::new(),
+            },
+            ..ColumnEntry::default()
+        };
+        assert!(engine.evaluate(&lost, &KpiSnapshot::default()).is_empty());
+        let transitions = engine.evaluate(
+            &ColumnEntry {
+                timestamp: 65_000,
+                ..lost
+            },
+            &KpiSnapshot::default(),
+        );
+        assert!(transitions
+            .iter()
+            .any(|t| t.rule_id == "communication-loss" && t.to == AlarmState::Active));
+    }
+
+    #[test]
+    fn test_journal_round_trip() {
+        let path = std::env::temp_dir().join(format!("alarms-test-{}.jsonl", std::process::id()));
+        let journal = AlarmJournal::new(path.clone());
+        let transition = |timestamp| AlarmTransition {
+            timestamp,
+            rule_id: "top-high".to_string(),
+            name: "Top temperature high".to_string(),
+            priority: AlarmPriority::High,
+            from: AlarmState::Normal,
+            to: AlarmState::Active,
+            value: Some(81.0),
+        };
+        journal
+            .append(&[transition(1000), transition(2000)])
+            .unwrap();
+        journal.append(&[transition(3000)]).unwrap();
+
+        assert_eq!(journal.read(0, u64::MAX).unwrap().len(), 3);
+        assert_eq!(journal.read(1500, 2500).unwrap(), vec![transition(2000)]);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+    Liquid,
+    Vapour,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum KpiField {
+    StillComposition,
+    DistillateComposition,
+    DistilledMass,
+    FlowRate,
+    Recovery,
+    TimeToTarget,
+}
+
+/// The value of an entry a rule watches. Plates count from the bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum AlarmInput {
+    #[serde(rename_all = "camelCase")]
+    Temperature { plate: u32 },
+    #[serde(rename_all = "camelCase")]
+    Composition { plate: u32, phase: Phase },
+    #[serde(rename_all = "camelCase")]
+    Kpi { field: KpiField },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum AlarmCondition {
+    #[serde(rename_all = "camelCase")]
+    High { input: AlarmInput, limit: f64 },
+    #[serde(rename_all = "camelCase")]
+    Low { input: AlarmInput, limit: f64 },
+    /// Change of the input per minute, in either direction
+    #[serde(rename_all = "camelCase")]
+    RateOfChange { input: AlarmInput, max_rate: f64 },
+    /// The column could not be read, the entry only holds the last values
+    CommunicationLoss,
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Type,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum AlarmPriority {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+/// A rule raises its alarm once the condition held for `on_delay_ms`, and the
+/// alarm returns to normal once the value is back past the limit by `deadband`
+/// for `off_delay_ms`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmRule {
+    pub id: String,
+    pub name: String,
+    pub condition: AlarmCondition,
+    #[serde(default)]
+    pub deadband: f64,
+    #[serde(default)]
+    pub on_delay_ms: u64,
+    #[serde(default)]
+    pub off_delay_ms: u64,
+    #[serde(default)]
+    pub priority: AlarmPriority,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AlarmState {
+    #[default]
+    Normal,
+    /// Raised and not acknowledged yet
+    Active,
+    /// Raised and acknowledged by the operator
+    Acknowledged,
+    /// Back to normal before it was acknowledged
+    Cleared,
+    /// Suppressed by the operator for a while
+    Shelved,
+}
+
+/// A change of an alarm's state, as written to the alarm journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmTransition {
+    pub timestamp: u64,
+    pub rule_id: String,
+    pub name: String,
+    pub priority: AlarmPriority,
+    pub from: AlarmState,
+    pub to: AlarmState,
+    /// The watched value when the transition was caused by the process
+    pub value: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmStatus {
+    pub rule_id: String,
+    pub name: String,
+    pub priority: AlarmPriority,
+    pub state: AlarmState,
+    /// When the current state was entered
+    pub since: Option<u64>,
+    pub shelved_until: Option<u64>,
+}
@@ -1,6 +1,6 @@
-use super::types::{CompositionResult, EquationParams};
+use super::types::{CompositionResult, EquationParams, ThermoModel};
 use crate::errors::Result;
-use crate::math::{integrate_trapezoidal, interpolate, newton_raphson, round};
+use crate::math::{interpolate, newton_raphson, round};
 use std::f64::consts::E;
 
 #[derive(Debug)]
@@ -15,6 +15,14 @@ impl CalculationService {
         }
     }
 
+    pub fn with_params(params: EquationParams) -> Self {
+        CalculationService { params }
+    }
+
+    pub fn params(&self) -> &EquationParams {
+        &self.params
+    }
+
     pub fn calculate_composition(
         &self,
         x_0: Option<f64>,
@@ -32,7 +40,7 @@ impl CalculationService {
 
         let x_1 = newton_raphson(residual_fn, x_0, tol, max_iter)?;
         let x_2 = 1.0 - x_1;
-        let gamma_1 = calculate_gammas(params.a_van_1, params.a_van_2, x_1, x_2).0;
+        let gamma_1 = calculate_gammas(params, x_1, x_2).0;
         let ps_1 = calculate_ps(temp, params.a_1, params.b_1, params.c_1);
         let k_1 = calculate_ks(gamma_1, ps_1, params.p);
         let y_1 = calculate_y(k_1, x_1);
@@ -44,13 +52,61 @@ impl CalculationService {
         Ok(result)
     }
 
-    pub fn calculate_distilled_mass(&self, m_b0: f64, x_b0: f64, x_bf: f64, x_d: f64) -> f64 {
-        let trap_num: usize = 1000;
-        let f = |x_b: f64| 1.0 / (x_d - x_b);
-        let integral_value = integrate_trapezoidal(f, x_b0, x_bf, trap_num);
-        let mass_final = m_b0 * (integral_value).exp();
+    /// Compositions of every plate, solved at the recorded `pressure` when there is one.
+    /// Missing temperatures and readings the solver can't settle give empty results.
+    pub fn calculate_compositions(
+        &self,
+        temperatures: &[Option<f64>],
+        pressure: Option<f64>,
+    ) -> Vec<CompositionResult> {
+        let row_service = pressure.map(|p| {
+            CalculationService::with_params(EquationParams {
+                p,
+                ..self.params.clone()
+            })
+        });
+        let calculation_service = row_service.as_ref().unwrap_or(self);
+
+        temperatures
+            .iter()
+            .map(|temp| {
+                temp.and_then(|temp| {
+                    calculation_service
+                        .calculate_composition(None, temp, None, None)
+                        .ok()
+                })
+                .unwrap_or(CompositionResult {
+                    x_1: None,
+                    y_1: None,
+                })
+            })
+            .collect()
+    }
 
-        mass_final
+    /// Distilled mass at every sample of a run from the still and distillate
+    /// compositions, zero until both are known. Uses the same balance as the KPIs,
+    /// starting from `initial_charge`.
+    pub fn calculate_distilled_masses<'a, I>(
+        &self,
+        initial_charge: f64,
+        compositions: I,
+    ) -> Vec<f64>
+    where
+        I: IntoIterator<Item = &'a [CompositionResult]>,
+    {
+        let mut balance = StillBalance::new(initial_charge);
+        compositions
+            .into_iter()
+            .map(|compositions| {
+                if let (Some(x_b), Some(x_d)) = (
+                    compositions.first().and_then(|c| c.x_1),
+                    compositions.last().and_then(|c| c.y_1),
+                ) {
+                    balance.step(x_b, x_d);
+                }
+                balance.distilled_mass()
+            })
+            .collect()
     }
 
     pub fn interpolate_temps(&self, num_plates: i32, t_1: f64, t_n: f64) -> Vec<f64> {
@@ -66,12 +122,96 @@ impl CalculationService {
 
         interpolated_temps
     }
+
+    /// Temperatures of every plate from the measured ones, linear in between
+    /// and held flat beyond the outermost sensors
+    pub fn interpolate_profile(&self, num_plates: i32, measured: &[(u32, f64)]) -> Vec<f64> {
+        let mut measured = measured.to_vec();
+        measured.sort_by_key(|&(plate, _)| plate);
+        let (Some(&first), Some(&last)) = (measured.first(), measured.last()) else {
+            return Vec::new();
+        };
+
+        (0..num_plates.max(1) as u32)
+            .map(|plate| {
+                let temp = match measured.iter().position(|&(at, _)| at >= plate) {
+                    _ if plate <= first.0 => first.1,
+                    None => last.1,
+                    Some(index) if measured[index].0 == plate => measured[index].1,
+                    Some(index) => {
+                        let (below, above) = (measured[index - 1], measured[index]);
+                        interpolate(
+                            below.0 as f64,
+                            below.1,
+                            above.0 as f64,
+                            above.1,
+                            plate as f64,
+                        )
+                    }
+                };
+                round(temp, 3)
+            })
+            .collect()
+    }
+}
+
+/// Still and distillate have to differ by this much for the balance to move
+const MIN_ENRICHMENT: f64 = 1e-6;
+
+/// Rayleigh balance of the still, stepped entry by entry from the first one
+/// with a solved still and distillate composition
+#[derive(Debug, Clone, Default)]
+pub struct StillBalance {
+    initial_charge: f64,
+    initial_composition: Option<f64>,
+    /// Current still composition and mass
+    still: Option<(f64, f64)>,
+}
+
+impl StillBalance {
+    pub fn new(initial_charge: f64) -> Self {
+        Self {
+            initial_charge,
+            ..Self::default()
+        }
+    }
+
+    /// Moves the balance on to the still composition `x_b` and distillate
+    /// composition `x_d` of the next entry, and returns the still mass
+    pub fn step(&mut self, x_b: f64, x_d: f64) -> f64 {
+        let (previous_x_b, previous_mass) = *self.still.get_or_insert((x_b, self.initial_charge));
+        self.initial_composition.get_or_insert(x_b);
+
+        // ln(W / W_prev) = ∫ dx / (x_D - x), over the step between the entries
+        let enrichment = x_d - (x_b + previous_x_b) / 2.0;
+        let still_mass = if enrichment > MIN_ENRICHMENT {
+            (previous_mass * ((x_b - previous_x_b) / enrichment).exp())
+                .clamp(0.0, self.initial_charge)
+        } else {
+            previous_mass
+        };
+        self.still = Some((x_b, still_mass));
+        still_mass
+    }
+
+    pub fn initial_charge(&self) -> f64 {
+        self.initial_charge
+    }
+
+    pub fn initial_composition(&self) -> Option<f64> {
+        self.initial_composition
+    }
+
+    pub fn distilled_mass(&self) -> f64 {
+        self.still
+            .map_or(0.0, |(_, still_mass)| self.initial_charge - still_mass)
+    }
 }
 
 fn calculate_residual(x_1: f64, temp: f64, params: &EquationParams) -> f64 {
     let x_2 = 1.0 - x_1;
 
-    let (gamma_1, gamma_2) = calculate_gammas(params.a_van_1, params.a_van_2, x_1, x_2);
+    let (gamma_1, gamma_2) = calculate_gammas(params, x_1, x_2);
 
     let ps_1 = calculate_ps(temp, params.a_1, params.b_1, params.c_1);
     let ps_2 = calculate_ps(temp, params.a_2, params.b_2, params.c_2);
@@ -92,7 +232,12 @@ fn calculate_ps(temp: f64, a: f64, b: f64, c: f64) -> f64 {
     return p;
 }
 
-fn calculate_gammas(a_12: f64, a_21: f64, x_1: f64, x_2: f64) -> (f64, f64) {
+fn calculate_gammas(params: &EquationParams, x_1: f64, x_2: f64) -> (f64, f64) {
+    if params.model == ThermoModel::Ideal {
+        return (1.0, 1.0);
+    }
+
+    let (a_12, a_21) = (params.a_van_1, params.a_van_2);
     let denominator = a_12 * x_1 + a_21 * x_2;
     let gamma1 = E.powf(a_12 * (a_21 * x_2 / denominator).powf(2.0));
     let gamma2 = E.powf(a_21 * (a_12 * x_1 / denominator).powf(2.0));
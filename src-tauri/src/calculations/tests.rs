@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::calculations::service::CalculationService;
+    use crate::calculations::types::{CompositionResult, EquationParams, ThermoModel};
 
     #[test]
     fn test_calculate_composition() {
@@ -25,4 +26,47 @@ mod tests {
         assert!(temps[3] == 85.0);
         assert!(temps[4] == 90.0);
     }
+
+    #[test]
+    fn test_model_and_pressure_change_compositions() {
+        let temperatures = [Some(75.0), None];
+        let van_laar = CalculationService::new().calculate_compositions(&temperatures, None);
+        let ideal = CalculationService::with_params(EquationParams {
+            model: ThermoModel::Ideal,
+            ..EquationParams::default()
+        })
+        .calculate_compositions(&temperatures, None);
+        let recorded = CalculationService::new().calculate_compositions(&temperatures, Some(760.0));
+
+        assert!(van_laar[1].x_1.is_none());
+        assert_ne!(van_laar[0].x_1, ideal[0].x_1);
+        assert_ne!(van_laar[0].x_1, recorded[0].x_1);
+    }
+
+    #[test]
+    fn test_distilled_mass_starts_from_the_first_still_composition() {
+        let still = |x_1: f64| {
+            vec![
+                CompositionResult {
+                    x_1: Some(x_1),
+                    y_1: None,
+                },
+                CompositionResult {
+                    x_1: None,
+                    y_1: Some(0.9),
+                },
+            ]
+        };
+        let service = CalculationService::new();
+
+        // An unchanged still has distilled nothing, whatever its composition
+        let steady = [still(0.4), still(0.4), still(0.4)];
+        let masses = service.calculate_distilled_masses(1000.0, steady.iter().map(Vec::as_slice));
+        assert_eq!(masses, vec![0.0, 0.0, 0.0]);
+
+        let depleting = [still(0.4), still(0.35), still(0.3)];
+        let masses =
+            service.calculate_distilled_masses(1000.0, depleting.iter().map(Vec::as_slice));
+        assert!(masses[1] > 0.0 && masses[2] > masses[1] && masses[2] < 1000.0);
+    }
 }
@@ -1,6 +1,18 @@
 use serde::{Deserialize, Serialize};
+use specta::Type;
 
-#[derive(Debug)]
+/// How the liquid phase deviates from Raoult's law
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, Type)]
+pub enum ThermoModel {
+    #[default]
+    VanLaar,
+    /// Ideal solution, activity coefficients of one
+    Ideal,
+}
+
+/// Antoine constants and Van Laar coefficients of the mixture, with the column
+/// pressure in the unit of the Antoine equation
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct EquationParams {
     pub a_1: f64,
     pub b_1: f64,
@@ -13,6 +25,8 @@ pub struct EquationParams {
     pub a_van_2: f64,
 
     pub p: f64,
+    #[serde(default)]
+    pub model: ThermoModel,
 }
 
 impl Default for EquationParams {
@@ -29,12 +43,20 @@ impl Default for EquationParams {
             a_van_2: 0.9227,
 
             p: 585.0,
+            model: ThermoModel::VanLaar,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
 pub struct CompositionResult {
     pub x_1: Option<f64>,
     pub y_1: Option<f64>,
 }
+
+impl CompositionResult {
+    /// Both the liquid and the vapour fraction are known
+    pub fn is_complete(&self) -> bool {
+        self.x_1.is_some() && self.y_1.is_some()
+    }
+}
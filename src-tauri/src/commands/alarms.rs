@@ -0,0 +1,122 @@
+use crate::alarms::types::{AlarmStatus, AlarmTransition};
+use crate::errors::{DataError, Result};
+use crate::settings::SettingsService;
+use crate::AppState;
+use log::info;
+use tauri::{AppHandle, Emitter, State};
+
+#[tauri::command]
+#[specta::specta]
+pub async fn alarm_status(app_state: State<'_, AppState>) -> Result<Vec<AlarmStatus>> {
+    let transmission_guard = app_state.transmission_state.lock().await;
+    Ok(transmission_guard.alarms.alarms())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn acknowledge_alarm(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    rule_id: String,
+) -> Result<AlarmTransition> {
+    let (transition, is_live) = {
+        let mut transmission_guard = app_state.transmission_state.lock().await;
+        (
+            transmission_guard.alarms.acknowledge(&rule_id)?,
+            transmission_guard.data_provider.is_live(),
+        )
+    };
+    record(&app_handle, &app_state, transition, is_live)
+}
+
+/// Shelves the alarm for `duration_ms`, or until it is unshelved
+#[tauri::command]
+#[specta::specta]
+pub async fn shelve_alarm(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    rule_id: String,
+    duration_ms: Option<u64>,
+) -> Result<AlarmTransition> {
+    let (transition, is_live) = {
+        let mut transmission_guard = app_state.transmission_state.lock().await;
+        (
+            transmission_guard.alarms.shelve(&rule_id, duration_ms)?,
+            transmission_guard.data_provider.is_live(),
+        )
+    };
+    record(&app_handle, &app_state, transition, is_live)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn unshelve_alarm(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    rule_id: String,
+) -> Result<Option<AlarmTransition>> {
+    let (transition, is_live) = {
+        let mut transmission_guard = app_state.transmission_state.lock().await;
+        (
+            transmission_guard.alarms.unshelve(&rule_id)?,
+            transmission_guard.data_provider.is_live(),
+        )
+    };
+    transition
+        .map(|transition| record(&app_handle, &app_state, transition, is_live))
+        .transpose()
+}
+
+/// The alarm journal over a time range, oldest first
+#[tauri::command]
+#[specta::specta]
+pub async fn alarm_journal(
+    app_state: State<'_, AppState>,
+    from: Option<u64>,
+    to: Option<u64>,
+) -> Result<Vec<AlarmTransition>> {
+    app_state
+        .alarm_journal
+        .read(from.unwrap_or(0), to.unwrap_or(u64::MAX))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn export_alarm_journal(
+    app_state: State<'_, AppState>,
+    path: String,
+    from: Option<u64>,
+    to: Option<u64>,
+) -> Result<()> {
+    info!("Exporting alarm journal to {}", path);
+    let transitions = app_state
+        .alarm_journal
+        .read(from.unwrap_or(0), to.unwrap_or(u64::MAX))?;
+    let time_zone = SettingsService::new()
+        .get_settings(&app_state.settings_path)
+        .unwrap_or_default()
+        .time
+        .time_zone()?;
+    app_state
+        .alarm_journal
+        .export(&transitions, &path, time_zone)
+}
+
+/// Journals an operator action on live data and tells the UI about it. Actions
+/// during playback happen at recorded times and stay out of the journal.
+fn record(
+    app_handle: &AppHandle,
+    app_state: &AppState,
+    transition: AlarmTransition,
+    is_live: bool,
+) -> Result<AlarmTransition> {
+    if is_live {
+        app_state
+            .alarm_journal
+            .append(std::slice::from_ref(&transition))?;
+    }
+    app_handle
+        .emit("alarm", transition.clone())
+        .map_err(|e| DataError::CustomError(e.to_string()))?;
+    Ok(transition)
+}
@@ -0,0 +1,51 @@
+use crate::comparison::types::{Alignment, ComparisonMetrics, SeriesInfo};
+use crate::comparison::Comparison;
+use crate::errors::{DataError, Result};
+use crate::AppState;
+use log::info;
+use tauri::State;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn load_comparison(
+    app_state: State<'_, AppState>,
+    session_ids: Vec<i64>,
+    alignment: Alignment,
+) -> Result<Vec<SeriesInfo>> {
+    info!("Loading sessions {:?} for comparison", session_ids);
+    let runs = {
+        let session_service = app_state.session_service.lock().await;
+        session_ids
+            .iter()
+            .map(|&id| {
+                Ok((
+                    session_service.get_session(id)?,
+                    session_service.load_entries(id)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let comparison = Comparison::new(runs, alignment)?;
+    let series = comparison.series();
+    *app_state.comparison.lock().await = Some(comparison);
+
+    Ok(series)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn comparison_metrics(app_state: State<'_, AppState>) -> Result<ComparisonMetrics> {
+    info!("Computing comparison metrics");
+    let comparison_guard = app_state.comparison.lock().await;
+    let comparison = comparison_guard.as_ref().ok_or(DataError::NoDataError)?;
+    Ok(comparison.metrics())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn clear_comparison(app_state: State<'_, AppState>) -> Result<()> {
+    info!("Clearing comparison");
+    *app_state.comparison.lock().await = None;
+    Ok(())
+}
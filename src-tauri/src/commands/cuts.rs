@@ -0,0 +1,41 @@
+use crate::cuts::types::{CutEvent, CutStatus};
+use crate::errors::{DataError, Result};
+use crate::AppState;
+use log::info;
+use tauri::{AppHandle, Emitter, State};
+
+/// The current cut of the running batch and the yield of every cut so far
+#[tauri::command]
+#[specta::specta]
+pub async fn cut_status(app_state: State<'_, AppState>) -> Result<Option<CutStatus>> {
+    let transmission_guard = app_state.transmission_state.lock().await;
+    Ok(transmission_guard.cuts.status())
+}
+
+/// Records that the operator switched to the next cut at the newest entry
+#[tauri::command]
+#[specta::specta]
+pub async fn switch_cut(app_handle: AppHandle, app_state: State<'_, AppState>) -> Result<CutEvent> {
+    info!("Switching cut");
+    let event = {
+        let mut transmission_guard = app_state.transmission_state.lock().await;
+        let event = transmission_guard
+            .cuts
+            .switch()
+            .ok_or(DataError::NoNextCut)?;
+
+        // Kept with the recording, so playback makes the switch again
+        if let (Some(session_id), Some(switch)) = (
+            transmission_guard.recording_session,
+            transmission_guard.cuts.switches().last(),
+        ) {
+            let session_service = app_state.session_service.lock().await;
+            session_service.add_cut_switch(session_id, switch)?;
+        }
+        event
+    };
+    app_handle
+        .emit("cut_event", event.clone())
+        .map_err(|e| DataError::CustomError(e.to_string()))?;
+    Ok(event)
+}
@@ -1,8 +1,23 @@
-use crate::data_manager::import_export::ExcelDataImporter;
+use crate::data_manager::import_export::types::{
+    ColumnMapping, ImportPreview, ImportSource, ImportSummary, InvalidRowPolicy, MergeSummary,
+    PendingImport,
+};
+use crate::data_manager::import_export::{ExcelDataExporter, ExcelDataImporter};
+use crate::data_manager::types::ColumnEntry;
+use crate::errors::{DataError, FileError, ImportError, Result};
+use crate::kpi::KpiTracker;
+use crate::reports::types::{ReportEvent, ReportEventKind, ReportMetadata};
+use crate::reports::ReportService;
+use crate::runs::types::RunInfo;
+use crate::sessions::types::{SessionInfo, SessionSource};
+use crate::settings::SettingsService;
 use crate::AppState;
 use crate::{calculations::service::CalculationService, data_manager::factory::ProviderFactory};
 use log::info;
-use rust_xlsxwriter::{Workbook, XlsxError};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use tauri::{AppHandle, Emitter, State};
 
 #[tauri::command]
@@ -11,107 +26,371 @@ pub async fn import_data(
     app_handle: AppHandle,
     app_state: State<'_, AppState>,
     path: String,
-) -> Result<(), String> {
+    mapping: Option<ColumnMapping>,
+    policy: Option<InvalidRowPolicy>,
+) -> Result<ImportSummary> {
     info!("Importing data from {}", path);
-    let calculation_service = CalculationService::new();
-    let importer = ExcelDataImporter::new(calculation_service);
+    let cancel = app_state.import_cancel.clone();
+    cancel.store(false, Ordering::Relaxed);
+    let settings = SettingsService::new()
+        .get_settings(&app_state.settings_path)
+        .unwrap_or_default();
+    let time_zone = settings.time.time_zone()?;
+    let initial_charge = settings.kpi.initial_charge;
 
-    let (number_plates, imported_data) = importer.import(&path).await.map_err(|e| e.to_string())?;
+    // Large workbooks take a while, keep the async runtime free while reading them
+    let progress_handle = app_handle.clone();
+    let import_path = path.clone();
+    let imported = tokio::task::spawn_blocking(move || {
+        let calculation_service = CalculationService::new();
+        let importer = ExcelDataImporter::new(calculation_service)
+            .with_time_zone(time_zone)
+            .with_initial_charge(initial_charge);
+        importer.import(
+            &import_path,
+            mapping.as_ref(),
+            policy.unwrap_or_default(),
+            &cancel,
+            |progress| {
+                let _ = progress_handle.emit("import_progress", progress);
+            },
+        )
+    })
+    .await
+    .map_err(|e| ImportError::TaskError(e.to_string()))?;
+
+    // Nothing is played back until the user accepts the validation report
+    let name = Path::new(&path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("Imported run")
+        .to_string();
+    let mut pending_import = app_state.pending_import.lock().await;
+    PendingImport::stage(
+        &mut pending_import,
+        name,
+        imported,
+        |summary: &ImportSummary| summary.number_plates,
+    )
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn import_files(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    sources: Vec<ImportSource>,
+    mapping: Option<ColumnMapping>,
+    policy: Option<InvalidRowPolicy>,
+) -> Result<MergeSummary> {
+    info!("Importing {} sources", sources.len());
+    let Some(first) = sources.first() else {
+        return Err(ImportError::InvalidFormat("No files selected".into()).into());
+    };
+    let name = Path::new(&first.path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("Imported run")
+        .to_string();
+
+    let cancel = app_state.import_cancel.clone();
+    cancel.store(false, Ordering::Relaxed);
+    let settings = SettingsService::new()
+        .get_settings(&app_state.settings_path)
+        .unwrap_or_default();
+    let time_zone = settings.time.time_zone()?;
+    let initial_charge = settings.kpi.initial_charge;
+
+    let progress_handle = app_handle.clone();
+    let imported = tokio::task::spawn_blocking(move || {
+        let importer = ExcelDataImporter::new(CalculationService::new())
+            .with_time_zone(time_zone)
+            .with_initial_charge(initial_charge);
+        importer.import_many(
+            &sources,
+            mapping.as_ref(),
+            policy.unwrap_or_default(),
+            &cancel,
+            |progress| {
+                let _ = progress_handle.emit("import_progress", progress);
+            },
+        )
+    })
+    .await
+    .map_err(|e| ImportError::TaskError(e.to_string()))?;
+
+    let mut pending_import = app_state.pending_import.lock().await;
+    PendingImport::stage(
+        &mut pending_import,
+        name,
+        imported,
+        |summary: &MergeSummary| summary.number_plates,
+    )
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_sheets(path: String) -> Result<Vec<String>> {
+    info!("Listing sheets of {}", path);
+    let importer = ExcelDataImporter::new(CalculationService::new());
+    importer.sheet_names(&path)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn commit_import(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+) -> Result<SessionInfo> {
+    let pending = app_state
+        .pending_import
+        .lock()
+        .await
+        .take()
+        .ok_or(ImportError::NothingPending)?;
+    info!("Committing import {}", pending.name);
+    let number_plates = pending.number_plates;
+
+    // Keep a copy of the imported run in the session store
+    let session = {
+        let mut session_service = app_state.session_service.lock().await;
+        let session = session_service.create_session(
+            &pending.name,
+            SessionSource::Imported,
+            number_plates,
+        )?;
+        session_service.append_entries(session.id, &pending.entries)?;
+        session_service.get_session(session.id)?
+    };
 
     let provider_factory = ProviderFactory::new();
-    let provider = provider_factory.create_playback_provider(imported_data, 0);
+    let provider = provider_factory.create_playback_provider(pending.entries, 0);
 
     {
         let mut transmission_guard = app_state.transmission_state.lock().await;
         transmission_guard.set_data_provider(provider);
+        // A new file has no switches, none of the previous run carry over
+        transmission_guard.cuts.reset();
     }
 
     app_handle
         .emit("number_plates", number_plates)
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| DataError::CustomError(e.to_string()))?;
+
+    Ok(session)
+}
 
+#[tauri::command]
+#[specta::specta]
+pub async fn discard_import(app_state: State<'_, AppState>) -> Result<()> {
+    info!("Discarding pending import");
+    app_state.pending_import.lock().await.take();
     Ok(())
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn import_temperatures(path: &str) -> Result<(), String> {
+pub async fn preview_import(path: String, rows: Option<u32>) -> Result<ImportPreview> {
+    info!("Previewing {}", path);
+    let preview = tokio::task::spawn_blocking(move || {
+        let importer = ExcelDataImporter::new(CalculationService::new());
+        importer.preview(&path, rows.unwrap_or(10) as usize)
+    })
+    .await
+    .map_err(|e| ImportError::TaskError(e.to_string()))??;
+
+    Ok(preview)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_import(app_state: State<'_, AppState>) -> Result<()> {
+    info!("Cancelling import");
+    app_state.import_cancel.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn import_temperatures(path: &str) -> Result<()> {
     info!("Importing data from {}", path);
     todo!()
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn export_data(app_state: State<'_, AppState>, path: String) -> Result<(), String> {
+pub async fn export_data(app_state: State<'_, AppState>, path: String) -> Result<()> {
     info!("Export data to excel...");
-    let mut workbook = Workbook::new();
-    let worksheet = workbook.add_worksheet();
+    // Only the in memory part of the run is copied while the lock is held
+    let snapshot = app_state.history.lock().await.snapshot();
+    if snapshot.is_empty() {
+        return Err(DataError::EmptyDataError.into());
+    }
+    let column_data = snapshot.entries()?;
 
-    let column_data = {
-        let data_column = app_state.history.lock().await;
-        data_column.history.clone()
-    };
+    let settings = SettingsService::new()
+        .get_settings(&app_state.settings_path)
+        .unwrap_or_default();
+    let annotations = app_state
+        .transmission_state
+        .lock()
+        .await
+        .annotations
+        .annotations()
+        .to_vec();
+    let mut exporter = ExcelDataExporter::new()
+        .with_time_zone(settings.time.time_zone()?)
+        .with_annotations(annotations);
+    match exported_run(&app_state, &column_data).await? {
+        Some(run) => {
+            exporter = exporter
+                .with_kpis(run.kpi_settings(&settings.kpi))
+                .with_run(run)
+        }
+        None => exporter = exporter.with_kpis(settings.kpi),
+    }
+    exporter.export(&column_data, &path)?;
 
-    info!("Writing headers...");
+    Ok(())
+}
 
-    let Some(first) = column_data.first() else {
-        return Err("No current data".into());
+/// The run the exported entries were recorded for
+async fn exported_run(
+    app_state: &State<'_, AppState>,
+    column_data: &[Arc<ColumnEntry>],
+) -> Result<Option<RunInfo>> {
+    let Some(run_id) = column_data
+        .iter()
+        .find_map(|entry| entry.run.as_ref().map(|tag| tag.run_id))
+    else {
+        return Ok(None);
     };
+    let session_service = app_state.session_service.lock().await;
+    Ok(Some(session_service.get_run(run_id)?))
+}
 
-    // write headers
-    worksheet
-        .write(0, 0, "Timestamp")
-        .map_err(|e: XlsxError| format!("Xlsx error: {}", e))?;
-    let num_values = first.temperatures.len();
-    for i in 0..num_values {
-        worksheet
-            .write(0, (i + 1) as u16, format!("Temperature {}", i + 1))
-            .map_err(|e: XlsxError| format!("Xlsx error: {}", e))?;
-        worksheet
-            .write(
-                0,
-                (num_values + i + 1) as u16,
-                format!("Composition x_1 {}", i + 1),
-            )
-            .map_err(|e: XlsxError| format!("Xlsx error: {}", e))?;
-        worksheet
-            .write(
-                0,
-                (num_values * 2 + i + 1) as u16,
-                format!("Composition y_1 {}", i + 1),
-            )
-            .map_err(|e: XlsxError| format!("Xlsx error: {}", e))?;
+#[tauri::command]
+#[specta::specta]
+pub async fn export_report(
+    app_state: State<'_, AppState>,
+    path: String,
+    title: Option<String>,
+) -> Result<()> {
+    info!("Export report to {}", path);
+    let snapshot = app_state.history.lock().await.snapshot();
+    if snapshot.is_empty() {
+        return Err(DataError::EmptyDataError.into());
     }
+    let column_data = snapshot.entries()?;
 
-    // write data
-    info!("Writing data");
-    for (row, value) in column_data.iter().enumerate() {
-        let row = (row + 1) as u32;
-        worksheet
-            .write(row, 0, value.timestamp)
-            .map_err(|e: XlsxError| format!("Xlsx error: {}", e))?;
-
-        for (i, &temp) in value.temperatures.iter().enumerate() {
-            worksheet
-                .write(row, (i + 1) as u16, temp)
-                .map_err(|e: XlsxError| format!("Xlsx error: {}", e))?;
+    let mut metadata = ReportMetadata::new(title.as_deref().unwrap_or("Distillation batch report"));
+    let settings = SettingsService::new()
+        .get_settings(&app_state.settings_path)
+        .unwrap_or_default();
+    let run = exported_run(&app_state, &column_data).await?;
+    let mut events = Vec::new();
+    let kpi_settings = match &run {
+        Some(run) => {
+            let fields = [
+                ("Batch ID", run.metadata.batch_id.clone()),
+                ("Operator", run.metadata.operator.clone()),
+                ("Feed", run.metadata.feed_description.clone()),
+                ("Charge mass", format!("{:.3}", run.metadata.charge_mass)),
+            ];
+            for (name, value) in fields {
+                if !value.is_empty() {
+                    metadata = metadata.with_field(name, value);
+                }
+            }
+            events.extend(run.phases.iter().map(|change| ReportEvent {
+                timestamp: change.timestamp,
+                kind: ReportEventKind::Process,
+                description: format!("{} phase started", change.phase.describe()),
+            }));
+            run.kpi_settings(&settings.kpi)
+        }
+        None => settings.kpi,
+    };
+    if let Some(kpi) = KpiTracker::replay(kpi_settings, column_data.iter().map(AsRef::as_ref)).pop()
+    {
+        let figures = [
+            ("Distilled mass", kpi.distilled_mass),
+            (
+                "Recovery (%)",
+                kpi.recovery.map(|recovery| recovery * 100.0),
+            ),
+            ("Final still x_B", kpi.still_composition),
+            ("Final distillate x_D", kpi.distillate_composition),
+        ];
+        for (name, value) in figures {
+            if let Some(value) = value {
+                metadata = metadata.with_field(name, format!("{:.3}", value));
+            }
         }
-        for (i, comp) in value.compositions.iter().enumerate() {
-            worksheet
-                .write(row, (num_values + i + 1) as u16, comp.x_1)
-                .map_err(|e: XlsxError| format!("Xlsx error: {}", e))?;
-
-            worksheet
-                .write(row, (num_values * 2 + i + 1) as u16, comp.y_1)
-                .map_err(|e: XlsxError| format!("Xlsx error: {}", e))?;
+    }
+
+    let (start, end) = match (column_data.first(), column_data.last()) {
+        (Some(first), Some(last)) => (first.timestamp, last.timestamp),
+        _ => (0, 0),
+    };
+
+    // Cut switches of the batch and what each cut yielded
+    let (yields, notes) = {
+        let transmission_guard = app_state.transmission_state.lock().await;
+        let notes: Vec<ReportEvent> = transmission_guard
+            .annotations
+            .annotations()
+            .iter()
+            .filter(|note| (start..=end).contains(&note.timestamp))
+            .map(|note| ReportEvent {
+                timestamp: note.timestamp,
+                kind: ReportEventKind::Note,
+                description: note.text.clone(),
+            })
+            .collect();
+        (transmission_guard.cuts.yields(), notes)
+    };
+    events.extend(yields.iter().skip(1).map(|cut| ReportEvent {
+        timestamp: cut.start,
+        kind: ReportEventKind::Process,
+        description: format!("Switched to the {} cut", cut.name),
+    }));
+    events.extend(notes);
+    events.extend(
+        app_state
+            .alarm_journal
+            .read(start, end)?
+            .into_iter()
+            .map(|transition| ReportEvent {
+                timestamp: transition.timestamp,
+                kind: ReportEventKind::Alarm,
+                description: match transition.value {
+                    Some(value) => format!(
+                        "{}: {:?} to {:?} at {:.3}",
+                        transition.name, transition.from, transition.to, value
+                    ),
+                    None => format!(
+                        "{}: {:?} to {:?}",
+                        transition.name, transition.from, transition.to
+                    ),
+                },
+            }),
+    );
+    events.sort_by_key(|event| event.timestamp);
+    for cut in &yields {
+        if let (Some(mass), Some(share)) = (cut.mass, cut.share) {
+            metadata = metadata.with_field(
+                &format!("{} yield", cut.name),
+                format!("{:.3} ({:.1} %)", mass, share * 100.0),
+            );
         }
     }
 
-    info!("Saving excel...");
-    workbook
-        .save(path)
-        .map_err(|e: XlsxError| format!("Xlsx error: {}", e))?;
-    info!("Excel saved");
+    let report_service = ReportService::new();
+    let html = report_service.render_html(&column_data, &metadata, &events);
+
+    fs::write(&path, html).map_err(|e| FileError::WriteError(e.to_string()))?;
+    info!("Report saved");
 
     Ok(())
 }
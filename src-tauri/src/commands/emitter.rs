@@ -1,11 +1,32 @@
-use log::info;
+use log::{error, info};
+use std::sync::Arc;
 use std::time::Instant;
 use tauri::{AppHandle, Emitter};
 use tokio::time::Duration;
 
-use crate::AppState;
+use crate::alarms::types::AlarmTransition;
+use crate::alarms::{AlarmEngine, AlarmJournal};
+use crate::cuts::types::CutEvent;
+use crate::cuts::CutTracker;
+use crate::data_manager::import_export::types::TimeRange;
+use crate::data_manager::types::{ColumnEntry, PlaybackPosition, SeekTarget};
+use crate::diagnostics::types::SensorFault;
+use crate::errors::DataError;
+use crate::kpi::types::KpiSnapshot;
+use crate::kpi::KpiTracker;
+use crate::processing::types::ProcessedEntry;
+use crate::processing::Pipeline;
+use crate::sessions::types::{Annotation, SessionSource};
+use crate::sessions::AnnotationTrack;
+use crate::settings::SettingsService;
+use crate::{AppState, TransmissionState};
 use tauri::State;
 
+/// Pace of live polling at normal speed
+const LIVE_INTERVAL_MS: u64 = 1000;
+/// How often a paused transmission checks whether it was resumed
+const PAUSE_POLL_MS: u64 = 100;
+
 #[tauri::command]
 #[specta::specta]
 pub async fn send_column_data(
@@ -14,53 +35,278 @@ pub async fn send_column_data(
     number_plates: i32,
 ) -> Result<(), String> {
     info!("Initializing send_column_data...");
+    let settings = SettingsService::new()
+        .get_settings(&app_state.settings_path)
+        .unwrap_or_default();
     {
         // initialize transmission state
-        let transmission_guard = app_state.transmission_state.clone();
-        transmission_guard.lock().await.set_is_running(true);
+        let mut transmission_guard = app_state.transmission_state.lock().await;
+        transmission_guard.set_is_running(true);
+        let reverse = transmission_guard
+            .data_provider
+            .position()
+            .is_some_and(|position| position.reverse);
+        transmission_guard.pipeline = Pipeline::new(&settings.processing);
+        transmission_guard.kpis = KpiTracker::new(match transmission_guard.run.as_ref() {
+            Some(run) => run.info().kpi_settings(&settings.kpi),
+            None => settings.kpi.clone(),
+        });
+        // Played back sessions make the switches stored with them again
+        let switches = match transmission_guard.data_provider.is_live() {
+            true => Vec::new(),
+            false => transmission_guard.cuts.switches().to_vec(),
+        };
+        transmission_guard.cuts = CutTracker::new(&settings.cuts);
+        transmission_guard.cuts.set_switches(switches);
+        transmission_guard.alarms = AlarmEngine::new(&settings.alarms);
+        transmission_guard.set_reverse(reverse);
+
+        // live data is recorded into a new session as it is produced
+        if transmission_guard.data_provider.is_live()
+            && transmission_guard.recording_session.is_none()
+        {
+            let session_service = app_state.session_service.lock().await;
+            let session = session_service.create_session(
+                "Live run",
+                SessionSource::Live,
+                number_plates as u32,
+            )?;
+            transmission_guard.recording_session = Some(session.id);
+            transmission_guard.annotations = AnnotationTrack::new(session.id, Vec::new());
+        }
+
+        // and journaled to disk so a crash does not lose the run
+        let mut journal_guard = app_state.journal_writer.lock().await;
+        if transmission_guard.data_provider.is_live() && journal_guard.is_none() {
+            let writer = app_state
+                .journal_service
+                .create(number_plates as u32, settings.recording.fsync_policy)?;
+            *journal_guard = Some(writer);
+        }
+    }
+
+    // Whatever stops the transmission early, it is no longer running
+    let transmitted = transmit(&app_handle, &app_state, number_plates).await;
+    if transmitted.is_err() {
+        app_state
+            .transmission_state
+            .lock()
+            .await
+            .set_is_running(false);
     }
+    transmitted
+}
 
+async fn transmit(
+    app_handle: &AppHandle,
+    app_state: &AppState,
+    number_plates: i32,
+) -> Result<(), String> {
     loop {
         let start_time = Instant::now();
 
-        let (speed, entry) = {
+        let (speed_factor, entry, recording_session, delay, position, outputs) = {
             let mut transmission_guard = app_state.transmission_state.lock().await;
             print!("\n------------------------\n");
             println!("\nTransmission state: {:?}", transmission_guard.is_running);
             println!("Paused: {}", transmission_guard.is_paused);
-            println!("Speed: {}", transmission_guard.speed);
+            println!("Speed: {}", transmission_guard.speed_factor);
 
             if !transmission_guard.is_running {
                 return Ok(());
             }
 
             if transmission_guard.is_paused {
+                drop(transmission_guard);
+                tokio::time::sleep(Duration::from_millis(PAUSE_POLL_MS)).await;
                 continue;
             }
 
-            let entry = transmission_guard
+            let mut entry = transmission_guard
                 .data_provider
                 .get_next_entry(number_plates)
                 .await?;
+            // Recorded entries keep the run they were produced in
+            if transmission_guard.data_provider.is_live() {
+                if let Some(run) = transmission_guard.run.as_ref() {
+                    entry = run.tag_entry(entry);
+                }
+            }
 
-            (transmission_guard.speed, entry)
+            let outputs = EntryOutputs::derive(&mut transmission_guard, &entry);
+            // Live entries are recorded with the distilled mass of the KPIs
+            if transmission_guard.data_provider.is_live() {
+                if let Some(distilled_mass) = outputs.kpis.distilled_mass {
+                    entry = Arc::new(ColumnEntry {
+                        distilled_mass,
+                        ..(*entry).clone()
+                    });
+                }
+            }
+            (
+                transmission_guard.speed_factor,
+                entry,
+                transmission_guard.recording_session,
+                transmission_guard.data_provider.next_delay(),
+                transmission_guard.data_provider.position(),
+                outputs,
+            )
         };
-        {
+        let spill = {
             let mut history_guard = app_state.history.lock().await;
-            history_guard.history.push(entry.clone());
+            history_guard.push(entry.clone());
+            history_guard.spill()
+        };
+        // A failed write loses part of the recording, not the acquisition
+        if let Some(spill) = spill {
+            if let Err(e) = spill.flush() {
+                report_recording_error(app_handle, "spill the history", e);
+            }
+        }
+        if let Some(session_id) = recording_session {
+            let session_service = app_state.session_service.lock().await;
+            if let Err(e) = session_service.append_entry(session_id, &entry) {
+                report_recording_error(app_handle, "store the entry", e);
+            }
+        }
+        if let Some(writer) = app_state.journal_writer.lock().await.as_mut() {
+            if let Err(e) = writer.append(&entry) {
+                report_recording_error(app_handle, "journal the entry", e);
+            }
         }
 
         let elapsed_time = start_time.elapsed();
         println!("Elapsed time: {:?}", elapsed_time);
         println!("\nSending: {:?}", entry);
+
+        // Playback follows the recorded spacing of the samples, live data the poll interval
+        let wait = match delay {
+            Some(delay) => {
+                if let (true, Some(next)) = (delay.wait < delay.recorded, position) {
+                    info!("Skipping a {} ms gap in the recording", delay.recorded);
+                    app_handle
+                        .emit(
+                            "playback_gap",
+                            TimeRange {
+                                from: entry.timestamp.min(next.timestamp),
+                                to: entry.timestamp.max(next.timestamp),
+                            },
+                        )
+                        .map_err(|e| e.to_string())?;
+                }
+                delay.wait
+            }
+            None => LIVE_INTERVAL_MS,
+        };
+
+        outputs.emit(app_handle, &app_state.alarm_journal)?;
         app_handle
             .emit("column_data", entry)
             .map_err(|e| e.to_string())?;
+        if let Some(position) = position {
+            app_handle
+                .emit("playback_position", position)
+                .map_err(|e| e.to_string())?;
+        }
 
-        tokio::time::sleep(Duration::from_millis(speed)).await;
+        tokio::time::sleep(Duration::from_secs_f64(wait as f64 / 1000.0 / speed_factor)).await;
     }
 }
 
+fn report_recording_error(app_handle: &AppHandle, action: &str, e: impl std::fmt::Display) {
+    let message = format!("Failed to {}: {}", action, e);
+    error!("{}", message);
+    let _ = app_handle.emit("recording_error", message);
+}
+
+/// Plays the loaded comparison, one frame with every run per reference sample.
+/// It has its own pause and cancel, so a single run can play next to it.
+#[tauri::command]
+#[specta::specta]
+pub async fn send_comparison_data(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+) -> Result<(), String> {
+    info!("Initializing send_comparison_data...");
+    {
+        let mut comparison_guard = app_state.comparison.lock().await;
+        let comparison = comparison_guard
+            .as_mut()
+            .ok_or(DataError::NoDataError.to_string())?;
+        if comparison.is_running() {
+            return Err(
+                DataError::CustomError("The comparison is already playing".into()).to_string(),
+            );
+        }
+        comparison.start();
+    }
+
+    loop {
+        let next = {
+            let mut comparison_guard = app_state.comparison.lock().await;
+            let Some(comparison) = comparison_guard.as_mut() else {
+                return Ok(());
+            };
+            if !comparison.is_running() {
+                return Ok(());
+            }
+            if comparison.is_paused() {
+                drop(comparison_guard);
+                tokio::time::sleep(Duration::from_millis(PAUSE_POLL_MS)).await;
+                continue;
+            }
+            let next = comparison.next_frame();
+            if next.is_none() {
+                comparison.reset();
+                comparison.stop();
+            }
+            next
+        };
+        let Some((frame, delay)) = next else {
+            info!("Comparison finished");
+            return Ok(());
+        };
+
+        app_handle
+            .emit("comparison_frame", frame)
+            .map_err(|e| e.to_string())?;
+
+        let speed_factor = app_state.transmission_state.lock().await.speed_factor;
+        tokio::time::sleep(Duration::from_secs_f64(
+            delay as f64 / 1000.0 / speed_factor,
+        ))
+        .await;
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn toggle_comparison_data(app_state: State<'_, AppState>) -> Result<String, String> {
+    info!("Toggling comparison data");
+    let mut comparison_guard = app_state.comparison.lock().await;
+    let comparison = comparison_guard
+        .as_mut()
+        .ok_or(DataError::NoDataError.to_string())?;
+    comparison.toggle();
+
+    if comparison.is_paused() {
+        Ok("paused".to_string())
+    } else {
+        Ok("running".to_string())
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_comparison_data(app_state: State<'_, AppState>) -> Result<(), String> {
+    info!("Canceling comparison data");
+    if let Some(comparison) = app_state.comparison.lock().await.as_mut() {
+        comparison.stop();
+        comparison.reset();
+    }
+    Ok(())
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn toggle_column_data(app_state: State<'_, AppState>) -> Result<String, String> {
@@ -85,28 +331,204 @@ pub async fn cancel_column_data(app_state: State<'_, AppState>) -> Result<(), St
     transmission_state.reset().await?;
 
     let mut history_guard = app_state.history.lock().await;
-    history_guard.history.clear();
+    history_guard.clear();
+
+    // The run ended normally, its journal is no longer needed
+    if let Some(writer) = app_state.journal_writer.lock().await.take() {
+        writer.finish()?;
+    }
 
     Ok(())
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn handle_skip(app_state: State<'_, AppState>, skip_count: i32) -> Result<(), String> {
+pub async fn handle_skip(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    skip_count: i32,
+) -> Result<(), String> {
     info!("Handling skip {} seconds", skip_count);
     let mut transmission_guard = app_state.transmission_state.lock().await;
-    transmission_guard.data_provider.skip(skip_count as i64)?;
+    transmission_guard
+        .data_provider
+        .skip_time(skip_count as i64 * 1000)?;
+    transmission_guard.playback_moved();
+    emit_position(&app_handle, transmission_guard.data_provider.position())?;
     Ok(())
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn set_speed(app_state: State<'_, AppState>, speed_factor: u32) -> Result<(), String> {
-    info!("Setting speed");
-    let mut transmission_state = app_state.transmission_state.lock().await;
-    let new_speed = 1000 / speed_factor;
+pub async fn seek_playback(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    target: SeekTarget,
+) -> Result<Option<PlaybackPosition>, String> {
+    info!("Seeking playback to {:?}", target);
+    let mut transmission_guard = app_state.transmission_state.lock().await;
+    transmission_guard.data_provider.seek(target)?;
+    transmission_guard.playback_moved();
+    emit_position(&app_handle, transmission_guard.data_provider.position())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_playback_range(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    range: Option<TimeRange>,
+) -> Result<Option<PlaybackPosition>, String> {
+    info!("Setting playback range {:?}", range);
+    let mut transmission_guard = app_state.transmission_state.lock().await;
+    transmission_guard.data_provider.set_range(range)?;
+    transmission_guard.playback_moved();
+    emit_position(&app_handle, transmission_guard.data_provider.position())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_looping(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    looping: bool,
+) -> Result<Option<PlaybackPosition>, String> {
+    info!("Setting looping {}", looping);
+    let mut transmission_guard = app_state.transmission_state.lock().await;
+    transmission_guard.data_provider.set_looping(looping);
+    emit_position(&app_handle, transmission_guard.data_provider.position())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_reverse(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    reverse: bool,
+) -> Result<Option<PlaybackPosition>, String> {
+    info!("Setting reverse playback {}", reverse);
+    let mut transmission_guard = app_state.transmission_state.lock().await;
+    transmission_guard.data_provider.set_reverse(reverse);
+    transmission_guard.set_reverse(reverse);
+    emit_position(&app_handle, transmission_guard.data_provider.position())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn step_playback(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    forward: bool,
+) -> Result<Option<PlaybackPosition>, String> {
+    info!(
+        "Stepping playback {}",
+        if forward { "forward" } else { "back" }
+    );
+    let mut transmission_guard = app_state.transmission_state.lock().await;
+    if transmission_guard.is_running && !transmission_guard.is_paused {
+        return Err("Pause playback before stepping".into());
+    }
+
+    // The stepped entry is shown but not added to the history of the run, and
+    // filtered on its own
+    if let Some(entry) = transmission_guard.data_provider.step(forward)? {
+        transmission_guard.pipeline.reset();
+        EntryOutputs::derive(&mut transmission_guard, &entry)
+            .emit(&app_handle, &app_state.alarm_journal)?;
+        app_handle
+            .emit("column_data", entry)
+            .map_err(|e| e.to_string())?;
+    }
+    emit_position(&app_handle, transmission_guard.data_provider.position())
+}
+
+/// Everything worked out from an entry besides the entry itself
+struct EntryOutputs {
+    faults: Vec<SensorFault>,
+    processed: Option<ProcessedEntry>,
+    kpis: KpiSnapshot,
+    cut_events: Vec<CutEvent>,
+    alarms: Vec<AlarmTransition>,
+    annotations: Vec<Annotation>,
+    /// Only transitions of live data go into the alarm journal
+    is_live: bool,
+}
+
+impl EntryOutputs {
+    fn derive(transmission_state: &mut TransmissionState, entry: &ColumnEntry) -> Self {
+        let kpis = transmission_state.kpis.update(entry);
+        Self {
+            faults: transmission_state.data_provider.take_faults(),
+            processed: transmission_state.pipeline.process_if_configured(entry),
+            cut_events: transmission_state.cuts.update(entry, &kpis),
+            alarms: transmission_state.alarms.evaluate(entry, &kpis),
+            annotations: transmission_state.annotations.advance(entry.timestamp),
+            is_live: transmission_state.data_provider.is_live(),
+            kpis,
+        }
+    }
+
+    fn emit(self, app_handle: &AppHandle, alarm_journal: &AlarmJournal) -> Result<(), String> {
+        if self.is_live {
+            alarm_journal.append(&self.alarms)?;
+        }
+        for transition in self.alarms {
+            app_handle
+                .emit("alarm", transition)
+                .map_err(|e| e.to_string())?;
+        }
+        for fault in self.faults {
+            app_handle
+                .emit("sensor_fault", fault)
+                .map_err(|e| e.to_string())?;
+        }
+        // Filtered values go out next to the raw entry, which stays what is recorded
+        if let Some(processed) = self.processed {
+            app_handle
+                .emit("processed_data", processed)
+                .map_err(|e| e.to_string())?;
+        }
+        app_handle
+            .emit("kpi", self.kpis)
+            .map_err(|e| e.to_string())?;
+        for annotation in self.annotations {
+            app_handle
+                .emit("annotation", annotation)
+                .map_err(|e| e.to_string())?;
+        }
+        for event in self.cut_events {
+            app_handle
+                .emit("cut_event", event)
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
 
-    transmission_state.set_speed(new_speed as u64);
+/// Keeps the scrubber of the UI in sync after playback moved
+fn emit_position(
+    app_handle: &AppHandle,
+    position: Option<PlaybackPosition>,
+) -> Result<Option<PlaybackPosition>, String> {
+    if let Some(position) = position {
+        app_handle
+            .emit("playback_position", position)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(position)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_speed(app_state: State<'_, AppState>, speed_factor: f64) -> Result<(), String> {
+    info!("Setting speed to {}x", speed_factor);
+    if !speed_factor.is_finite() || speed_factor <= 0.0 {
+        return Err(
+            DataError::CustomError(format!("Invalid speed factor {}", speed_factor)).to_string(),
+        );
+    }
+    let mut transmission_state = app_state.transmission_state.lock().await;
+    transmission_state.set_speed(speed_factor);
 
     Ok(())
 }
@@ -0,0 +1,27 @@
+use crate::errors::Result;
+use crate::history::types::HistoryBucket;
+use crate::AppState;
+use log::info;
+use tauri::State;
+
+/// Temperatures of the current run over a time range for charting. A resolution
+/// of zero, or none, returns every entry.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_history(
+    app_state: State<'_, AppState>,
+    from: Option<u64>,
+    to: Option<u64>,
+    resolution: Option<u64>,
+) -> Result<Vec<HistoryBucket>> {
+    info!(
+        "Querying history from {:?} to {:?} every {:?} ms",
+        from, to, resolution
+    );
+    let snapshot = app_state.history.lock().await.snapshot();
+    snapshot.query(
+        from.unwrap_or(0),
+        to.unwrap_or(u64::MAX),
+        resolution.unwrap_or(0),
+    )
+}
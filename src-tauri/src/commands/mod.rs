@@ -1,5 +1,12 @@
+pub mod alarms;
+pub mod comparison;
+pub mod cuts;
 pub mod data_handle;
 pub mod dialogs;
 pub mod emitter;
+pub mod history;
 pub mod modbus;
+pub mod recovery;
+pub mod runs;
+pub mod sessions;
 pub mod settings;
@@ -2,12 +2,13 @@ use std::sync::Arc;
 
 use crate::calculations::service::CalculationService;
 use crate::data_manager::factory::ProviderFactory;
+use crate::diagnostics::SensorMonitor;
 use crate::errors::{ModbusError, Result};
 use crate::settings::SettingsService;
 use crate::AppState;
 use crate::ModbusClient;
 use crate::ModbusService;
-use log::error;
+use log::{error, info};
 use tauri::State;
 use tokio::sync::Mutex;
 
@@ -18,9 +19,10 @@ pub async fn connect_modbus(app_state: State<'_, AppState>) -> Result<()> {
     let settings_path = app_state.settings_path.clone();
     let settings_service = SettingsService::new();
     let settings = settings_service.get_settings(&settings_path)?;
+    settings.modbus.validate()?;
 
     // Connect to Modbus
-    let modbus_client = ModbusClient::new();
+    let modbus_client = ModbusClient::from_settings(&settings.modbus);
     let modbus_service = ModbusService::new(modbus_client);
     let new_channel = modbus_service.connect(&settings.modbus).await?;
 
@@ -30,10 +32,18 @@ pub async fn connect_modbus(app_state: State<'_, AppState>) -> Result<()> {
     let provider = provider_factory.create_live_provider(
         Arc::new(calculation_service),
         Arc::new(Mutex::new(Some(new_channel.clone()))),
+        SensorMonitor::new(settings.diagnostics.clone()),
+        &settings.modbus,
     );
 
     let mut transmission_guard = app_state.transmission_state.lock().await;
     transmission_guard.set_data_provider(provider);
+    if let Some(run) = transmission_guard.run.as_ref() {
+        info!(
+            "Attaching run {} to the live data",
+            run.info().metadata.batch_id
+        );
+    }
     transmission_guard.start();
 
     Ok(())
@@ -55,5 +65,10 @@ pub async fn disconnect_modbus(app_state: State<'_, AppState>) -> Result<()> {
     // Clear the channel
     *channel_guard = None;
 
+    // The run ended normally, its journal is no longer needed
+    if let Some(writer) = app_state.journal_writer.lock().await.take() {
+        writer.finish()?;
+    }
+
     Ok(())
 }
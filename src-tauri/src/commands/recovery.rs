@@ -0,0 +1,89 @@
+use crate::data_manager::import_export::ExcelDataExporter;
+use crate::errors::{DataError, Result};
+use crate::journal::types::JournalSummary;
+use crate::settings::SettingsService;
+use crate::AppState;
+use log::info;
+use tauri::{AppHandle, Emitter, State};
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_unfinished_recordings(
+    app_state: State<'_, AppState>,
+) -> Result<Vec<JournalSummary>> {
+    info!("Looking for unfinished recordings");
+    let active = {
+        let journal_writer = app_state.journal_writer.lock().await;
+        journal_writer
+            .as_ref()
+            .map(|writer| writer.id().to_string())
+    };
+
+    // The journal of the recording in progress is not a crashed one
+    let journals = app_state.journal_service.list_unfinished()?;
+    Ok(journals
+        .into_iter()
+        .filter(|journal| Some(&journal.id) != active.as_ref())
+        .collect())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn recover_recording(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    journal_id: String,
+) -> Result<JournalSummary> {
+    info!("Recovering recording {}", journal_id);
+    let (summary, entries) = app_state.journal_service.read(&journal_id)?;
+
+    let spill = {
+        let mut history_guard = app_state.history.lock().await;
+        history_guard.replace(entries.clone());
+        history_guard.spill()
+    };
+    if let Some(spill) = spill {
+        spill.flush()?;
+    }
+
+    app_handle
+        .emit("number_plates", summary.number_plates)
+        .map_err(|e| DataError::CustomError(e.to_string()))?;
+    app_handle
+        .emit("session_data", entries)
+        .map_err(|e| DataError::CustomError(e.to_string()))?;
+
+    app_state.journal_service.discard(&journal_id)?;
+
+    Ok(summary)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn export_recording(
+    app_state: State<'_, AppState>,
+    journal_id: String,
+    path: String,
+) -> Result<()> {
+    info!("Exporting recording {} to {}", journal_id, path);
+    let (_, entries) = app_state.journal_service.read(&journal_id)?;
+
+    let time_zone = SettingsService::new()
+        .get_settings(&app_state.settings_path)
+        .unwrap_or_default()
+        .time
+        .time_zone()?;
+    let exporter = ExcelDataExporter::new().with_time_zone(time_zone);
+    exporter.export(&entries, &path)?;
+
+    app_state.journal_service.discard(&journal_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn discard_recording(app_state: State<'_, AppState>, journal_id: String) -> Result<()> {
+    info!("Discarding recording {}", journal_id);
+    app_state.journal_service.discard(&journal_id)
+}
@@ -0,0 +1,125 @@
+use crate::errors::{DataError, Result, RunError};
+use crate::kpi::KpiTracker;
+use crate::runs::types::{RunInfo, RunMetadata, RunPhase};
+use crate::runs::ActiveRun;
+use crate::sessions::service::now_millis;
+use crate::sessions::types::SessionSource;
+use crate::sessions::AnnotationTrack;
+use crate::settings::SettingsService;
+use crate::AppState;
+use log::info;
+use tauri::{AppHandle, Emitter, State};
+
+/// Starts a batch run. Live entries from now on are recorded into a session of
+/// their own and tagged with the run until it is ended.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_run(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    metadata: RunMetadata,
+    number_plates: u32,
+) -> Result<RunInfo> {
+    info!("Starting run {}", metadata.batch_id);
+    metadata.validate()?;
+    let settings = SettingsService::new().get_settings(&app_state.settings_path)?;
+
+    let run = {
+        let mut transmission_guard = app_state.transmission_state.lock().await;
+        if let Some(active) = transmission_guard.run.as_ref() {
+            return Err(RunError::AlreadyActive(active.info().metadata.batch_id.clone()).into());
+        }
+
+        let run = {
+            let session_service = app_state.session_service.lock().await;
+            let session = session_service.create_session(
+                metadata.batch_id.trim(),
+                SessionSource::Live,
+                number_plates,
+            )?;
+            session_service.create_run(session.id, &metadata, now_millis() as u64)?
+        };
+
+        let active = ActiveRun::new(run.clone());
+        transmission_guard.kpis = KpiTracker::new(run.kpi_settings(&settings.kpi));
+        transmission_guard.cuts.reset();
+        transmission_guard.run = Some(active);
+        transmission_guard.recording_session = transmission_guard.run_session();
+        if let Some(session_id) = transmission_guard.recording_session {
+            transmission_guard.annotations = AnnotationTrack::new(session_id, Vec::new());
+        }
+        run
+    };
+
+    // The history only ever holds one run
+    app_state.history.lock().await.clear();
+
+    app_handle
+        .emit("run", run.clone())
+        .map_err(|e| DataError::CustomError(e.to_string()))?;
+    Ok(run)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_run_phase(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    phase: RunPhase,
+) -> Result<RunInfo> {
+    info!("Setting run phase {}", phase.as_str());
+    let mut transmission_guard = app_state.transmission_state.lock().await;
+    let active = transmission_guard
+        .run
+        .as_mut()
+        .ok_or(RunError::NoActiveRun)?;
+
+    if let Some(change) = active.set_phase(phase, now_millis() as u64) {
+        let session_service = app_state.session_service.lock().await;
+        session_service.add_run_phase(active.info().id, &change)?;
+    }
+
+    let run = active.info().clone();
+    app_handle
+        .emit("run", run.clone())
+        .map_err(|e| DataError::CustomError(e.to_string()))?;
+    Ok(run)
+}
+
+/// Ends the active run. Live data keeps being recorded into its session, but
+/// is no longer tagged with it.
+#[tauri::command]
+#[specta::specta]
+pub async fn end_run(app_handle: AppHandle, app_state: State<'_, AppState>) -> Result<RunInfo> {
+    info!("Ending run");
+    let mut transmission_guard = app_state.transmission_state.lock().await;
+    let active = transmission_guard.run.take().ok_or(RunError::NoActiveRun)?;
+
+    let run = {
+        let session_service = app_state.session_service.lock().await;
+        session_service.end_run(active.info().id, now_millis() as u64)?
+    };
+
+    app_handle
+        .emit("run", run.clone())
+        .map_err(|e| DataError::CustomError(e.to_string()))?;
+    Ok(run)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn active_run(app_state: State<'_, AppState>) -> Result<Option<RunInfo>> {
+    let transmission_guard = app_state.transmission_state.lock().await;
+    Ok(transmission_guard
+        .run
+        .as_ref()
+        .map(|active| active.info().clone()))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_runs(app_state: State<'_, AppState>) -> Result<Vec<RunInfo>> {
+    info!("Listing runs");
+    let session_service = app_state.session_service.lock().await;
+    session_service.list_runs()
+}
@@ -0,0 +1,282 @@
+use crate::calculations::service::CalculationService;
+use crate::calculations::types::EquationParams;
+use crate::data_manager::factory::ProviderFactory;
+use crate::data_manager::recompute::recompute_entries;
+use crate::data_manager::types::ColumnEntry;
+use crate::errors::{DataError, Result, StorageError};
+use crate::sessions::types::{Annotation, SessionInfo};
+use crate::sessions::AnnotationTrack;
+use crate::settings::SettingsService;
+use crate::AppState;
+use log::info;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, State};
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_sessions(app_state: State<'_, AppState>) -> Result<Vec<SessionInfo>> {
+    info!("Listing sessions");
+    let session_service = app_state.session_service.lock().await;
+    session_service.list_sessions()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn open_session(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    session_id: i64,
+) -> Result<SessionInfo> {
+    info!("Opening session {}", session_id);
+    let (session, entries, annotations, switches) = {
+        let session_service = app_state.session_service.lock().await;
+        (
+            session_service.get_session(session_id)?,
+            session_service.load_entries(session_id)?,
+            session_service.list_annotations(session_id)?,
+            session_service.list_cut_switches(session_id)?,
+        )
+    };
+
+    // A running transmission keeps the annotations and cuts of its own session
+    {
+        let mut transmission_guard = app_state.transmission_state.lock().await;
+        if !transmission_guard.is_running {
+            transmission_guard.annotations = AnnotationTrack::new(session_id, annotations);
+            transmission_guard.cuts.set_switches(switches);
+        }
+    }
+
+    // Load the stored entries as the current history so they can be browsed and exported
+    let spill = {
+        let mut history_guard = app_state.history.lock().await;
+        history_guard.replace(entries.clone());
+        history_guard.spill()
+    };
+    if let Some(spill) = spill {
+        spill.flush()?;
+    }
+
+    app_handle
+        .emit("number_plates", session.number_plates)
+        .map_err(|e| DataError::CustomError(e.to_string()))?;
+    app_handle
+        .emit("session_data", entries)
+        .map_err(|e| DataError::CustomError(e.to_string()))?;
+
+    Ok(session)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn replay_session(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    session_id: i64,
+) -> Result<SessionInfo> {
+    info!("Replaying session {}", session_id);
+    let (session, entries, annotations, switches) = {
+        let session_service = app_state.session_service.lock().await;
+        (
+            session_service.get_session(session_id)?,
+            session_service.load_entries(session_id)?,
+            session_service.list_annotations(session_id)?,
+            session_service.list_cut_switches(session_id)?,
+        )
+    };
+
+    if entries.is_empty() {
+        return Err(DataError::EmptyDataError.into());
+    }
+
+    let provider_factory = ProviderFactory::new();
+    let provider = provider_factory.create_playback_provider(entries, 0);
+
+    {
+        let mut transmission_guard = app_state.transmission_state.lock().await;
+        transmission_guard.set_data_provider(provider);
+        transmission_guard.annotations = AnnotationTrack::new(session_id, annotations);
+        transmission_guard.cuts.set_switches(switches);
+    }
+
+    app_handle
+        .emit("number_plates", session.number_plates)
+        .map_err(|e| DataError::CustomError(e.to_string()))?;
+
+    Ok(session)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn default_equation_params() -> Result<EquationParams> {
+    Ok(EquationParams::default())
+}
+
+/// What-if analysis of a stored run. The session itself is left untouched, the
+/// recomputed entries become the current history so they can be charted and exported.
+#[tauri::command]
+#[specta::specta]
+pub async fn recompute_session(
+    app_state: State<'_, AppState>,
+    session_id: i64,
+    params: EquationParams,
+    use_recorded_pressure: Option<bool>,
+) -> Result<Vec<Arc<ColumnEntry>>> {
+    info!("Recomputing session {}", session_id);
+    if !params.p.is_finite() || params.p <= 0.0 {
+        return Err(DataError::CustomError(format!("Invalid pressure {}", params.p)).into());
+    }
+    // The history belongs to the transmission while it runs
+    if app_state.transmission_state.lock().await.is_running {
+        return Err(DataError::CustomError(
+            "Stop the transmission before recomputing a session".into(),
+        )
+        .into());
+    }
+
+    let kpi_settings = SettingsService::new()
+        .get_settings(&app_state.settings_path)
+        .unwrap_or_default()
+        .kpi;
+    let (entries, initial_charge) = {
+        let session_service = app_state.session_service.lock().await;
+        let entries = session_service.load_entries(session_id)?;
+        // A run recorded with the session balances against its own charge
+        let initial_charge = match entries.iter().find_map(|entry| entry.run.as_ref()) {
+            Some(tag) => session_service.get_run(tag.run_id)?.metadata.charge_mass,
+            None => kpi_settings.initial_charge,
+        };
+        (entries, initial_charge)
+    };
+    if entries.is_empty() {
+        return Err(DataError::EmptyDataError.into());
+    }
+
+    let entries = tokio::task::spawn_blocking(move || {
+        let calculation_service = CalculationService::with_params(params);
+        recompute_entries(
+            &entries,
+            &calculation_service,
+            initial_charge,
+            use_recorded_pressure.unwrap_or(true),
+        )
+    })
+    .await
+    .map_err(|e| DataError::CustomError(e.to_string()))?;
+
+    let spill = {
+        let mut history_guard = app_state.history.lock().await;
+        history_guard.replace(entries.clone());
+        history_guard.spill()
+    };
+    if let Some(spill) = spill {
+        spill.flush()?;
+    }
+
+    Ok(entries)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn rename_session(
+    app_state: State<'_, AppState>,
+    session_id: i64,
+    name: String,
+) -> Result<SessionInfo> {
+    info!("Renaming session {} to {}", session_id, name);
+    let session_service = app_state.session_service.lock().await;
+    session_service.rename_session(session_id, name.trim())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_session(app_state: State<'_, AppState>, session_id: i64) -> Result<()> {
+    info!("Deleting session {}", session_id);
+    {
+        let mut transmission_guard = app_state.transmission_state.lock().await;
+        if transmission_guard.recording_session == Some(session_id) {
+            transmission_guard.recording_session = None;
+        }
+    }
+
+    let session_service = app_state.session_service.lock().await;
+    session_service.delete_session(session_id)
+}
+
+/// Attaches a note to a moment of a session. Without a session id the note goes
+/// to the session being recorded or played, without a timestamp to its newest entry.
+#[tauri::command]
+#[specta::specta]
+pub async fn add_annotation(
+    app_state: State<'_, AppState>,
+    session_id: Option<i64>,
+    timestamp: Option<u64>,
+    text: String,
+) -> Result<Annotation> {
+    let mut transmission_guard = app_state.transmission_state.lock().await;
+    let session_id = session_id
+        .or(transmission_guard.annotations.session_id())
+        .ok_or(StorageError::NoSession)?;
+    let timestamp = match timestamp {
+        Some(timestamp) => timestamp,
+        None if transmission_guard.annotations.session_id() == Some(session_id) => {
+            transmission_guard
+                .annotations
+                .last_timestamp()
+                .ok_or(DataError::NoDataError)?
+        }
+        None => return Err(DataError::NoDataError.into()),
+    };
+
+    let session_service = app_state.session_service.lock().await;
+    let annotation = session_service.add_annotation(session_id, timestamp, &text)?;
+    transmission_guard.annotations.upsert(annotation.clone());
+    Ok(annotation)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn update_annotation(
+    app_state: State<'_, AppState>,
+    annotation_id: i64,
+    timestamp: Option<u64>,
+    text: String,
+) -> Result<Annotation> {
+    info!("Updating annotation {}", annotation_id);
+    let mut transmission_guard = app_state.transmission_state.lock().await;
+    let session_service = app_state.session_service.lock().await;
+    let annotation = session_service.update_annotation(annotation_id, timestamp, &text)?;
+    transmission_guard.annotations.upsert(annotation.clone());
+    Ok(annotation)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_annotation(app_state: State<'_, AppState>, annotation_id: i64) -> Result<()> {
+    info!("Deleting annotation {}", annotation_id);
+    let mut transmission_guard = app_state.transmission_state.lock().await;
+    let session_service = app_state.session_service.lock().await;
+    session_service.delete_annotation(annotation_id)?;
+    transmission_guard.annotations.remove(annotation_id);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_annotations(
+    app_state: State<'_, AppState>,
+    session_id: Option<i64>,
+) -> Result<Vec<Annotation>> {
+    let session_id = match session_id {
+        Some(session_id) => session_id,
+        None => app_state
+            .transmission_state
+            .lock()
+            .await
+            .annotations
+            .session_id()
+            .ok_or(StorageError::NoSession)?,
+    };
+    let session_service = app_state.session_service.lock().await;
+    session_service.list_annotations(session_id)
+}
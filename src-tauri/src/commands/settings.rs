@@ -1,4 +1,5 @@
-use crate::errors::{Result, SettingsError};
+use crate::data_manager::import_export::types::{ColumnMapping, NamedColumnMapping};
+use crate::errors::{ImportError, Result, SettingsError};
 use crate::settings::{Settings, SettingsService};
 use crate::AppState;
 use log::debug;
@@ -11,8 +12,22 @@ pub async fn save_settings(app_state: State<'_, AppState>, settings: Settings) -
     let settings_service = SettingsService::new();
     let app_data_dir = app_state.settings_path.clone();
 
+    settings.time.time_zone()?;
+    settings.modbus.validate()?;
+    settings.processing.validate()?;
+    settings.kpi.validate()?;
+    settings.cuts.validate()?;
+    settings.alarms.validate()?;
     let new_setting = settings_service.update_settings(&app_data_dir, &settings)?;
 
+    // A connected provider reads with the new settings from its next poll on
+    app_state
+        .transmission_state
+        .lock()
+        .await
+        .data_provider
+        .apply_settings(&new_setting.modbus);
+
     Ok(new_setting)
 }
 
@@ -49,3 +64,59 @@ pub async fn available_ports() -> Result<Vec<String>> {
         Err(err) => Err(SettingsError::GetUsbDevicesError(err.to_string()).into()),
     }
 }
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_import_mappings(
+    app_state: State<'_, AppState>,
+) -> Result<Vec<NamedColumnMapping>> {
+    debug!("Listing import mappings");
+    let settings_service = SettingsService::new();
+    let settings = settings_service.get_settings(&app_state.settings_path)?;
+
+    Ok(settings.import_mappings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn save_import_mapping(
+    app_state: State<'_, AppState>,
+    name: String,
+    mapping: ColumnMapping,
+) -> Result<Vec<NamedColumnMapping>> {
+    debug!("Saving import mapping {}", name);
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(ImportError::InvalidMapping("Mapping name is required".into()).into());
+    }
+
+    let settings_service = SettingsService::new();
+    let mut settings = settings_service.get_settings(&app_state.settings_path)?;
+
+    // Saving under an existing name replaces that mapping
+    match settings.import_mappings.iter_mut().find(|m| m.name == name) {
+        Some(existing) => existing.mapping = mapping,
+        None => settings
+            .import_mappings
+            .push(NamedColumnMapping { name, mapping }),
+    }
+
+    let settings = settings_service.update_settings(&app_state.settings_path, &settings)?;
+    Ok(settings.import_mappings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_import_mapping(
+    app_state: State<'_, AppState>,
+    name: String,
+) -> Result<Vec<NamedColumnMapping>> {
+    debug!("Deleting import mapping {}", name);
+    let settings_service = SettingsService::new();
+    let mut settings = settings_service.get_settings(&app_state.settings_path)?;
+
+    settings.import_mappings.retain(|m| m.name != name);
+
+    let settings = settings_service.update_settings(&app_state.settings_path, &settings)?;
+    Ok(settings.import_mappings)
+}
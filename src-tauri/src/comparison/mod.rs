@@ -0,0 +1,5 @@
+pub mod service;
+pub mod tests;
+pub mod types;
+
+pub use service::Comparison;
@@ -0,0 +1,275 @@
+use super::types::{Alignment, ComparisonFrame, ComparisonMetrics, SeriesInfo, SeriesMetrics};
+use crate::data_manager::types::{typical_interval, ColumnEntry};
+use crate::errors::{DataError, Result};
+use crate::math::interpolate;
+use crate::sessions::types::SessionInfo;
+use log::info;
+use std::sync::Arc;
+
+/// Number of shifts tried in each direction when lining up purity curves
+const PURITY_SHIFT_STEPS: i64 = 200;
+/// Frame spacing of a reference run without a usable sample interval
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+/// Share of the reference samples that must overlap for a shift to count
+const MIN_OVERLAP_RATIO: f64 = 0.25;
+
+struct Series {
+    info: SeriesInfo,
+    entries: Vec<Arc<ColumnEntry>>,
+}
+
+impl Series {
+    fn start(&self) -> i64 {
+        self.elapsed(&self.entries[0])
+    }
+
+    fn end(&self) -> i64 {
+        self.elapsed(&self.entries[self.entries.len() - 1])
+    }
+
+    fn elapsed(&self, entry: &ColumnEntry) -> i64 {
+        entry.timestamp as i64 - self.info.aligned_at as i64
+    }
+
+    /// The last sample at or before `elapsed`
+    fn entry_at(&self, elapsed: i64) -> Option<Arc<ColumnEntry>> {
+        if elapsed < self.start() || elapsed > self.end() {
+            return None;
+        }
+        let index = self
+            .entries
+            .partition_point(|entry| self.elapsed(entry) <= elapsed);
+        self.entries.get(index.checked_sub(1)?).cloned()
+    }
+
+    /// Linear interpolation of `value` at `elapsed`
+    fn value_at<F>(&self, elapsed: i64, value: F) -> Option<f64>
+    where
+        F: Fn(&ColumnEntry) -> Option<f64>,
+    {
+        let index = self
+            .entries
+            .partition_point(|entry| self.elapsed(entry) < elapsed);
+        let after = self.entries.get(index)?;
+        if self.elapsed(after) == elapsed {
+            return value(after);
+        }
+        let before = self.entries.get(index.checked_sub(1)?)?;
+
+        Some(interpolate(
+            self.elapsed(before) as f64,
+            value(before)?,
+            self.elapsed(after) as f64,
+            value(after)?,
+            elapsed as f64,
+        ))
+    }
+}
+
+/// Several stored runs lined up on a common elapsed time. The first run is the
+/// reference, its samples set the clock of the frames.
+pub struct Comparison {
+    series: Vec<Series>,
+    frame_index: usize,
+    /// Typical spacing of the reference samples, used where the recording has none
+    interval: u64,
+    is_running: bool,
+    is_paused: bool,
+}
+
+impl Comparison {
+    pub fn new(
+        runs: Vec<(SessionInfo, Vec<Arc<ColumnEntry>>)>,
+        alignment: Alignment,
+    ) -> Result<Self> {
+        if runs.len() < 2 {
+            return Err(DataError::CustomError(
+                "At least two sessions are needed to compare".into(),
+            )
+            .into());
+        }
+
+        let mut series = Vec::with_capacity(runs.len());
+        for (session, entries) in runs {
+            if entries.is_empty() {
+                return Err(DataError::EmptyDataError.into());
+            }
+
+            let aligned_at = alignment_point(&entries, alignment).ok_or_else(|| {
+                DataError::CustomError(format!(
+                    "{} never reaches the alignment temperature",
+                    session.name
+                ))
+            })?;
+
+            series.push(Series {
+                info: SeriesInfo {
+                    session_id: session.id,
+                    name: session.name,
+                    number_plates: session.number_plates,
+                    aligned_at,
+                    entry_count: entries.len() as u32,
+                },
+                entries,
+            });
+        }
+        info!("Comparing {} runs aligned by {:?}", series.len(), alignment);
+
+        let interval = typical_interval(&series[0].entries)
+            .filter(|&interval| interval > 0)
+            .unwrap_or(DEFAULT_INTERVAL_MS);
+        Ok(Self {
+            series,
+            frame_index: 0,
+            interval,
+            is_running: false,
+            is_paused: false,
+        })
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.is_running
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    pub fn start(&mut self) {
+        self.is_running = true;
+        self.is_paused = false;
+    }
+
+    pub fn stop(&mut self) {
+        self.is_running = false;
+        self.is_paused = false;
+    }
+
+    pub fn toggle(&mut self) {
+        self.is_paused = !self.is_paused;
+    }
+
+    pub fn series(&self) -> Vec<SeriesInfo> {
+        self.series.iter().map(|s| s.info.clone()).collect()
+    }
+
+    /// The next frame and the recorded time until the one after it
+    pub fn next_frame(&mut self) -> Option<(ComparisonFrame, u64)> {
+        let reference = &self.series[0];
+        let entry = reference.entries.get(self.frame_index)?;
+        let elapsed = reference.elapsed(entry);
+
+        let frame = ComparisonFrame {
+            elapsed,
+            entries: self.series.iter().map(|s| s.entry_at(elapsed)).collect(),
+        };
+        let delay = reference
+            .entries
+            .get(self.frame_index + 1)
+            .map(|next| next.timestamp.saturating_sub(entry.timestamp))
+            .filter(|&delay| delay > 0)
+            .unwrap_or(self.interval);
+
+        self.frame_index += 1;
+        Some((frame, delay))
+    }
+
+    pub fn reset(&mut self) {
+        self.frame_index = 0;
+    }
+
+    pub fn metrics(&self) -> ComparisonMetrics {
+        let reference = &self.series[0];
+
+        let series = self
+            .series
+            .iter()
+            .skip(1)
+            .map(|other| {
+                let plates = reference.info.number_plates.min(other.info.number_plates) as usize;
+                let rms_deviation = (0..plates)
+                    .map(|plate| {
+                        rms_difference(reference, other, 0, |entry| {
+                            entry.temperatures.get(plate).copied()
+                        })
+                        .map(|(rms, _)| rms)
+                    })
+                    .collect();
+
+                SeriesMetrics {
+                    session_id: other.info.session_id,
+                    name: other.info.name.clone(),
+                    rms_deviation,
+                    purity_offset: purity_offset(reference, other),
+                    overlap: (reference.end().min(other.end())
+                        - reference.start().max(other.start()))
+                    .max(0),
+                }
+            })
+            .collect();
+
+        ComparisonMetrics {
+            reference: reference.info.clone(),
+            series,
+        }
+    }
+}
+
+fn alignment_point(entries: &[Arc<ColumnEntry>], alignment: Alignment) -> Option<u64> {
+    match alignment {
+        Alignment::Start => entries.first().map(|entry| entry.timestamp),
+        Alignment::TemperatureCrossing { plate, threshold } => entries
+            .iter()
+            .find(|entry| {
+                let temperature = match plate {
+                    Some(plate) => entry.temperatures.get(plate as usize),
+                    None => entry.temperatures.last(),
+                };
+                temperature.is_some_and(|&t| t >= threshold)
+            })
+            .map(|entry| entry.timestamp),
+    }
+}
+
+/// RMS of `value` of `other`, shifted by `shift`, minus the reference at every
+/// reference sample both runs cover, with the number of samples compared
+fn rms_difference<F>(
+    reference: &Series,
+    other: &Series,
+    shift: i64,
+    value: F,
+) -> Option<(f64, usize)>
+where
+    F: Fn(&ColumnEntry) -> Option<f64>,
+{
+    let mut sum = 0.0;
+    let mut count = 0;
+    for entry in &reference.entries {
+        let elapsed = reference.elapsed(entry);
+        if let (Some(a), Some(b)) = (value(entry), other.value_at(elapsed + shift, &value)) {
+            sum += (a - b).powi(2);
+            count += 1;
+        }
+    }
+
+    (count > 0).then(|| ((sum / count as f64).sqrt(), count))
+}
+
+/// Searches the shift of `other` that minimizes the RMS difference of the
+/// distillate purity, the vapour composition of the top plate
+fn purity_offset(reference: &Series, other: &Series) -> Option<i64> {
+    let purity = |entry: &ColumnEntry| entry.compositions.last().and_then(|c| c.y_1);
+    let span = reference.end() - reference.start();
+    let step = (span / 2 / PURITY_SHIFT_STEPS).max(1);
+    let min_samples = (reference.entries.len() as f64 * MIN_OVERLAP_RATIO).ceil() as usize;
+
+    (-PURITY_SHIFT_STEPS..=PURITY_SHIFT_STEPS)
+        .map(|i| i * step)
+        .filter_map(|shift| {
+            rms_difference(reference, other, shift, purity)
+                .filter(|&(_, count)| count >= min_samples.max(1))
+                .map(|(rms, _)| (shift, rms))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.abs().cmp(&b.0.abs())))
+        .map(|(shift, _)| shift)
+}
@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests {
+    use crate::calculations::types::CompositionResult;
+    use crate::comparison::types::Alignment;
+    use crate::comparison::Comparison;
+    use crate::data_manager::types::ColumnEntry;
+    use crate::sessions::types::{SessionInfo, SessionSource};
+    use std::sync::Arc;
+
+    // The top plate heats by 5 °C a second from 20 °C, starting after `delay` seconds
+    fn run(id: i64, delay: u64) -> (SessionInfo, Vec<Arc<ColumnEntry>>) {
+        let entries: Vec<Arc<ColumnEntry>> = (0..=20u64)
+            .map(|second| {
+                let temperature = 20.0 + 5.0 * second.saturating_sub(delay) as f64;
+                Arc::new(ColumnEntry {
+                    timestamp: second * 1000,
+                    temperatures: vec![temperature + 10.0, temperature],
+                    compositions: vec![CompositionResult {
+                        x_1: None,
+                        y_1: Some(temperature / 100.0),
+                    }],
+                    ..ColumnEntry::default()
+                })
+            })
+            .collect();
+
+        let session = SessionInfo {
+            id,
+            name: format!("Run {}", id),
+            source: SessionSource::Imported,
+            number_plates: 2,
+            created_at: 0,
+            started_at: None,
+            ended_at: None,
+            entry_count: entries.len() as u32,
+            run_id: None,
+            batch_id: None,
+        };
+        (session, entries)
+    }
+
+    #[test]
+    fn test_crossing_alignment_lines_runs_up() {
+        let alignment = Alignment::TemperatureCrossing {
+            plate: None,
+            threshold: 40.0,
+        };
+        let mut comparison = Comparison::new(vec![run(1, 0), run(2, 3)], alignment).unwrap();
+
+        let series = comparison.series();
+        assert_eq!((series[0].aligned_at, series[1].aligned_at), (4000, 7000));
+
+        let (frame, delay) = comparison.next_frame().unwrap();
+        assert_eq!((frame.elapsed, delay), (-4000, 1000));
+        assert_eq!(frame.entries[1].as_ref().unwrap().timestamp, 3000);
+        // The last frame waits one sample interval like the others
+        let last = std::iter::from_fn(|| comparison.next_frame())
+            .last()
+            .unwrap();
+        assert_eq!(last.1, 1000);
+
+        let metrics = comparison.metrics();
+        assert_eq!(metrics.series[0].rms_deviation, vec![Some(0.0), Some(0.0)]);
+        assert_eq!(metrics.series[0].overlap, 17_000);
+    }
+
+    #[test]
+    fn test_purity_offset_and_missing_crossing() {
+        let comparison = Comparison::new(vec![run(1, 0), run(2, 3)], Alignment::Start).unwrap();
+        let metrics = comparison.metrics();
+        assert_eq!(metrics.series[0].purity_offset, Some(3000));
+        assert!(metrics.series[0].rms_deviation[1].unwrap() > 0.0);
+
+        let alignment = Alignment::TemperatureCrossing {
+            plate: Some(1),
+            threshold: 500.0,
+        };
+        assert!(Comparison::new(vec![run(1, 0), run(2, 3)], alignment).is_err());
+        assert!(Comparison::new(vec![run(1, 0)], Alignment::Start).is_err());
+    }
+}
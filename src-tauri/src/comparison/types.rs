@@ -0,0 +1,58 @@
+use crate::data_manager::types::ColumnEntry;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::sync::Arc;
+
+/// How the runs of a comparison are lined up. A crossing aligns every run on the
+/// first sample where `plate` reaches `threshold`, the top plate when not given.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", tag = "mode")]
+pub enum Alignment {
+    Start,
+    #[serde(rename_all = "camelCase")]
+    TemperatureCrossing {
+        plate: Option<u32>,
+        threshold: f64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesInfo {
+    pub session_id: i64,
+    pub name: String,
+    pub number_plates: u32,
+    /// Timestamp of the alignment point, elapsed times are measured from it
+    pub aligned_at: u64,
+    pub entry_count: u32,
+}
+
+/// One step of a comparison. `entries` follows the order of the series, with
+/// `None` for runs that have not started or already ended at `elapsed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonFrame {
+    pub elapsed: i64,
+    pub entries: Vec<Option<Arc<ColumnEntry>>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesMetrics {
+    pub session_id: i64,
+    pub name: String,
+    /// RMS temperature difference to the reference per plate, `None` without overlap
+    pub rms_deviation: Vec<Option<f64>>,
+    /// Shift in milliseconds that best lines the top plate purity up with the
+    /// reference. Positive when this run reaches the same purity later.
+    pub purity_offset: Option<i64>,
+    pub overlap: i64,
+}
+
+/// Differences of every run to the reference, which is the first series.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonMetrics {
+    pub reference: SeriesInfo,
+    pub series: Vec<SeriesMetrics>,
+}
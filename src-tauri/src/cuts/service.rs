@@ -0,0 +1,240 @@
+use super::types::{CutDefinition, CutEvent, CutStatus, CutSwitch, CutVariable, CutYield};
+use crate::data_manager::types::ColumnEntry;
+use crate::kpi::types::KpiSnapshot;
+use crate::settings::types::CutSettings;
+use log::info;
+
+/// A cut that was entered, with what was collected in it so far
+#[derive(Debug, Clone)]
+struct CollectedCut {
+    start: u64,
+    start_mass: Option<f64>,
+    composition_sum: f64,
+    composition_count: u32,
+}
+
+/// Follows the cuts of a batch. Switches are recommended from the entries,
+/// but only made when the operator confirms them. Playback that moves back
+/// rewinds the collected cuts, the switches are kept and made again when
+/// playback passes them.
+#[derive(Debug, Clone, Default)]
+pub struct CutTracker {
+    definitions: Vec<CutDefinition>,
+    /// Every switch of the batch, the one into cut `i + 1` at index `i`
+    switches: Vec<CutSwitch>,
+    collected: Vec<CollectedCut>,
+    pending_since: Option<u64>,
+    recommended: bool,
+    /// Timestamp and distilled mass of the newest entry
+    last: Option<(u64, Option<f64>)>,
+    /// Playback runs back in time, so timestamps fall from one entry to the next
+    reverse: bool,
+}
+
+impl CutTracker {
+    pub fn new(settings: &CutSettings) -> Self {
+        Self {
+            definitions: settings.cuts.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Starts a new batch, forgetting its switches
+    pub fn reset(&mut self) {
+        self.set_switches(Vec::new());
+    }
+
+    /// Takes over the switches stored with a session, to be made again in playback
+    pub fn set_switches(&mut self, switches: Vec<CutSwitch>) {
+        *self = Self {
+            definitions: std::mem::take(&mut self.definitions),
+            switches,
+            reverse: self.reverse,
+            ..Self::default()
+        };
+    }
+
+    pub fn set_reverse(&mut self, reverse: bool) {
+        if self.reverse != reverse {
+            self.reverse = reverse;
+            self.recommended = false;
+            self.pending_since = None;
+        }
+    }
+
+    pub fn switches(&self) -> &[CutSwitch] {
+        &self.switches
+    }
+
+    /// Feeds the next entry with its KPIs and returns the recommendations it changed
+    pub fn update(&mut self, entry: &ColumnEntry, kpi: &KpiSnapshot) -> Vec<CutEvent> {
+        let mut events = Vec::new();
+        if self.definitions.is_empty() {
+            return events;
+        }
+        if let Some((last, _)) = self.last {
+            if entry.timestamp < last {
+                self.collected.retain(|cut| cut.start <= entry.timestamp);
+            }
+            // Against the direction of playback, so it jumped or wrapped around
+            let jumped = match self.reverse {
+                true => entry.timestamp > last,
+                false => entry.timestamp < last,
+            };
+            if jumped {
+                self.recommended = false;
+                self.pending_since = None;
+            }
+        }
+        self.last = Some((entry.timestamp, kpi.distilled_mass));
+
+        if self.collected.is_empty() {
+            self.collected.push(CollectedCut {
+                start: entry.timestamp,
+                start_mass: kpi.distilled_mass,
+                composition_sum: 0.0,
+                composition_count: 0,
+            });
+        }
+        // Switches made earlier are made again once playback reaches them
+        while let Some(switch) = self
+            .switches
+            .get(self.collected.len() - 1)
+            .filter(|switch| switch.timestamp <= entry.timestamp)
+        {
+            if self.definitions.get(self.collected.len()).is_none() {
+                break;
+            }
+            events.push(switch.clone().into());
+            self.collected.push(CollectedCut {
+                start: switch.timestamp,
+                start_mass: kpi.distilled_mass,
+                composition_sum: 0.0,
+                composition_count: 0,
+            });
+            self.recommended = false;
+            self.pending_since = None;
+        }
+        let top_composition = entry.compositions.last().and_then(|c| c.y_1);
+        if let (Some(cut), Some(composition)) = (self.collected.last_mut(), top_composition) {
+            cut.composition_sum += composition;
+            cut.composition_count += 1;
+        }
+
+        let current = self.collected.len() - 1;
+        let (Some(cut), Some(next)) = (
+            self.definitions.get(current),
+            self.definitions.get(current + 1),
+        ) else {
+            return events;
+        };
+        let Some(condition) = cut.switch_when.as_ref() else {
+            return events;
+        };
+        let value = match condition.variable {
+            CutVariable::TopComposition => top_composition,
+            CutVariable::TopTemperature => entry.temperatures.last().copied(),
+        };
+        let Some(value) = value else {
+            return events;
+        };
+
+        if self.recommended {
+            if !condition.still_met(value) {
+                self.recommended = false;
+                self.pending_since = None;
+                events.push(CutEvent::RecommendationWithdrawn {
+                    timestamp: entry.timestamp,
+                    cut: cut.name.clone(),
+                });
+            }
+        } else if condition.is_met(value) {
+            let since = *self.pending_since.get_or_insert(entry.timestamp);
+            if entry.timestamp.abs_diff(since) >= condition.hold_ms {
+                self.recommended = true;
+                info!("Recommending to switch from {} at {}", cut.name, value);
+                events.push(CutEvent::SwitchRecommended {
+                    timestamp: entry.timestamp,
+                    from: cut.name.clone(),
+                    to: next.name.clone(),
+                    value,
+                });
+            }
+        } else {
+            self.pending_since = None;
+        }
+
+        events
+    }
+
+    /// Switches to the next cut at the newest entry. `None` before the first
+    /// entry and while collecting the last cut.
+    pub fn switch(&mut self) -> Option<CutEvent> {
+        let (timestamp, distilled_mass) = self.last?;
+        let current = self.collected.len().checked_sub(1)?;
+        let next = self.definitions.get(current + 1)?;
+
+        info!("Switching to cut {}", next.name);
+        let switch = CutSwitch {
+            timestamp,
+            from: self.definitions[current].name.clone(),
+            to: next.name.clone(),
+            recommended: self.recommended,
+        };
+        // A switch made after moving back replaces the ones that came later
+        self.switches.truncate(current);
+        self.switches.push(switch.clone());
+        self.collected.push(CollectedCut {
+            start: timestamp,
+            start_mass: distilled_mass,
+            composition_sum: 0.0,
+            composition_count: 0,
+        });
+        self.recommended = false;
+        self.pending_since = None;
+        Some(switch.into())
+    }
+
+    pub fn yields(&self) -> Vec<CutYield> {
+        let last_mass = self.last.and_then(|(_, mass)| mass);
+        let first_mass = self.collected.first().and_then(|cut| cut.start_mass);
+        let total = last_mass.zip(first_mass).map(|(last, first)| last - first);
+
+        self.collected
+            .iter()
+            .enumerate()
+            .map(|(index, cut)| {
+                let next = self.collected.get(index + 1);
+                let end_mass = match next {
+                    Some(next) => next.start_mass,
+                    None => last_mass,
+                };
+                let mass = end_mass.zip(cut.start_mass).map(|(end, start)| end - start);
+                CutYield {
+                    name: self.definitions[index].name.clone(),
+                    start: cut.start,
+                    end: next.map(|next| next.start),
+                    mass,
+                    share: mass
+                        .zip(total.filter(|&total| total > 0.0))
+                        .map(|(mass, total)| mass / total),
+                    mean_composition: (cut.composition_count > 0)
+                        .then(|| cut.composition_sum / cut.composition_count as f64),
+                }
+            })
+            .collect()
+    }
+
+    pub fn status(&self) -> Option<CutStatus> {
+        let current = self.collected.len().max(1) - 1;
+        Some(CutStatus {
+            current: self.definitions.get(current)?.name.clone(),
+            next: self
+                .definitions
+                .get(current + 1)
+                .map(|cut| cut.name.clone()),
+            recommended: self.recommended,
+            yields: self.yields(),
+        })
+    }
+}
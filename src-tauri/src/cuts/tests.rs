@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod tests {
+    use crate::calculations::types::CompositionResult;
+    use crate::cuts::types::{CutDefinition, CutDirection, CutEvent, CutVariable, SwitchCondition};
+    use crate::cuts::CutTracker;
+    use crate::data_manager::types::ColumnEntry;
+    use crate::kpi::types::KpiSnapshot;
+    use crate::settings::types::CutSettings;
+
+    fn settings() -> CutSettings {
+        CutSettings {
+            cuts: vec![
+                CutDefinition {
+                    name: "Heads".to_string(),
+                    switch_when: Some(SwitchCondition {
+                        variable: CutVariable::TopTemperature,
+                        direction: CutDirection::Above,
+                        threshold: 78.0,
+                        hysteresis: 0.5,
+                        hold_ms: 2000,
+                    }),
+                },
+                CutDefinition {
+                    name: "Tails".to_string(),
+                    switch_when: None,
+                },
+            ],
+        }
+    }
+
+    // One entry a second, the distilled mass grows by one each second
+    fn feed(tracker: &mut CutTracker, second: u64, top: f64) -> Vec<CutEvent> {
+        let entry = ColumnEntry {
+            timestamp: second * 1000,
+            temperatures: vec![90.0, top],
+            compositions: vec![CompositionResult {
+                x_1: None,
+                y_1: Some(0.9),
+            }],
+            ..ColumnEntry::default()
+        };
+        let kpi = KpiSnapshot {
+            distilled_mass: Some(second as f64),
+            ..KpiSnapshot::default()
+        };
+        tracker.update(&entry, &kpi)
+    }
+
+    #[test]
+    fn test_recommendation_hysteresis_and_yields() {
+        let mut tracker = CutTracker::new(&settings());
+        assert!(feed(&mut tracker, 0, 77.0).is_empty());
+        // Too short above the threshold to count
+        assert!(feed(&mut tracker, 1, 78.5).is_empty());
+        assert!(feed(&mut tracker, 2, 77.9).is_empty());
+
+        assert!(feed(&mut tracker, 3, 78.5).is_empty());
+        assert!(feed(&mut tracker, 4, 78.6).is_empty());
+        let events = feed(&mut tracker, 5, 78.6);
+        assert!(matches!(events[..], [CutEvent::SwitchRecommended { .. }]));
+
+        // Dropping within the hysteresis keeps the recommendation, below it withdraws it
+        assert!(feed(&mut tracker, 6, 77.8).is_empty());
+        let events = feed(&mut tracker, 7, 77.4);
+        assert!(matches!(
+            events[..],
+            [CutEvent::RecommendationWithdrawn { .. }]
+        ));
+
+        feed(&mut tracker, 8, 79.0);
+        let switched = tracker.switch().unwrap();
+        assert!(matches!(
+            switched,
+            CutEvent::Switched {
+                timestamp: 8000,
+                recommended: false,
+                ..
+            }
+        ));
+        assert!(tracker.switch().is_none());
+
+        feed(&mut tracker, 10, 79.0);
+        let yields = tracker.yields();
+        assert_eq!(yields[0].mass, Some(8.0));
+        assert_eq!(yields[0].end, Some(8000));
+        assert_eq!(yields[1].mass, Some(2.0));
+        assert_eq!(yields[1].share, Some(0.2));
+        assert_eq!(yields[1].mean_composition, Some(0.9));
+    }
+
+    #[test]
+    fn test_switches_survive_moving_back() {
+        let mut tracker = CutTracker::new(&settings());
+        for second in 0..5 {
+            feed(&mut tracker, second, 77.0);
+        }
+        tracker.switch().unwrap();
+        feed(&mut tracker, 6, 77.0);
+
+        // Seeking back undoes the switch until playback passes it again
+        feed(&mut tracker, 2, 77.0);
+        assert_eq!(tracker.status().unwrap().current, "Heads");
+        assert_eq!(tracker.switches().len(), 1);
+        let events = feed(&mut tracker, 5, 77.0);
+        assert!(matches!(
+            events[..],
+            [CutEvent::Switched {
+                timestamp: 4000,
+                ..
+            }]
+        ));
+        assert_eq!(tracker.yields()[1].start, 4000);
+    }
+
+    #[test]
+    fn test_last_cut_with_a_condition_has_nothing_to_recommend() {
+        let mut settings = settings();
+        settings.cuts[1].switch_when = settings.cuts[0].switch_when.clone();
+        let mut tracker = CutTracker::new(&settings);
+        feed(&mut tracker, 0, 77.0);
+        tracker.switch().unwrap();
+        for second in 1..5 {
+            assert!(feed(&mut tracker, second, 79.0).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(CutSettings::default().validate().is_ok());
+        let mut settings = settings();
+        settings.cuts.swap(0, 1);
+        assert!(settings.validate().is_err());
+    }
+}
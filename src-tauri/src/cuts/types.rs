@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CutVariable {
+    /// Vapour composition y_1 of the top plate
+    TopComposition,
+    TopTemperature,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CutDirection {
+    Above,
+    Below,
+}
+
+/// When to leave a cut. The switch is recommended once the variable has been
+/// past `threshold` for `hold_ms`, and withdrawn only when it comes back by
+/// more than `hysteresis`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchCondition {
+    pub variable: CutVariable,
+    pub direction: CutDirection,
+    pub threshold: f64,
+    pub hysteresis: f64,
+    #[serde(default)]
+    pub hold_ms: u64,
+}
+
+impl SwitchCondition {
+    pub fn is_met(&self, value: f64) -> bool {
+        match self.direction {
+            CutDirection::Above => value > self.threshold,
+            CutDirection::Below => value < self.threshold,
+        }
+    }
+
+    /// Whether a recommendation made earlier still holds
+    pub fn still_met(&self, value: f64) -> bool {
+        match self.direction {
+            CutDirection::Above => value > self.threshold - self.hysteresis,
+            CutDirection::Below => value < self.threshold + self.hysteresis,
+        }
+    }
+}
+
+/// A cut of the batch, in the order they are collected. The last cut has no
+/// switch condition and lasts until the end of the batch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CutDefinition {
+    pub name: String,
+    pub switch_when: Option<SwitchCondition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum CutEvent {
+    /// The current cut should be switched to the next one
+    #[serde(rename_all = "camelCase")]
+    SwitchRecommended {
+        timestamp: u64,
+        from: String,
+        to: String,
+        value: f64,
+    },
+    /// The variable came back before the operator switched
+    #[serde(rename_all = "camelCase")]
+    RecommendationWithdrawn { timestamp: u64, cut: String },
+    #[serde(rename_all = "camelCase")]
+    Switched {
+        timestamp: u64,
+        from: String,
+        to: String,
+        /// Whether the switch had been recommended when it was made
+        recommended: bool,
+    },
+}
+
+/// A switch made by the operator, stored with the session it was made in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CutSwitch {
+    pub timestamp: u64,
+    pub from: String,
+    pub to: String,
+    pub recommended: bool,
+}
+
+impl From<CutSwitch> for CutEvent {
+    fn from(switch: CutSwitch) -> Self {
+        CutEvent::Switched {
+            timestamp: switch.timestamp,
+            from: switch.from,
+            to: switch.to,
+            recommended: switch.recommended,
+        }
+    }
+}
+
+/// What was collected in one cut. Masses come from the distillate balance of
+/// the KPIs, `end` is `None` for the cut that is still being collected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CutYield {
+    pub name: String,
+    pub start: u64,
+    pub end: Option<u64>,
+    pub mass: Option<f64>,
+    /// Share of all distillate collected so far
+    pub share: Option<f64>,
+    pub mean_composition: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CutStatus {
+    pub current: String,
+    pub next: Option<String>,
+    pub recommended: bool,
+    pub yields: Vec<CutYield>,
+}
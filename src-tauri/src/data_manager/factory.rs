@@ -4,8 +4,8 @@ use rodbus::client::Channel;
 use tokio::sync::Mutex;
 
 use crate::{
-    calculations::service::CalculationService,
-    modbus::{client::ModbusClient, service::ModbusService},
+    calculations::service::CalculationService, diagnostics::SensorMonitor,
+    settings::types::ModbusSettings,
 };
 
 use super::{
@@ -24,13 +24,14 @@ impl ProviderFactory {
         &self,
         calculation_service: Arc<CalculationService>,
         modbus_channel: Arc<Mutex<Option<Channel>>>,
+        monitor: SensorMonitor,
+        settings: &ModbusSettings,
     ) -> Box<dyn DataProvider + Send> {
-        let modbus_client = ModbusClient::new();
-        let modbus_service = Arc::new(ModbusService::new(modbus_client));
         Box::new(LiveDataProvider::new(
             modbus_channel,
             calculation_service,
-            modbus_service,
+            settings.clone(),
+            monitor,
         ))
     }
 
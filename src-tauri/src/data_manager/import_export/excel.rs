@@ -1,47 +1,215 @@
 use crate::calculations::service::CalculationService;
 use crate::calculations::types::CompositionResult;
-use crate::data_manager::types::{ColumnEntry, ColumnStructure};
-use crate::errors::{ImportError, Result};
-use calamine::{open_workbook, Data, DataType, Range, Reader, Xlsx};
+use crate::data_manager::types::{
+    ColumnEntry, ColumnStructure, EntryQuality, Quality, QualityReason,
+};
+use crate::errors::{DataError, FileError, ImportError, Result};
+use crate::kpi::KpiTracker;
+use crate::math::interpolate;
+use crate::runs::types::RunInfo;
+use crate::sessions::types::Annotation;
+use crate::settings::types::KpiSettings;
+use calamine::{open_workbook, Data, DataType, Reader, Xlsx};
+use chrono_tz::Tz;
 use log::info;
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, XlsxError};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
+
+use super::merge::{merge_parts, ImportedPart};
+use super::timestamps::{detect_format, is_relative, parse_timestamp, write_timestamp};
+use super::types::{
+    ColumnMapping, ImportPreview, ImportProgress, ImportSource, ImportSummary, InvalidRowPolicy,
+    MergeSummary, RowDiagnostic, RowResolution,
+};
 
 pub struct ExcelDataImporter {
     calculation_service: CalculationService,
+    time_zone: Tz,
+    /// Charge of the still the distilled mass is balanced against
+    initial_charge: f64,
 }
 
 impl ExcelDataImporter {
     pub fn new(calculation_service: CalculationService) -> Self {
         Self {
             calculation_service,
+            time_zone: Tz::UTC,
+            initial_charge: KpiSettings::default().initial_charge,
         }
     }
 
-    pub async fn import(&self, path: &str) -> Result<(usize, Vec<Arc<ColumnEntry>>)> {
-        let mut workbook: Xlsx<_> = open_workbook(path)
+    pub fn with_time_zone(mut self, time_zone: Tz) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
+    pub fn with_initial_charge(mut self, initial_charge: f64) -> Self {
+        self.initial_charge = initial_charge;
+        self
+    }
+
+    /// Reads the first sheet of the workbook row by row, reporting progress as it goes.
+    /// Without a mapping the layout is detected from the header row. Invalid temperature
+    /// cells are resolved with `policy` and reported in the summary.
+    pub fn import<F>(
+        &self,
+        path: &str,
+        mapping: Option<&ColumnMapping>,
+        policy: InvalidRowPolicy,
+        cancel: &AtomicBool,
+        on_progress: F,
+    ) -> Result<(Vec<Arc<ColumnEntry>>, ImportSummary)>
+    where
+        F: FnMut(ImportProgress),
+    {
+        self.import_sheet(path, None, mapping, policy, cancel, on_progress)
+    }
+
+    /// Imports every source with the same mapping and merges them into one run
+    /// ordered by timestamp. All sources must have the same number of plates.
+    pub fn import_many<F>(
+        &self,
+        sources: &[ImportSource],
+        mapping: Option<&ColumnMapping>,
+        policy: InvalidRowPolicy,
+        cancel: &AtomicBool,
+        mut on_progress: F,
+    ) -> Result<(Vec<Arc<ColumnEntry>>, MergeSummary)>
+    where
+        F: FnMut(ImportProgress),
+    {
+        let mut parts = Vec::with_capacity(sources.len());
+        for (index, source) in sources.iter().enumerate() {
+            info!("Importing source {} of {}", index + 1, sources.len());
+            let (entries, summary) = self.import_sheet(
+                &source.path,
+                source.sheet.as_deref(),
+                mapping,
+                policy,
+                cancel,
+                |progress| {
+                    // Every source takes an equal share of the overall progress
+                    on_progress(ImportProgress {
+                        percentage: (index as f64 * 100.0 + progress.percentage)
+                            / sources.len() as f64,
+                        ..progress
+                    })
+                },
+            )?;
+            parts.push(ImportedPart {
+                source: source.clone(),
+                entries,
+                summary,
+            });
+        }
+
+        merge_parts(parts, &self.calculation_service, self.initial_charge)
+    }
+
+    /// Lists the sheets of a workbook in their tab order.
+    pub fn sheet_names(&self, path: &str) -> Result<Vec<String>> {
+        let workbook: Xlsx<_> = open_workbook(path)
             .map_err(|_| ImportError::InvalidFormat("Unable to open workbook".into()))?;
-        let worksheet_name = workbook
-            .sheet_names()
-            .first()
-            .cloned()
-            .ok_or(ImportError::InvalidFormat("No sheets found".into()))?;
+        Ok(workbook.sheet_names())
+    }
+
+    fn import_sheet<F>(
+        &self,
+        path: &str,
+        sheet: Option<&str>,
+        mapping: Option<&ColumnMapping>,
+        policy: InvalidRowPolicy,
+        cancel: &AtomicBool,
+        on_progress: F,
+    ) -> Result<(Vec<Arc<ColumnEntry>>, ImportSummary)>
+    where
+        F: FnMut(ImportProgress),
+    {
+        let started = Instant::now();
 
-        let range = workbook
-            .worksheet_range(&worksheet_name)
-            .map_err(|_| ImportError::InvalidFormat("Cannot read sheet".into()))?;
+        let mut run = ImportRun {
+            importer: self,
+            mapping,
+            structure: None,
+            rows: Vec::new(),
+            summary: ImportSummary {
+                policy,
+                ..ImportSummary::default()
+            },
+            total_rows: 0,
+            last_percentage: 0,
+            on_progress,
+        };
+
+        read_rows(path, sheet, |row_index, total_rows, row| {
+            if cancel.load(Ordering::Relaxed) {
+                info!("Import cancelled after {} rows", run.summary.rows_read);
+                return Err(ImportError::Cancelled.into());
+            }
+            run.total_rows = total_rows;
+            run.push_row(row_index, row)?;
+            Ok(true)
+        })?;
 
-        let column_structure = self.parse_headers(&range)?;
+        let has_compositions = run
+            .structure
+            .as_ref()
+            .is_some_and(|structure| structure.has_compositions());
+        let mut summary = run.summary;
+        // Rows are repaired and played back in time order, a repeated timestamp keeps its first row
+        let mut rows = run.rows;
+        rows.sort_by_key(|row| row.timestamp);
+        rows.dedup_by_key(|row| row.timestamp);
+        let rows = self.resolve_rows(rows, has_compositions, &mut summary);
+        let entries = self.build_entries(rows);
+
+        if entries.is_empty() {
+            return Err(ImportError::InvalidFormat("No valid data rows found".into()).into());
+        }
 
-        let entries = self.process_rows(&range, &column_structure)?;
+        summary.rows_imported = entries.len() as u32;
+        summary.rows_skipped = summary.rows_read - summary.rows_imported;
+        summary.diagnostics.sort_by_key(|diagnostic| diagnostic.row);
+        summary.duration_ms = started.elapsed().as_millis() as u64;
+        info!(
+            "Imported {} of {} rows ({} repaired) in {} ms",
+            summary.rows_imported, summary.rows_read, summary.rows_repaired, summary.duration_ms
+        );
 
-        Ok((column_structure.number_plates, entries))
+        Ok((entries, summary))
     }
 
-    fn parse_headers(&self, range: &Range<Data>) -> Result<ColumnStructure> {
-        let headers = range
-            .rows()
-            .next()
-            .ok_or_else(|| ImportError::InvalidFormat("No headers found".into()))?;
+    /// Reads the header row and the first `max_rows` data rows as text.
+    pub fn preview(&self, path: &str, max_rows: usize) -> Result<ImportPreview> {
+        let mut headers: Option<Vec<Data>> = None;
+        let mut rows = Vec::new();
+
+        read_rows(path, None, |_, _, row| {
+            if headers.is_none() {
+                headers = Some(row);
+            } else {
+                rows.push(row.iter().map(|cell| cell.to_string()).collect());
+            }
+            Ok(rows.len() < max_rows)
+        })?;
+
+        let headers =
+            headers.ok_or_else(|| ImportError::InvalidFormat("No headers found".into()))?;
+        let suggested_mapping = self
+            .parse_headers(&headers)
+            .ok()
+            .map(|structure| structure.to_mapping());
+
+        Ok(ImportPreview {
+            headers: headers.iter().map(|cell| cell.to_string()).collect(),
+            rows,
+            suggested_mapping,
+        })
+    }
+
+    fn parse_headers(&self, headers: &[Data]) -> Result<ColumnStructure> {
         if headers.len() < 2 {
             return Err(ImportError::InvalidFormat("Insufficient columns".into()).into());
         }
@@ -68,143 +236,735 @@ impl ExcelDataImporter {
 
         info!("Detected {} plates in Excel file", number_plates);
 
-        let compositions_x_start = if has_compositions {
-            Some(1 + number_plates)
-        } else {
-            None
-        };
-
-        let compositions_y_start = if has_compositions {
-            Some(1 + number_plates * 2)
-        } else {
-            None
-        };
+        let columns = |start: usize| (start..start + number_plates).collect::<Vec<usize>>();
 
         Ok(ColumnStructure {
             number_plates,
-            has_compositions,
             timestamp_column: 0,
-            temperatures_start: 1,
-            compositions_x_start,
-            compositions_y_start,
+            timestamp_format: None,
+            temperature_columns: columns(1),
+            pressure_column: None,
+            compositions_x_columns: has_compositions.then(|| columns(1 + number_plates)),
+            compositions_y_columns: has_compositions.then(|| columns(1 + number_plates * 2)),
         })
     }
 
-    fn process_rows(
+    fn apply_mapping(&self, mapping: &ColumnMapping, headers: &[Data]) -> Result<ColumnStructure> {
+        let structure = ColumnStructure::from_mapping(mapping);
+        let number_plates = structure.number_plates;
+
+        if number_plates == 0 {
+            return Err(ImportError::InvalidMapping(
+                "At least one temperature column is required".into(),
+            )
+            .into());
+        }
+        if structure.last_column() >= headers.len() {
+            return Err(ImportError::InvalidMapping(format!(
+                "Column {} is outside the {} columns of the sheet",
+                structure.last_column() + 1,
+                headers.len()
+            ))
+            .into());
+        }
+        for (name, columns) in [
+            ("x_1", &structure.compositions_x_columns),
+            ("y_1", &structure.compositions_y_columns),
+        ] {
+            if let Some(columns) = columns {
+                if columns.len() != number_plates {
+                    return Err(ImportError::InvalidMapping(format!(
+                        "Expected {} composition {} columns, found {}",
+                        number_plates,
+                        name,
+                        columns.len()
+                    ))
+                    .into());
+                }
+            }
+        }
+
+        info!("Using column mapping with {} plates", number_plates);
+        Ok(structure)
+    }
+
+    /// Reads the cells of one data row. Rows without a usable timestamp are rejected,
+    /// invalid temperatures are kept as `None` until the policy is applied.
+    fn parse_row(
         &self,
-        range: &Range<Data>,
+        row_index: u32,
+        row: &[Data],
         structure: &ColumnStructure,
-    ) -> Result<Vec<Arc<ColumnEntry>>> {
-        let mut imported_data: Vec<Arc<ColumnEntry>> = Vec::new();
-        let total_rows = range.rows().count().saturating_sub(1);
-
-        let mut x_b0 = 0.0;
-        let initial_mass = 1000.0;
+    ) -> std::result::Result<ParsedRow, RowDiagnostic> {
+        let sheet_row = row_index + 1;
+        let rejected = |reason: &str, raw_value: Option<&Data>| RowDiagnostic {
+            row: sheet_row,
+            column: Some(structure.timestamp_column as u32),
+            reason: reason.to_string(),
+            raw_value: raw_value.map(|cell| cell.to_string()),
+            resolution: RowResolution::Skipped,
+        };
 
-        for (index, row) in range.rows().skip(1).enumerate() {
-            let percentage_complete = (index as f64 + 1.0) / total_rows as f64 * 100.0;
+        let timestamp_cell = row
+            .get(structure.timestamp_column)
+            .filter(|cell| !cell.is_empty())
+            .ok_or_else(|| rejected("missing timestamp", None))?;
+        let timestamp = structure
+            .timestamp_format
+            .and_then(|format| parse_timestamp(timestamp_cell, format, self.time_zone))
+            .ok_or_else(|| rejected("unreadable timestamp", Some(timestamp_cell)))?;
 
-            if row.is_empty() || row.len() < structure.temperatures_start + structure.number_plates
-            {
-                continue;
+        let mut temperatures = Vec::with_capacity(structure.number_plates);
+        let mut invalid = Vec::new();
+        for (plate, &column) in structure.temperature_columns.iter().enumerate() {
+            let cell = row.get(column);
+            let value = cell
+                .and_then(|cell| cell.as_f64())
+                .filter(|v| v.is_finite());
+            if value.is_none() {
+                let reason = match cell {
+                    None => format!("short row, {} of {} columns", row.len(), column + 1),
+                    Some(Data::Empty) => "missing temperature".to_string(),
+                    Some(_) => "temperature is not a number".to_string(),
+                };
+                invalid.push(InvalidCell {
+                    plate,
+                    column,
+                    reason,
+                    raw_value: cell.filter(|c| !c.is_empty()).map(|c| c.to_string()),
+                });
             }
+            temperatures.push(value);
+        }
 
-            let timestamp = match row
-                .get(structure.timestamp_column)
-                .and_then(|cell| cell.as_f64())
-            {
-                Some(ts) => ts as u64,
-                None => continue,
+        let cell = |column: usize| row.get(column).and_then(|cell| cell.as_f64());
+        let pressure = structure.pressure_column.and_then(cell);
+
+        let temperature_quality: Vec<Quality> = temperatures
+            .iter()
+            .map(|value| match value {
+                Some(_) => Quality::Good,
+                None => Quality::Bad(QualityReason::MissingValue),
+            })
+            .collect();
+
+        let (compositions, quality) = if structure.has_compositions() {
+            let read = |columns: &Option<Vec<usize>>| -> Vec<Option<f64>> {
+                match columns {
+                    Some(columns) => columns.iter().map(|&column| cell(column)).collect(),
+                    None => vec![None; structure.number_plates],
+                }
             };
 
-            let temperatures: Vec<f64> = row
+            let compositions: Vec<CompositionResult> = read(&structure.compositions_x_columns)
+                .into_iter()
+                .zip(read(&structure.compositions_y_columns))
+                .map(|(x, y)| CompositionResult { x_1: x, y_1: y })
+                .collect();
+            let quality = EntryQuality {
+                compositions: compositions
+                    .iter()
+                    .map(|c| match c.is_complete() {
+                        true => Quality::Good,
+                        false => Quality::Bad(QualityReason::MissingValue),
+                    })
+                    .collect(),
+                temperatures: temperature_quality,
+            };
+            (compositions, quality)
+        } else {
+            let compositions = self
+                .calculation_service
+                .calculate_compositions(&temperatures, pressure);
+            let quality = EntryQuality::solved(temperature_quality, &compositions);
+            (compositions, quality)
+        };
+
+        Ok(ParsedRow {
+            row: sheet_row,
+            timestamp,
+            temperatures,
+            compositions,
+            pressure,
+            quality,
+            invalid,
+        })
+    }
+
+    /// Applies the policy to the invalid cells, dropping the rows it can't repair.
+    fn resolve_rows(
+        &self,
+        rows: Vec<ParsedRow>,
+        has_compositions: bool,
+        summary: &mut ImportSummary,
+    ) -> Vec<ParsedRow> {
+        let policy = summary.policy;
+        let number_plates = summary.number_plates as usize;
+
+        // Next valid reading of every plate, only kept for rows that need interpolating
+        let mut next_valid = Vec::new();
+        if policy == InvalidRowPolicy::Interpolate {
+            let mut upcoming: Vec<Option<(u64, f64)>> = vec![None; number_plates];
+            next_valid = vec![Vec::new(); rows.len()];
+            for (index, row) in rows.iter().enumerate().rev() {
+                if !row.invalid.is_empty() {
+                    next_valid[index] = upcoming.clone();
+                }
+                for (plate, temperature) in row.temperatures.iter().enumerate() {
+                    if let Some(temperature) = temperature {
+                        upcoming[plate] = Some((row.timestamp, *temperature));
+                    }
+                }
+            }
+        }
+
+        let mut previous: Vec<Option<(u64, f64)>> = vec![None; number_plates];
+        let mut resolved = Vec::with_capacity(rows.len());
+        for (index, mut row) in rows.into_iter().enumerate() {
+            let invalid = std::mem::take(&mut row.invalid);
+            let repaired: Vec<Option<f64>> = invalid
                 .iter()
-                .skip(structure.temperatures_start)
-                .take(structure.number_plates)
-                .filter_map(|cell| cell.as_f64())
+                .map(|cell| match policy {
+                    InvalidRowPolicy::FillForward => previous[cell.plate].map(|(_, value)| value),
+                    InvalidRowPolicy::Interpolate => {
+                        match (previous[cell.plate], next_valid[index][cell.plate]) {
+                            (Some((t_0, v_0)), Some((t_1, v_1))) => Some(interpolate(
+                                t_0 as f64,
+                                v_0,
+                                t_1 as f64,
+                                v_1,
+                                row.timestamp as f64,
+                            )),
+                            _ => None,
+                        }
+                    }
+                    InvalidRowPolicy::Skip | InvalidRowPolicy::Abort => None,
+                })
                 .collect();
 
-            if temperatures.len() != structure.number_plates {
-                info!("Row {} has incomplete temperature, skipping", index + 1);
+            let is_repair = !repaired.is_empty();
+            let keep = repaired.iter().all(Option::is_some);
+            let resolution = match policy {
+                _ if !keep => RowResolution::Skipped,
+                InvalidRowPolicy::FillForward => RowResolution::FilledForward,
+                _ => RowResolution::Interpolated,
+            };
+
+            for (cell, value) in invalid.into_iter().zip(repaired) {
+                if keep {
+                    let quality = match policy {
+                        InvalidRowPolicy::FillForward => QualityReason::FilledForward,
+                        _ => QualityReason::TimeInterpolation,
+                    };
+                    row.temperatures[cell.plate] = value;
+                    row.quality.temperatures[cell.plate] = Quality::Uncertain(quality);
+                    if !has_compositions {
+                        let composition = self
+                            .calculation_service
+                            .calculate_compositions(&[value], row.pressure)
+                            .remove(0);
+                        row.quality.compositions[cell.plate] =
+                            Quality::of_composition(Quality::Uncertain(quality), &composition);
+                        row.compositions[cell.plate] = composition;
+                    }
+                }
+                summary.diagnostics.push(RowDiagnostic {
+                    row: row.row,
+                    column: Some(cell.column as u32),
+                    reason: cell.reason,
+                    raw_value: cell.raw_value,
+                    resolution,
+                });
+            }
+
+            if !keep {
                 continue;
             }
+            if is_repair {
+                summary.rows_repaired += 1;
+            }
+            for (plate, temperature) in row.temperatures.iter().enumerate() {
+                previous[plate] = temperature.map(|temperature| (row.timestamp, temperature));
+            }
+            resolved.push(row);
+        }
 
-            let compositions: Vec<CompositionResult> = if structure.has_compositions {
-                let comp_x: Vec<Option<f64>> = match structure.compositions_x_start {
-                    Some(start) => row
-                        .iter()
-                        .skip(start)
-                        .take(structure.number_plates)
-                        .map(|cell| cell.as_f64())
-                        .collect(),
-                    None => vec![None; structure.number_plates],
-                };
+        resolved
+    }
 
-                let comp_y: Vec<Option<f64>> = match structure.compositions_y_start {
-                    Some(start) => row
-                        .iter()
-                        .skip(start)
-                        .take(structure.number_plates)
-                        .map(|cell| cell.as_f64())
-                        .collect(),
-                    None => vec![None; structure.number_plates],
-                };
+    fn build_entries(&self, rows: Vec<ParsedRow>) -> Vec<Arc<ColumnEntry>> {
+        let total = rows.len();
+        let masses = self.calculation_service.calculate_distilled_masses(
+            self.initial_charge,
+            rows.iter().map(|row| row.compositions.as_slice()),
+        );
 
-                comp_x
-                    .into_iter()
-                    .zip(comp_y.into_iter())
-                    .map(|(x, y)| CompositionResult { x_1: x, y_1: y })
-                    .collect()
-            } else {
-                temperatures
-                    .iter()
-                    .map(|&temp| {
-                        self.calculation_service
-                            .calculate_composition(None, temp, None, None)
-                            .unwrap_or_else(|_| CompositionResult {
-                                x_1: None,
-                                y_1: None,
-                            })
-                    })
-                    .collect()
+        rows.into_iter()
+            .zip(masses)
+            .enumerate()
+            .map(|(index, (row, distilled_mass))| {
+                Arc::new(ColumnEntry {
+                    timestamp: row.timestamp,
+                    temperatures: self.plate_temperatures(row.temperatures),
+                    compositions: row.compositions,
+                    percentage_complete: (index + 1) as f64 / total as f64 * 100.0,
+                    distilled_mass,
+                    pressure: row.pressure,
+                    quality: row.quality,
+                    run: None,
+                    measurements: Vec::new(),
+                })
+            })
+            .collect()
+    }
+
+    /// One temperature per plate. A plate still missing keeps its slot with a
+    /// value interpolated from the others, its quality stays bad.
+    fn plate_temperatures(&self, temperatures: Vec<Option<f64>>) -> Vec<f64> {
+        let number_plates = temperatures.len();
+        let measured: Vec<(u32, f64)> = temperatures
+            .iter()
+            .enumerate()
+            .filter_map(|(plate, temp)| temp.map(|temp| (plate as u32, temp)))
+            .collect();
+        if measured.len() == number_plates {
+            return temperatures.into_iter().flatten().collect();
+        }
+
+        let profile = self
+            .calculation_service
+            .interpolate_profile(number_plates as i32, &measured);
+        temperatures
+            .into_iter()
+            .enumerate()
+            .map(|(plate, temp)| temp.or(profile.get(plate).copied()).unwrap_or_default())
+            .collect()
+    }
+}
+
+/// Streams the rows of `sheet`, or of the first sheet when not given. The callback receives the zero based row
+/// index, the number of data rows announced by the sheet and the row cells, and
+/// returns whether reading should continue.
+fn read_rows<F>(path: &str, sheet: Option<&str>, mut on_row: F) -> Result<()>
+where
+    F: FnMut(u32, u32, Vec<Data>) -> Result<bool>,
+{
+    let mut workbook: Xlsx<_> = open_workbook(path)
+        .map_err(|_| ImportError::InvalidFormat("Unable to open workbook".into()))?;
+    let sheet_names = workbook.sheet_names();
+    let worksheet_name = match sheet {
+        Some(sheet) => sheet_names
+            .iter()
+            .find(|name| name.as_str() == sheet)
+            .cloned()
+            .ok_or_else(|| ImportError::InvalidFormat(format!("Sheet {} not found", sheet)))?,
+        None => sheet_names
+            .first()
+            .cloned()
+            .ok_or(ImportError::InvalidFormat("No sheets found".into()))?,
+    };
+
+    let mut reader = workbook
+        .worksheet_cells_reader(&worksheet_name)
+        .map_err(|_| ImportError::InvalidFormat("Cannot read sheet".into()))?;
+    let dimensions = reader.dimensions();
+    let first_column = dimensions.start.1;
+    let total_rows = dimensions.end.0.saturating_sub(dimensions.start.0);
+
+    // Cells arrive in row order, so a row is complete once the next one starts
+    let mut current_row: Option<u32> = None;
+    let mut row: Vec<Data> = Vec::new();
+    loop {
+        let cell = reader
+            .next_cell()
+            .map_err(|e| ImportError::InvalidFormat(e.to_string()))?;
+        let next_row = cell.as_ref().map(|c| c.get_position().0);
+
+        if let Some(row_index) = current_row {
+            if next_row != current_row && !on_row(row_index, total_rows, std::mem::take(&mut row))?
+            {
+                return Ok(());
+            }
+        }
+
+        let Some(cell) = cell else {
+            return Ok(());
+        };
+        let (row_index, column) = cell.get_position();
+        let column = column.saturating_sub(first_column) as usize;
+        if row.len() <= column {
+            row.resize(column + 1, Data::Empty);
+        }
+        row[column] = Data::from(cell.get_value().clone());
+        current_row = Some(row_index);
+    }
+}
+
+/// Values of one data row before the invalid cells are resolved
+struct ParsedRow {
+    row: u32,
+    timestamp: u64,
+    temperatures: Vec<Option<f64>>,
+    compositions: Vec<CompositionResult>,
+    pressure: Option<f64>,
+    quality: EntryQuality,
+    invalid: Vec<InvalidCell>,
+}
+
+struct InvalidCell {
+    plate: usize,
+    column: usize,
+    reason: String,
+    raw_value: Option<String>,
+}
+
+struct ImportRun<'a, F: FnMut(ImportProgress)> {
+    importer: &'a ExcelDataImporter,
+    mapping: Option<&'a ColumnMapping>,
+    structure: Option<ColumnStructure>,
+    rows: Vec<ParsedRow>,
+    summary: ImportSummary,
+    total_rows: u32,
+    last_percentage: u32,
+    on_progress: F,
+}
+
+impl<F: FnMut(ImportProgress)> ImportRun<'_, F> {
+    fn push_row(&mut self, row_index: u32, row: Vec<Data>) -> Result<()> {
+        // The first row holds the headers
+        let Some(structure) = &mut self.structure else {
+            let structure = match self.mapping {
+                Some(mapping) => self.importer.apply_mapping(mapping, &row)?,
+                None => self.importer.parse_headers(&row)?,
             };
+            self.summary.number_plates = structure.number_plates as u32;
+            self.summary.timestamp_format = structure.timestamp_format;
+            self.structure = Some(structure);
+            return Ok(());
+        };
+        self.summary.rows_read += 1;
 
-            if compositions.first().and_then(|c| c.x_1).is_some() && x_b0 == 0.0 {
-                x_b0 = compositions.first().unwrap().x_1.unwrap();
-            }
-
-            let mut distilled_mass = 0.0;
-            if imported_data.len() > 0 && x_b0 > 0.0 {
-                if let Some(first_comp) = compositions.first() {
-                    if let Some(last_comp) = compositions.last() {
-                        if let (Some(x_bf), Some(x_d)) = (first_comp.x_1, last_comp.y_1) {
-                            println!("\ncount: {}", index);
-                            println!("xb0: {}, x_bf: {}, x_d: {}", x_b0, x_bf, x_d);
-                            distilled_mass = self.calculation_service.calculate_distilled_mass(
-                                initial_mass,
-                                0.69,
-                                x_bf,
-                                x_d,
-                            );
-                            println!("distilled_mass: {}", distilled_mass);
-                        }
-                    }
+        // Without an explicit format the first readable timestamp decides it for the sheet
+        if structure.timestamp_format.is_none() {
+            structure.timestamp_format =
+                row.get(structure.timestamp_column).and_then(detect_format);
+            if let Some(format) = structure.timestamp_format {
+                info!("Detected {:?} timestamps", format);
+                self.summary.timestamp_format = Some(format);
+            }
+        }
+
+        let abort = self.summary.policy == InvalidRowPolicy::Abort;
+        match self.importer.parse_row(row_index, &row, structure) {
+            Ok(parsed) => {
+                if let (true, Some(cell)) = (abort, parsed.invalid.first()) {
+                    return Err(ImportError::InvalidRow(format!(
+                        "{} column {}: {}",
+                        parsed.row,
+                        cell.column + 1,
+                        cell.reason
+                    ))
+                    .into());
+                }
+                self.rows.push(parsed);
+            }
+            Err(diagnostic) => {
+                if abort {
+                    return Err(ImportError::InvalidRow(format!(
+                        "{}: {}",
+                        diagnostic.row, diagnostic.reason
+                    ))
+                    .into());
                 }
+                info!("Row {} skipped: {}", diagnostic.row, diagnostic.reason);
+                self.summary.diagnostics.push(diagnostic);
             }
+        }
+
+        // Only report whole percent steps to keep the event rate low
+        let percentage_complete = if self.total_rows > 0 {
+            (self.summary.rows_read as f64 / self.total_rows as f64 * 100.0).min(100.0)
+        } else {
+            0.0
+        };
+        let percentage = percentage_complete as u32;
+        if percentage > self.last_percentage {
+            self.last_percentage = percentage;
+            (self.on_progress)(ImportProgress {
+                rows_read: self.summary.rows_read,
+                total_rows: self.total_rows,
+                percentage: percentage_complete,
+            });
+        }
+
+        Ok(())
+    }
+}
 
-            imported_data.push(Arc::new(ColumnEntry {
-                timestamp,
-                temperatures,
-                compositions,
-                percentage_complete,
-                distilled_mass,
-            }));
+const KPI_HEADERS: [&str; 6] = [
+    "Still x_B",
+    "Distillate x_D",
+    "Distilled mass",
+    "Flow rate (/min)",
+    "Recovery",
+    "Time to target (min)",
+];
+
+pub struct ExcelDataExporter {
+    time_zone: Tz,
+    kpis: Option<KpiSettings>,
+    annotations: Vec<Annotation>,
+    run: Option<RunInfo>,
+}
+
+impl ExcelDataExporter {
+    pub fn new() -> Self {
+        Self {
+            time_zone: Tz::UTC,
+            kpis: None,
+            annotations: Vec::new(),
+            run: None,
         }
+    }
 
-        if imported_data.is_empty() {
-            return Err(ImportError::InvalidFormat("No valid data rows found".into()).into());
+    pub fn with_time_zone(mut self, time_zone: Tz) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
+    /// Adds the operating KPIs of the run, computed over the exported entries
+    pub fn with_kpis(mut self, settings: KpiSettings) -> Self {
+        self.kpis = Some(settings);
+        self
+    }
+
+    /// Writes the notes of the run to a sheet of their own
+    pub fn with_annotations(mut self, annotations: Vec<Annotation>) -> Self {
+        self.annotations = annotations;
+        self
+    }
+
+    /// Writes the batch metadata and phases of the run to a sheet of their own
+    pub fn with_run(mut self, run: RunInfo) -> Self {
+        self.run = Some(run);
+        self
+    }
+
+    pub fn export(&self, column_data: &[Arc<ColumnEntry>], path: &str) -> Result<()> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+
+        info!("Writing headers...");
+
+        let Some(first) = column_data.first() else {
+            return Err(DataError::NoDataError.into());
+        };
+
+        // Runs with relative times have no date, their timestamps are written as elapsed seconds
+        let relative = is_relative(first.timestamp);
+        let datetime_format = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss.000");
+
+        // write headers
+        let timestamp_header = if relative {
+            "Elapsed (s)".to_string()
+        } else {
+            format!("Timestamp ({})", self.time_zone.name())
+        };
+        worksheet
+            .write(0, 0, timestamp_header.as_str())
+            .map_err(xlsx_error)?;
+        worksheet.set_column_width(0, 24).map_err(xlsx_error)?;
+        let num_values = first.temperatures.len();
+        for i in 0..num_values {
+            worksheet
+                .write(0, (i + 1) as u16, format!("Temperature {}", i + 1))
+                .map_err(xlsx_error)?;
+            worksheet
+                .write(
+                    0,
+                    (num_values + i + 1) as u16,
+                    format!("Composition x_1 {}", i + 1),
+                )
+                .map_err(xlsx_error)?;
+            worksheet
+                .write(
+                    0,
+                    (num_values * 2 + i + 1) as u16,
+                    format!("Composition y_1 {}", i + 1),
+                )
+                .map_err(xlsx_error)?;
+        }
+
+        let quality_column = (num_values * 3 + 1) as u16;
+        worksheet
+            .write(0, quality_column, "Quality")
+            .map_err(xlsx_error)?;
+        worksheet
+            .set_column_width(quality_column, 40)
+            .map_err(xlsx_error)?;
+
+        let kpis = self.kpis.clone().map(|settings| {
+            KpiTracker::replay(settings, column_data.iter().map(|entry| entry.as_ref()))
+        });
+        if kpis.is_some() {
+            for (i, header) in KPI_HEADERS.iter().enumerate() {
+                worksheet
+                    .write(0, quality_column + 1 + i as u16, *header)
+                    .map_err(xlsx_error)?;
+            }
         }
 
-        Ok(imported_data)
+        // write data
+        info!("Writing data");
+        for (row, value) in column_data.iter().enumerate() {
+            let row = (row + 1) as u32;
+            write_timestamp(
+                worksheet,
+                row,
+                0,
+                value.timestamp,
+                self.time_zone,
+                &datetime_format,
+            )
+            .map_err(xlsx_error)?;
+
+            for (i, &temp) in value.temperatures.iter().enumerate() {
+                worksheet
+                    .write(row, (i + 1) as u16, temp)
+                    .map_err(xlsx_error)?;
+            }
+            for (i, comp) in value.compositions.iter().enumerate() {
+                worksheet
+                    .write(row, (num_values + i + 1) as u16, comp.x_1)
+                    .map_err(xlsx_error)?;
+
+                worksheet
+                    .write(row, (num_values * 2 + i + 1) as u16, comp.y_1)
+                    .map_err(xlsx_error)?;
+            }
+
+            if !value.quality.is_good() {
+                worksheet
+                    .write(row, quality_column, describe_quality(value))
+                    .map_err(xlsx_error)?;
+            }
+
+            if let Some(kpi) = kpis.as_ref().and_then(|kpis| kpis.get(row as usize - 1)) {
+                let values = [
+                    kpi.still_composition,
+                    kpi.distillate_composition,
+                    kpi.distilled_mass,
+                    kpi.flow_rate,
+                    kpi.recovery,
+                    kpi.time_to_target,
+                ];
+                for (i, value) in values.into_iter().enumerate() {
+                    worksheet
+                        .write(row, quality_column + 1 + i as u16, value)
+                        .map_err(xlsx_error)?;
+                }
+            }
+        }
+
+        if !self.annotations.is_empty() {
+            let sheet = workbook.add_worksheet();
+            sheet.set_name("Annotations").map_err(xlsx_error)?;
+            sheet
+                .write(0, 0, timestamp_header.as_str())
+                .map_err(xlsx_error)?;
+            sheet.write(0, 1, "Note").map_err(xlsx_error)?;
+            sheet.set_column_width(0, 24).map_err(xlsx_error)?;
+            sheet.set_column_width(1, 60).map_err(xlsx_error)?;
+
+            for (row, annotation) in self.annotations.iter().enumerate() {
+                let row = (row + 1) as u32;
+                write_timestamp(
+                    sheet,
+                    row,
+                    0,
+                    annotation.timestamp,
+                    self.time_zone,
+                    &datetime_format,
+                )
+                .map_err(xlsx_error)?;
+                sheet
+                    .write(row, 1, annotation.text.as_str())
+                    .map_err(xlsx_error)?;
+            }
+        }
+
+        if let Some(run) = &self.run {
+            let sheet = workbook.add_worksheet();
+            sheet.set_name("Run").map_err(xlsx_error)?;
+            sheet.set_column_width(0, 24).map_err(xlsx_error)?;
+            sheet.set_column_width(1, 40).map_err(xlsx_error)?;
+
+            let fields = [
+                ("Batch ID", run.metadata.batch_id.clone()),
+                ("Operator", run.metadata.operator.clone()),
+                ("Feed", run.metadata.feed_description.clone()),
+                ("Charge mass", run.metadata.charge_mass.to_string()),
+            ];
+            for (row, (name, value)) in fields.iter().enumerate() {
+                sheet.write(row as u32, 0, *name).map_err(xlsx_error)?;
+                sheet
+                    .write(row as u32, 1, value.as_str())
+                    .map_err(xlsx_error)?;
+            }
+
+            // Start, phase changes and end of the run, one row each
+            let first_row = fields.len() as u32 + 1;
+            sheet
+                .write(first_row, 0, timestamp_header.as_str())
+                .map_err(xlsx_error)?;
+            sheet.write(first_row, 1, "Event").map_err(xlsx_error)?;
+            let mut events = vec![(run.started_at, "Started".to_string())];
+            events.extend(
+                run.phases
+                    .iter()
+                    .map(|change| (change.timestamp, change.phase.describe().to_string())),
+            );
+            events.extend(run.ended_at.map(|ended_at| (ended_at, "Ended".to_string())));
+
+            for (row, (timestamp, event)) in events.iter().enumerate() {
+                let row = first_row + 1 + row as u32;
+                write_timestamp(sheet, row, 0, *timestamp, self.time_zone, &datetime_format)
+                    .map_err(xlsx_error)?;
+                sheet.write(row, 1, event.as_str()).map_err(xlsx_error)?;
+            }
+        }
+
+        info!("Saving excel...");
+        workbook.save(path).map_err(xlsx_error)?;
+        info!("Excel saved");
+
+        Ok(())
     }
 }
+
+/// Lists the values of an entry that are not good, e.g. "T2 uncertain (filled forward)"
+fn describe_quality(entry: &ColumnEntry) -> String {
+    let describe = |label: String, quality: Quality| match quality {
+        Quality::Good => None,
+        Quality::Uncertain(reason) => Some(format!("{} uncertain ({})", label, reason.describe())),
+        Quality::Bad(reason) => Some(format!("{} bad ({})", label, reason.describe())),
+    };
+
+    let temperatures = (0..entry.temperatures.len())
+        .filter_map(|plate| describe(format!("T{}", plate + 1), entry.temperature_quality(plate)));
+    let compositions = (0..entry.compositions.len()).filter_map(|plate| {
+        describe(
+            format!("x/y {}", plate + 1),
+            entry.composition_quality(plate),
+        )
+    });
+
+    temperatures
+        .chain(compositions)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn xlsx_error(e: XlsxError) -> FileError {
+    FileError::WriteError(format!("Xlsx error: {}", e))
+}
@@ -0,0 +1,181 @@
+use super::timestamps::is_relative;
+use super::types::{
+    ImportSource, ImportSummary, MergeSummary, SourceOverlap, SourceSummary, TimeRange,
+};
+use crate::calculations::service::CalculationService;
+use crate::data_manager::types::{typical_interval, ColumnEntry, GAP_FACTOR};
+use crate::errors::{DataError, ImportError, Result};
+use log::info;
+use std::sync::Arc;
+
+pub struct ImportedPart {
+    pub source: ImportSource,
+    pub entries: Vec<Arc<ColumnEntry>>,
+    pub summary: ImportSummary,
+}
+
+/// Joins imported parts into one continuous run. Parts with dates are ordered by
+/// their first timestamp, parts with relative times are chained in the given order.
+pub fn merge_parts(
+    mut parts: Vec<ImportedPart>,
+    calculation_service: &CalculationService,
+    initial_charge: f64,
+) -> Result<(Vec<Arc<ColumnEntry>>, MergeSummary)> {
+    let number_plates = parts
+        .first()
+        .map(|part| part.summary.number_plates)
+        .ok_or(DataError::EmptyDataError)?;
+    if let Some(part) = parts
+        .iter()
+        .find(|part| part.summary.number_plates != number_plates)
+    {
+        return Err(ImportError::PlateMismatch(format!(
+            "{} has {} plates, expected {}",
+            part.source.label(),
+            part.summary.number_plates,
+            number_plates
+        ))
+        .into());
+    }
+
+    let relative = parts
+        .iter()
+        .filter(|part| {
+            part.entries
+                .first()
+                .is_some_and(|e| is_relative(e.timestamp))
+        })
+        .count();
+    if relative > 0 && relative < parts.len() {
+        return Err(ImportError::InvalidFormat(
+            "Cannot merge relative times with dated ones".into(),
+        )
+        .into());
+    }
+
+    if relative > 0 {
+        chain_relative(&mut parts);
+    } else {
+        parts.sort_by_key(|part| part.entries.first().map(|e| e.timestamp));
+    }
+
+    let ranges: Vec<Option<TimeRange>> =
+        parts.iter().map(|part| time_range(&part.entries)).collect();
+    let overlaps = find_overlaps(&parts, &ranges);
+
+    let mut entries: Vec<Arc<ColumnEntry>> =
+        Vec::with_capacity(parts.iter().map(|part| part.entries.len()).sum());
+    let mut sources = Vec::with_capacity(parts.len());
+    for (part, range) in parts.into_iter().zip(ranges) {
+        entries.extend(part.entries);
+        sources.push(SourceSummary {
+            source: part.source,
+            range,
+            summary: part.summary,
+        });
+    }
+
+    // Overlapping sources can hold the same sample twice, the first one is kept
+    entries.sort_by_key(|entry| entry.timestamp);
+    let before = entries.len();
+    entries.dedup_by_key(|entry| entry.timestamp);
+    let duplicates_dropped = (before - entries.len()) as u32;
+
+    let gaps = find_gaps(&entries);
+
+    // Progress and distilled mass were relative to each part, restate them for the whole run
+    let total = entries.len();
+    let masses = calculation_service.calculate_distilled_masses(
+        initial_charge,
+        entries.iter().map(|entry| entry.compositions.as_slice()),
+    );
+    for (index, (entry, distilled_mass)) in entries.iter_mut().zip(masses).enumerate() {
+        let entry = Arc::make_mut(entry);
+        entry.percentage_complete = (index + 1) as f64 / total as f64 * 100.0;
+        entry.distilled_mass = distilled_mass;
+    }
+
+    info!(
+        "Merged {} sources into {} entries, {} overlaps, {} gaps",
+        sources.len(),
+        entries.len(),
+        overlaps.len(),
+        gaps.len()
+    );
+
+    let summary = MergeSummary {
+        number_plates,
+        sources,
+        entry_count: entries.len() as u32,
+        duplicates_dropped,
+        overlaps,
+        gaps,
+    };
+
+    Ok((entries, summary))
+}
+
+/// Shifts every part to start one sample interval after the previous one ended.
+fn chain_relative(parts: &mut [ImportedPart]) {
+    let mut next_start: Option<u64> = None;
+    for part in parts.iter_mut() {
+        let Some(first) = part.entries.first().map(|e| e.timestamp) else {
+            continue;
+        };
+
+        if let Some(start) = next_start {
+            for entry in part.entries.iter_mut() {
+                let entry = Arc::make_mut(entry);
+                entry.timestamp = entry.timestamp.saturating_sub(first) + start;
+            }
+        }
+
+        if let Some(last) = part.entries.last().map(|e| e.timestamp) {
+            next_start = Some(last + typical_interval(&part.entries).unwrap_or(1000));
+        }
+    }
+}
+
+fn find_overlaps(parts: &[ImportedPart], ranges: &[Option<TimeRange>]) -> Vec<SourceOverlap> {
+    let mut overlaps = Vec::new();
+    for (i, first) in ranges.iter().enumerate() {
+        for (j, second) in ranges.iter().enumerate().skip(i + 1) {
+            if let (Some(first), Some(second)) = (first, second) {
+                if second.from <= first.to {
+                    overlaps.push(SourceOverlap {
+                        first: parts[i].source.label(),
+                        second: parts[j].source.label(),
+                        range: TimeRange {
+                            from: second.from,
+                            to: first.to.min(second.to),
+                        },
+                    });
+                }
+            }
+        }
+    }
+    overlaps
+}
+
+fn find_gaps(entries: &[Arc<ColumnEntry>]) -> Vec<TimeRange> {
+    let Some(interval) = typical_interval(entries) else {
+        return Vec::new();
+    };
+    let threshold = interval.max(1) * GAP_FACTOR;
+
+    entries
+        .windows(2)
+        .filter(|pair| pair[1].timestamp - pair[0].timestamp > threshold)
+        .map(|pair| TimeRange {
+            from: pair[0].timestamp,
+            to: pair[1].timestamp,
+        })
+        .collect()
+}
+
+fn time_range(entries: &[Arc<ColumnEntry>]) -> Option<TimeRange> {
+    Some(TimeRange {
+        from: entries.first()?.timestamp,
+        to: entries.last()?.timestamp,
+    })
+}
@@ -1,3 +1,7 @@
 pub mod excel;
+pub mod merge;
+pub mod tests;
+pub mod timestamps;
+pub mod types;
 
-pub use excel::ExcelDataImporter;
+pub use excel::{ExcelDataExporter, ExcelDataImporter};
@@ -0,0 +1,472 @@
+#[cfg(test)]
+mod tests {
+    use crate::calculations::service::CalculationService;
+    use crate::data_manager::import_export::timestamps::{detect_format, parse_timestamp};
+    use crate::data_manager::import_export::types::{
+        ColumnMapping, ImportSource, InvalidRowPolicy, PendingImport, RowResolution,
+        TimestampFormat,
+    };
+    use crate::data_manager::import_export::{ExcelDataExporter, ExcelDataImporter};
+    use crate::data_manager::types::{Quality, QualityReason};
+    use crate::errors::{Error, ImportError};
+    use calamine::Data;
+    use chrono_tz::Tz;
+    use rust_xlsxwriter::{ExcelDateTime, Format, Workbook};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn write_workbook(name: &str, rows: &[(f64, Option<f64>, f64)]) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}.xlsx", name, std::process::id()));
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.write(0, 0, "Timestamp").unwrap();
+        worksheet.write(0, 1, "Temperature 1").unwrap();
+        worksheet.write(0, 2, "Temperature 2").unwrap();
+        for (i, &(timestamp, t_1, t_2)) in rows.iter().enumerate() {
+            let row = i as u32 + 1;
+            worksheet.write(row, 0, timestamp).unwrap();
+            if let Some(t_1) = t_1 {
+                worksheet.write(row, 1, t_1).unwrap();
+            }
+            worksheet.write(row, 2, t_2).unwrap();
+        }
+        workbook.save(&path).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_import_reports_skipped_rows_and_progress() {
+        let path = write_workbook(
+            "import-summary",
+            &[
+                (0.0, Some(92.0), 78.0),
+                (1.0, None, 78.5),
+                (2.0, Some(92.4), 79.0),
+            ],
+        );
+        let importer = ExcelDataImporter::new(CalculationService::new());
+        let cancel = AtomicBool::new(false);
+        let mut progress = Vec::new();
+
+        let (entries, summary) = importer
+            .import(&path, None, InvalidRowPolicy::Skip, &cancel, |p| {
+                progress.push(p.percentage)
+            })
+            .unwrap();
+
+        assert_eq!(summary.number_plates, 2);
+        assert_eq!(summary.rows_read, 3);
+        assert_eq!(summary.rows_imported, 2);
+        assert_eq!(summary.rows_skipped, 1);
+        assert_eq!(summary.diagnostics[0].row, 3);
+        assert_eq!(summary.diagnostics[0].column, Some(1));
+        assert_eq!(summary.diagnostics[0].resolution, RowResolution::Skipped);
+        assert_eq!(entries[1].timestamp, 2000);
+        assert_eq!(progress.last().copied(), Some(100.0));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_single_file_is_ordered_by_timestamp() {
+        let path = write_workbook(
+            "import-order",
+            &[
+                (2.0, Some(92.4), 79.0),
+                (0.0, Some(92.0), 78.0),
+                (2.0, Some(93.0), 79.5),
+                (1.0, Some(92.2), 78.5),
+            ],
+        );
+        let importer = ExcelDataImporter::new(CalculationService::new());
+        let cancel = AtomicBool::new(false);
+
+        let (entries, summary) = importer
+            .import(&path, None, InvalidRowPolicy::Skip, &cancel, |_| {})
+            .unwrap();
+
+        let timestamps: Vec<u64> = entries.iter().map(|entry| entry.timestamp).collect();
+        assert_eq!(timestamps, vec![0, 1000, 2000]);
+        assert_eq!(entries[2].temperatures[0], 92.4);
+        assert_eq!(summary.rows_skipped, 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_invalid_rows_follow_policy() {
+        let path = write_workbook(
+            "import-policy",
+            &[
+                (0.0, Some(80.0), 76.0),
+                (1.0, None, 76.5),
+                (2.0, Some(81.0), 77.0),
+            ],
+        );
+        let importer = ExcelDataImporter::new(CalculationService::new());
+        let cancel = AtomicBool::new(false);
+        let import = |policy| importer.import(&path, None, policy, &cancel, |_| {});
+
+        let (entries, summary) = import(InvalidRowPolicy::FillForward).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].temperatures, vec![80.0, 76.5]);
+        assert!(entries[1].compositions[0].x_1.is_some());
+        assert_eq!(
+            entries[1].temperature_quality(0),
+            Quality::Uncertain(QualityReason::FilledForward)
+        );
+        assert!(entries[1].temperature_quality(1).is_good());
+        assert!(entries[0].quality.is_good());
+        assert_eq!(summary.rows_repaired, 1);
+        assert_eq!(
+            summary.diagnostics[0].resolution,
+            RowResolution::FilledForward
+        );
+
+        let (entries, _) = import(InvalidRowPolicy::Interpolate).unwrap();
+        assert_eq!(entries[1].temperatures, vec![80.5, 76.5]);
+        assert_eq!(
+            entries[1].composition_quality(0),
+            Quality::Uncertain(QualityReason::TimeInterpolation)
+        );
+
+        assert!(import(InvalidRowPolicy::Abort).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_import_can_be_cancelled() {
+        let path = write_workbook("import-cancel", &[(0.0, Some(92.0), 78.0)]);
+        let importer = ExcelDataImporter::new(CalculationService::new());
+        let cancel = AtomicBool::new(false);
+        let mut pending = None;
+        let imported = importer.import(&path, None, InvalidRowPolicy::Skip, &cancel, |_| {});
+        PendingImport::stage(&mut pending, "first".into(), imported, |s| s.number_plates).unwrap();
+        assert!(pending.is_some());
+
+        cancel.store(true, Ordering::Relaxed);
+        let mut progress = Vec::new();
+        let imported = importer.import(&path, None, InvalidRowPolicy::Skip, &cancel, |p| {
+            progress.push(p)
+        });
+        let staged =
+            PendingImport::stage(&mut pending, "second".into(), imported, |s| s.number_plates);
+        assert!(matches!(
+            staged,
+            Err(Error::ImportError(ImportError::Cancelled))
+        ));
+        assert!(progress.is_empty());
+        assert!(pending.is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_import_with_column_mapping() {
+        let path = std::env::temp_dir().join(format!("import-mapping-{}.xlsx", std::process::id()));
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        for (column, header) in ["Top", "Elapsed", "Bottom", "P mmHg"].iter().enumerate() {
+            worksheet.write(0, column as u16, *header).unwrap();
+        }
+        for row in 1..4u32 {
+            worksheet.write(row, 0, 75.0 + row as f64).unwrap();
+            worksheet.write(row, 1, row * 5).unwrap();
+            worksheet.write(row, 2, 80.0 + row as f64).unwrap();
+            worksheet.write(row, 3, 585.0).unwrap();
+        }
+        workbook.save(&path).unwrap();
+        let path = path.to_str().unwrap();
+
+        let importer = ExcelDataImporter::new(CalculationService::new());
+        let preview = importer.preview(path, 2).unwrap();
+        assert_eq!(preview.headers, vec!["Top", "Elapsed", "Bottom", "P mmHg"]);
+        assert_eq!(preview.rows.len(), 2);
+
+        let mapping = ColumnMapping {
+            timestamp: 1,
+            temperatures: vec![2, 0],
+            pressure: Some(3),
+            compositions_x: None,
+            compositions_y: None,
+            timestamp_format: None,
+        };
+        let cancel = AtomicBool::new(false);
+        let (entries, summary) = importer
+            .import(
+                path,
+                Some(&mapping),
+                InvalidRowPolicy::Skip,
+                &cancel,
+                |_| {},
+            )
+            .unwrap();
+
+        assert_eq!(summary.number_plates, 2);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].timestamp, 5000);
+        assert_eq!(entries[0].temperatures, vec![81.0, 76.0]);
+        assert_eq!(entries[0].pressure, Some(585.0));
+        assert!(entries[0].compositions[0].x_1.is_some());
+
+        // A composition without its vapour fraction is as bad as a solver failure
+        let liquid_only = ColumnMapping {
+            compositions_x: Some(vec![3, 3]),
+            ..mapping.clone()
+        };
+        let (entries, _) = importer
+            .import(
+                path,
+                Some(&liquid_only),
+                InvalidRowPolicy::Skip,
+                &cancel,
+                |_| {},
+            )
+            .unwrap();
+        assert_eq!(
+            entries[0].composition_quality(0),
+            Quality::Bad(QualityReason::MissingValue)
+        );
+        assert!(entries[0].temperature_quality(0).is_good());
+
+        let out_of_range = ColumnMapping {
+            temperatures: vec![2, 7],
+            ..mapping
+        };
+        assert!(importer
+            .import(
+                path,
+                Some(&out_of_range),
+                InvalidRowPolicy::Skip,
+                &cancel,
+                |_| {}
+            )
+            .is_err());
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_timestamp_formats_are_detected() {
+        let madrid: Tz = "Europe/Madrid".parse().unwrap();
+        let cases = [
+            (
+                Data::String("2024-07-27T10:30:00.250+02:00".into()),
+                TimestampFormat::Iso8601,
+                1_722_069_000_250,
+            ),
+            (
+                Data::String("2024-07-27 10:30:00".into()),
+                TimestampFormat::Iso8601,
+                1_722_069_000_000,
+            ),
+            (
+                Data::Float(1_722_069_000.5),
+                TimestampFormat::UnixSeconds,
+                1_722_069_000_500,
+            ),
+            (
+                Data::Int(1_722_069_000_000),
+                TimestampFormat::UnixMillis,
+                1_722_069_000_000,
+            ),
+            (
+                Data::Float(12.345),
+                TimestampFormat::RelativeSeconds,
+                12_345,
+            ),
+        ];
+
+        for (cell, format, expected) in cases {
+            assert_eq!(detect_format(&cell), Some(format));
+            assert_eq!(parse_timestamp(&cell, format, madrid), Some(expected));
+        }
+
+        // Plain serials keep their fraction of a day when the format is given
+        let serial = Data::Float(45500.4375);
+        assert_eq!(
+            parse_timestamp(&serial, TimestampFormat::ExcelDateTime, madrid),
+            Some(1_722_069_000_000)
+        );
+    }
+
+    #[test]
+    fn test_excel_datetimes_round_trip_in_time_zone() {
+        let path = std::env::temp_dir().join(format!("import-dates-{}.xlsx", std::process::id()));
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        let format = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+        worksheet.write(0, 0, "Timestamp").unwrap();
+        worksheet.write(0, 1, "Temperature 1").unwrap();
+        worksheet.write(0, 2, "Temperature 2").unwrap();
+        for row in 1..3u32 {
+            let serial = 45500.4375 + row as f64 / 86_400.0;
+            let datetime = ExcelDateTime::from_serial_datetime(serial).unwrap();
+            worksheet
+                .write_datetime_with_format(row, 0, datetime, &format)
+                .unwrap();
+            worksheet.write(row, 1, 80.0).unwrap();
+            worksheet.write(row, 2, 76.0).unwrap();
+        }
+        workbook.save(&path).unwrap();
+        let path = path.to_str().unwrap();
+
+        let madrid: Tz = "Europe/Madrid".parse().unwrap();
+        let importer = ExcelDataImporter::new(CalculationService::new()).with_time_zone(madrid);
+        let cancel = AtomicBool::new(false);
+        let (entries, summary) = importer
+            .import(path, None, InvalidRowPolicy::Skip, &cancel, |_| {})
+            .unwrap();
+
+        assert_eq!(
+            summary.timestamp_format,
+            Some(TimestampFormat::ExcelDateTime)
+        );
+        assert_eq!(entries[0].timestamp, 1_722_069_001_000);
+        assert_eq!(entries[1].timestamp, 1_722_069_002_000);
+
+        ExcelDataExporter::new()
+            .with_time_zone(madrid)
+            .export(&entries, path)
+            .unwrap();
+        let (reimported, _) = importer
+            .import(path, None, InvalidRowPolicy::Skip, &cancel, |_| {})
+            .unwrap();
+        assert_eq!(reimported[1].timestamp, entries[1].timestamp);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_sheets_and_files_are_merged_by_timestamp() {
+        let start = 1_722_069_000.0;
+        let path = std::env::temp_dir().join(format!("import-merge-{}.xlsx", std::process::id()));
+        let mut workbook = Workbook::new();
+        // The second shift is stored first, with one sample overlapping the first shift
+        for (name, offsets) in [("Shift 2", [2.0, 3.0, 30.0]), ("Shift 1", [0.0, 1.0, 2.0])] {
+            let worksheet = workbook.add_worksheet().set_name(name).unwrap();
+            worksheet.write(0, 0, "Timestamp").unwrap();
+            worksheet.write(0, 1, "Temperature 1").unwrap();
+            worksheet.write(0, 2, "Temperature 2").unwrap();
+            for (i, offset) in offsets.iter().enumerate() {
+                let row = i as u32 + 1;
+                worksheet.write(row, 0, start + offset).unwrap();
+                worksheet.write(row, 1, 80.0).unwrap();
+                worksheet.write(row, 2, 76.0).unwrap();
+            }
+        }
+        workbook.save(&path).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let importer = ExcelDataImporter::new(CalculationService::new());
+        assert_eq!(
+            importer.sheet_names(&path).unwrap(),
+            vec!["Shift 2", "Shift 1"]
+        );
+
+        let sources: Vec<ImportSource> = ["Shift 2", "Shift 1"]
+            .iter()
+            .map(|sheet| ImportSource {
+                path: path.clone(),
+                sheet: Some(sheet.to_string()),
+            })
+            .collect();
+        let cancel = AtomicBool::new(false);
+        let (entries, summary) = importer
+            .import_many(&sources, None, InvalidRowPolicy::Skip, &cancel, |_| {})
+            .unwrap();
+
+        let offsets: Vec<u64> = entries
+            .iter()
+            .map(|e| (e.timestamp - 1_722_069_000_000) / 1000)
+            .collect();
+        assert_eq!(offsets, vec![0, 1, 2, 3, 30]);
+        assert_eq!(summary.sources[0].source.sheet.as_deref(), Some("Shift 1"));
+        assert_eq!(summary.duplicates_dropped, 1);
+        assert_eq!(summary.overlaps.len(), 1);
+        assert_eq!(summary.gaps.len(), 1);
+        assert_eq!(entries.last().unwrap().percentage_complete, 100.0);
+
+        let other = write_workbook("import-merge-single", &[(0.0, Some(80.0), 76.0)]);
+        let mut three_plates = Workbook::new();
+        let worksheet = three_plates.add_worksheet();
+        for (column, header) in [
+            "Timestamp",
+            "Temperature 1",
+            "Temperature 2",
+            "Temperature 3",
+        ]
+        .iter()
+        .enumerate()
+        {
+            worksheet.write(0, column as u16, *header).unwrap();
+            worksheet.write(1, column as u16, 80.0).unwrap();
+        }
+        let three_path =
+            std::env::temp_dir().join(format!("import-merge-3-{}.xlsx", std::process::id()));
+        three_plates.save(&three_path).unwrap();
+        let mismatched = [
+            ImportSource {
+                path: other.clone(),
+                sheet: None,
+            },
+            ImportSource {
+                path: three_path.to_str().unwrap().to_string(),
+                sheet: None,
+            },
+        ];
+        assert!(importer
+            .import_many(&mismatched, None, InvalidRowPolicy::Skip, &cancel, |_| {})
+            .is_err());
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&other);
+        let _ = std::fs::remove_file(&three_path);
+    }
+
+    #[test]
+    fn test_distilled_mass_carries_across_merged_parts() {
+        // The still keeps heating up across two consecutive recordings
+        let first = write_workbook(
+            "import-mass-1",
+            &[
+                (0.0, Some(80.0), 76.0),
+                (1.0, Some(81.0), 76.0),
+                (2.0, Some(82.0), 76.0),
+            ],
+        );
+        let second = write_workbook(
+            "import-mass-2",
+            &[
+                (0.0, Some(83.0), 76.0),
+                (1.0, Some(84.0), 76.0),
+                (2.0, Some(85.0), 76.0),
+            ],
+        );
+        let sources: Vec<ImportSource> = [&first, &second]
+            .iter()
+            .map(|path| ImportSource {
+                path: path.to_string(),
+                sheet: None,
+            })
+            .collect();
+
+        let importer = ExcelDataImporter::new(CalculationService::new());
+        let cancel = AtomicBool::new(false);
+        let (entries, _) = importer
+            .import_many(&sources, None, InvalidRowPolicy::Skip, &cancel, |_| {})
+            .unwrap();
+
+        let masses: Vec<f64> = entries.iter().map(|e| e.distilled_mass).collect();
+        assert_eq!(masses.len(), 6);
+        assert!(
+            masses.windows(2).all(|pair| pair[1] >= pair[0]),
+            "{:?}",
+            masses
+        );
+        assert!(masses[5] > masses[2]);
+
+        let _ = std::fs::remove_file(&first);
+        let _ = std::fs::remove_file(&second);
+    }
+}
@@ -0,0 +1,127 @@
+use super::types::TimestampFormat;
+use calamine::{Data, DataType};
+use chrono::{DateTime, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use rust_xlsxwriter::{ExcelDateTime, Format, Worksheet, XlsxError};
+
+/// Entry timestamps are milliseconds since the Unix epoch. Runs imported with
+/// relative times start at zero and stay below this bound.
+const RELATIVE_LIMIT_MS: u64 = 100_000_000_000;
+const UNIX_SECONDS_MIN: f64 = 100_000_000.0;
+
+/// Days between the Excel epoch (1899-12-30) and the Unix epoch
+const EXCEL_UNIX_EPOCH_DAYS: f64 = 25_569.0;
+const MS_PER_DAY: f64 = 86_400_000.0;
+
+const ISO_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Guesses the encoding of a timestamp cell. Plain numbers are told apart by
+/// magnitude, Excel serials stored without a date format need an explicit format.
+pub fn detect_format(cell: &Data) -> Option<TimestampFormat> {
+    match cell {
+        Data::DateTime(_) => Some(TimestampFormat::ExcelDateTime),
+        Data::DateTimeIso(_) => Some(TimestampFormat::Iso8601),
+        Data::String(s) if s.trim().parse::<f64>().is_err() => {
+            parse_iso(s, Tz::UTC).map(|_| TimestampFormat::Iso8601)
+        }
+        _ => {
+            let value = cell.as_f64()?;
+            if value >= RELATIVE_LIMIT_MS as f64 {
+                Some(TimestampFormat::UnixMillis)
+            } else if value >= UNIX_SECONDS_MIN {
+                Some(TimestampFormat::UnixSeconds)
+            } else {
+                Some(TimestampFormat::RelativeSeconds)
+            }
+        }
+    }
+}
+
+pub fn parse_timestamp(cell: &Data, format: TimestampFormat, time_zone: Tz) -> Option<u64> {
+    match format {
+        TimestampFormat::ExcelDateTime => {
+            let serial = match cell {
+                Data::DateTime(datetime) => datetime.as_f64(),
+                _ => cell.as_f64()?,
+            };
+            from_excel_serial(serial, time_zone)
+        }
+        TimestampFormat::Iso8601 => match cell {
+            Data::String(s) | Data::DateTimeIso(s) => parse_iso(s, time_zone),
+            _ => None,
+        },
+        TimestampFormat::UnixSeconds | TimestampFormat::RelativeSeconds => {
+            to_millis(cell.as_f64()? * 1000.0)
+        }
+        TimestampFormat::UnixMillis => to_millis(cell.as_f64()?),
+    }
+}
+
+pub fn is_relative(timestamp: u64) -> bool {
+    timestamp < RELATIVE_LIMIT_MS
+}
+
+/// Converts a timestamp into an Excel serial date showing the wall time of `time_zone`.
+pub fn to_excel_serial(timestamp: u64, time_zone: Tz) -> Option<f64> {
+    let local = time_zone
+        .timestamp_millis_opt(timestamp as i64)
+        .single()?
+        .naive_local();
+
+    Some(local.and_utc().timestamp_millis() as f64 / MS_PER_DAY + EXCEL_UNIX_EPOCH_DAYS)
+}
+
+/// Writes a timestamp as a date in the wall time of `time_zone` with `format`,
+/// or as elapsed seconds when it is relative.
+pub fn write_timestamp(
+    worksheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    timestamp: u64,
+    time_zone: Tz,
+    format: &Format,
+) -> Result<(), XlsxError> {
+    match to_excel_serial(timestamp, time_zone).filter(|_| !is_relative(timestamp)) {
+        Some(serial) => {
+            let datetime = ExcelDateTime::from_serial_datetime(serial)?;
+            worksheet.write_datetime_with_format(row, col, datetime, format)?;
+        }
+        None => {
+            worksheet.write(row, col, timestamp as f64 / 1000.0)?;
+        }
+    }
+    Ok(())
+}
+
+fn from_excel_serial(serial: f64, time_zone: Tz) -> Option<u64> {
+    let millis = ((serial - EXCEL_UNIX_EPOCH_DAYS) * MS_PER_DAY).round() as i64;
+    let local = DateTime::from_timestamp_millis(millis)?.naive_utc();
+    from_local(local, time_zone)
+}
+
+fn parse_iso(value: &str, time_zone: Tz) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return u64::try_from(datetime.timestamp_millis()).ok();
+    }
+
+    ISO_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .and_then(|local| from_local(local, time_zone))
+}
+
+fn from_local(local: NaiveDateTime, time_zone: Tz) -> Option<u64> {
+    // Wall times repeated by a DST change resolve to the first occurrence
+    let datetime = time_zone.from_local_datetime(&local).earliest()?;
+    u64::try_from(datetime.timestamp_millis()).ok()
+}
+
+fn to_millis(value: f64) -> Option<u64> {
+    (value.is_finite() && value >= 0.0).then(|| value.round() as u64)
+}
@@ -0,0 +1,188 @@
+use crate::data_manager::types::ColumnEntry;
+use crate::errors::Result;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportProgress {
+    pub rows_read: u32,
+    pub total_rows: u32,
+    pub percentage: f64,
+}
+
+/// What the importer does with a row holding a missing or non-numeric temperature.
+/// Rows without a usable timestamp are always skipped, or abort the import.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum InvalidRowPolicy {
+    #[default]
+    Skip,
+    FillForward,
+    Interpolate,
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum RowResolution {
+    Skipped,
+    FilledForward,
+    Interpolated,
+}
+
+/// One invalid cell. `row` is one based like the sheet, `column` is zero based
+/// like `ColumnMapping`.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RowDiagnostic {
+    pub row: u32,
+    pub column: Option<u32>,
+    pub reason: String,
+    pub raw_value: Option<String>,
+    pub resolution: RowResolution,
+}
+
+/// Validation report of an import, returned before the data is committed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub number_plates: u32,
+    pub policy: InvalidRowPolicy,
+    pub rows_read: u32,
+    pub rows_imported: u32,
+    pub rows_skipped: u32,
+    pub rows_repaired: u32,
+    pub diagnostics: Vec<RowDiagnostic>,
+    pub timestamp_format: Option<TimestampFormat>,
+    pub duration_ms: u64,
+}
+
+/// How the timestamp column of a sheet is encoded. Dates without an explicit
+/// offset are read in the configured time zone.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TimestampFormat {
+    ExcelDateTime,
+    Iso8601,
+    UnixSeconds,
+    UnixMillis,
+    RelativeSeconds,
+}
+
+/// Zero based column indices of a sheet. Temperatures are listed from the bottom
+/// plate to the top one, compositions follow the same order. The pressure column
+/// is read in the same unit as `EquationParams::p`.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnMapping {
+    pub timestamp: u32,
+    pub temperatures: Vec<u32>,
+    pub pressure: Option<u32>,
+    pub compositions_x: Option<Vec<u32>>,
+    pub compositions_y: Option<Vec<u32>>,
+    /// Detected from the first data row when not set
+    #[serde(default)]
+    pub timestamp_format: Option<TimestampFormat>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NamedColumnMapping {
+    pub name: String,
+    pub mapping: ColumnMapping,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub suggested_mapping: Option<ColumnMapping>,
+}
+
+/// A workbook to import. Without a sheet name the first sheet is read.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSource {
+    pub path: String,
+    pub sheet: Option<String>,
+}
+
+impl ImportSource {
+    pub fn label(&self) -> String {
+        let file = Path::new(&self.path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&self.path);
+
+        match &self.sheet {
+            Some(sheet) => format!("{} [{}]", file, sheet),
+            None => file.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeRange {
+    pub from: u64,
+    pub to: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceSummary {
+    pub source: ImportSource,
+    pub range: Option<TimeRange>,
+    pub summary: ImportSummary,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceOverlap {
+    pub first: String,
+    pub second: String,
+    pub range: TimeRange,
+}
+
+/// Report of a multi source import. Sources are listed in the order they were merged.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeSummary {
+    pub number_plates: u32,
+    pub sources: Vec<SourceSummary>,
+    pub entry_count: u32,
+    pub duplicates_dropped: u32,
+    pub overlaps: Vec<SourceOverlap>,
+    pub gaps: Vec<TimeRange>,
+}
+
+/// An import waiting for the user to accept its validation report.
+pub struct PendingImport {
+    pub name: String,
+    pub number_plates: u32,
+    pub entries: Vec<Arc<ColumnEntry>>,
+}
+
+impl PendingImport {
+    /// Stages the outcome of an import. A failed or cancelled import clears the
+    /// one waiting, so an older file is never committed in its place.
+    pub fn stage<S>(
+        pending: &mut Option<PendingImport>,
+        name: String,
+        imported: Result<(Vec<Arc<ColumnEntry>>, S)>,
+        number_plates: impl FnOnce(&S) -> u32,
+    ) -> Result<S> {
+        *pending = None;
+        let (entries, summary) = imported?;
+        *pending = Some(PendingImport {
+            name,
+            number_plates: number_plates(&summary),
+            entries,
+        });
+        Ok(summary)
+    }
+}
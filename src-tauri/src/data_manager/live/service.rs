@@ -1,12 +1,24 @@
 use crate::calculations::service::CalculationService;
 use crate::calculations::types::CompositionResult;
+use crate::data_manager::import_export::types::TimeRange;
 use crate::data_manager::provider::DataProvider;
-use crate::data_manager::types::ColumnEntry;
+use crate::data_manager::types::{
+    ColumnEntry, EntryQuality, Measurement, PlaybackDelay, PlaybackPosition, Quality,
+    QualityReason, SeekTarget,
+};
+use crate::diagnostics::types::{ChannelReading, RawReading, SensorFault};
+use crate::diagnostics::SensorMonitor;
 use crate::errors::{DataError, Result};
 use crate::modbus::client::ModbusClient;
+use crate::modbus::register_map::{read_blocks, RegisterValues};
 use crate::modbus::service::ModbusService;
+use crate::modbus::types::{
+    ByteOrder, PointRole, RegisterDataType, RegisterFunction, RegisterPoint, WordOrder,
+};
+use crate::settings::types::ModbusSettings;
 use async_trait::async_trait;
-use rodbus::client::Channel;
+use log::{info, warn};
+use rodbus::client::{Channel, RequestParam};
 use rodbus::{AddressRange, UnitId};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
@@ -17,51 +29,214 @@ pub struct LiveDataProvider {
     modbus_channel: Arc<Mutex<Option<Channel>>>,
     calculation_service: Arc<CalculationService>,
     modbus_service: Arc<ModbusService<ModbusClient>>,
-    history: Vec<Arc<ColumnEntry>>,
+    settings: ModbusSettings,
+    /// Newest entry, held when a read fails
+    last: Option<Arc<ColumnEntry>>,
+    monitor: SensorMonitor,
+    /// Fault changes not yet taken by the emitter
+    faults: Vec<SensorFault>,
 }
 
 impl LiveDataProvider {
     pub fn new(
         modbus_channel: Arc<Mutex<Option<Channel>>>,
         calculation_service: Arc<CalculationService>,
-        modbus_service: Arc<ModbusService<ModbusClient>>,
+        settings: ModbusSettings,
+        monitor: SensorMonitor,
     ) -> Self {
         Self {
-            modbus_service,
+            modbus_service: Arc::new(ModbusService::new(ModbusClient::from_settings(&settings))),
+            settings,
             calculation_service,
             modbus_channel,
-            history: Vec::new(),
+            last: None,
+            monitor,
+            faults: Vec::new(),
         }
     }
 }
 
+impl LiveDataProvider {
+    /// The configured map, or one register per plate from the bottom up in
+    /// hundredths of °C. Fewer registers than plates are spread from the bottom
+    /// plate to the top one, the plates in between are interpolated.
+    fn register_points(&self, number_plates: i32) -> Vec<RegisterPoint> {
+        if !self.settings.register_map.is_empty() {
+            return self.settings.register_map.clone();
+        }
+
+        let plates = number_plates.max(1) as u32;
+        let registers = (self.settings.count.max(1) as u32).min(plates);
+        (0..registers)
+            .map(|i| {
+                let plate = match registers {
+                    1 => 0,
+                    _ => (i * (plates - 1) + (registers - 1) / 2) / (registers - 1),
+                };
+                (plate, self.settings.initial_address + i as u16)
+            })
+            .map(|(plate, address)| RegisterPoint {
+                name: format!("Plate {}", plate + 1),
+                address,
+                function: RegisterFunction::Holding,
+                data_type: RegisterDataType::U16,
+                word_order: WordOrder::HighFirst,
+                byte_order: ByteOrder::BigEndian,
+                scale: 0.01,
+                offset: 0.0,
+                unit: "°C".into(),
+                role: PointRole::PlateTemperature { plate },
+            })
+            .collect()
+    }
+
+    async fn read_registers(
+        &self,
+        channel: &mut Channel,
+        param: RequestParam,
+        points: &[RegisterPoint],
+    ) -> Result<RegisterValues> {
+        let mut values = RegisterValues::default();
+        for block in read_blocks(points) {
+            let range = AddressRange::try_from(block.start, block.count)
+                .map_err(|e| DataError::CustomError(format!("Invalid register range: {}", e)))?;
+            let registers = match block.function {
+                RegisterFunction::Holding => {
+                    self.modbus_service
+                        .read_holding_registers(channel, param, range)
+                        .await?
+                }
+                RegisterFunction::Input => {
+                    self.modbus_service
+                        .read_input_registers(channel, param, range)
+                        .await?
+                }
+            };
+            values.insert(block.function, &registers);
+        }
+        Ok(values)
+    }
+}
+
 #[async_trait]
 impl DataProvider for LiveDataProvider {
     async fn get_next_entry(&mut self, number_plates: i32) -> Result<Arc<ColumnEntry>> {
-        let initial_mass = 1000.0;
-
         let mut channel_guard = self.modbus_channel.lock().await;
         let channel = channel_guard
             .as_mut()
             .ok_or_else(|| DataError::CustomError("No Modbus channel available".into()))?;
 
-        // Modbus parameters
         let param = rodbus::client::RequestParam {
-            id: UnitId::new(10),
-            response_timeout: Duration::from_millis(1000),
+            id: UnitId::new(self.settings.unit_id),
+            response_timeout: Duration::from_millis(self.settings.timeout as u64),
         };
-        let address = AddressRange::try_from(100, 2).unwrap();
+        let points = self.register_points(number_plates);
+        let read = self.read_registers(channel, param, &points).await;
+        drop(channel_guard);
 
-        let temperatures = self
-            .modbus_service
-            .read_holding_registers(channel, param, address)
-            .await?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
 
-        let inter_temps = self.calculation_service.interpolate_temps(
-            number_plates,
-            temperatures[0].value as f64 / 100.0,
-            temperatures[1].value as f64 / 100.0,
-        );
+        let mut readings = Vec::new();
+        let mut unreadable = Vec::new();
+        let mut pressure = None;
+        let mut measurements = Vec::new();
+        if let Ok(values) = &read {
+            for point in &points {
+                let Some(registers) = values.registers(point) else {
+                    continue;
+                };
+                let in_column = |plate: u32| (plate as i32) < number_plates;
+                let Some(value) = point.decode(&registers) else {
+                    if let PointRole::PlateTemperature { plate } = point.role {
+                        if in_column(plate) {
+                            unreadable.push(plate);
+                        }
+                    }
+                    continue;
+                };
+                match point.role {
+                    PointRole::PlateTemperature { plate } if in_column(plate) => {
+                        let raw = point.raw(&registers).unwrap_or_default();
+                        readings.push(ChannelReading {
+                            plate,
+                            raw: match point.register_count() {
+                                1 => RawReading::Word(raw as u16),
+                                _ => RawReading::DoubleWord(raw),
+                            },
+                            temperature: value,
+                        });
+                    }
+                    PointRole::Pressure => pressure = Some(value),
+                    _ => {}
+                }
+                measurements.push(Measurement {
+                    name: point.name.clone(),
+                    role: point.role,
+                    value,
+                    unit: point.unit.clone(),
+                });
+            }
+        }
+
+        // A failed read holds the last known values instead of ending the run
+        let (measured, read_quality) = if !readings.is_empty() {
+            // The register map can list the plates in any order
+            readings.sort_by_key(|reading| reading.plate);
+            let faults = self.monitor.check(timestamp, &readings);
+            self.faults.extend(faults);
+            let measured: Vec<(u32, f64)> = readings
+                .iter()
+                .map(|reading| (reading.plate, reading.temperature))
+                .collect();
+            (measured, Quality::Good)
+        } else {
+            let last = self.last.as_ref().ok_or_else(|| match read {
+                Err(e) => e,
+                Ok(_) => DataError::CustomError("No plate temperature was read".into()).into(),
+            })?;
+            warn!("Modbus read failed, holding the last values");
+            pressure = last.pressure;
+            measurements = last.measurements.clone();
+            let measured = last
+                .temperatures
+                .iter()
+                .enumerate()
+                .map(|(plate, &temp)| (plate as u32, temp))
+                .collect();
+            (measured, Quality::Bad(QualityReason::CommunicationFailure))
+        };
+
+        let inter_temps = self
+            .calculation_service
+            .interpolate_profile(number_plates, &measured);
+
+        // Plates without a sensor of their own are no better than the sensors
+        // they are interpolated from
+        let faulty = self.monitor.faulty_plates();
+        let sensor_fault = Quality::Bad(QualityReason::SensorFault);
+        let temperature_quality: Vec<Quality> = (0..inter_temps.len() as u32)
+            .map(|plate| {
+                let measured = measured.iter().any(|&(at, _)| at == plate);
+                match read_quality {
+                    // The sensor sent a value that is not a number
+                    Quality::Good if unreadable.contains(&plate) => {
+                        Quality::Bad(QualityReason::MissingValue)
+                    }
+                    Quality::Good if measured && faulty.contains(&plate) => sensor_fault,
+                    Quality::Good if !measured && !faulty.is_empty() => sensor_fault,
+                    Quality::Good if self.monitor.has_profile_fault() => {
+                        Quality::Uncertain(QualityReason::SensorFault)
+                    }
+                    Quality::Good if !measured => {
+                        Quality::Uncertain(QualityReason::PlateInterpolation)
+                    }
+                    quality => quality,
+                }
+            })
+            .collect();
 
         let mut compositions = Vec::with_capacity(number_plates as usize);
         for &temp in &inter_temps {
@@ -74,44 +249,22 @@ impl DataProvider for LiveDataProvider {
                 });
             compositions.push(composition);
         }
-
-        let mut distilled_mass = 0.0;
-        if self.history.len() > 1 {
-            if let (Some(first_entry), Some(last_entry)) =
-                (self.history.first(), self.history.last())
-            {
-                if let (Some(first_comp), Some(last_comp)) = (
-                    first_entry.compositions.first(),
-                    last_entry.compositions.last(),
-                ) {
-                    if let (Some(x_b0), Some(x_bf), Some(x_d)) = (
-                        first_comp.x_1,
-                        last_entry.compositions.first().and_then(|c| c.x_1),
-                        last_comp.y_1,
-                    ) {
-                        distilled_mass = self.calculation_service.calculate_distilled_mass(
-                            initial_mass,
-                            x_b0,
-                            x_bf,
-                            x_d,
-                        );
-                    }
-                }
-            }
-        }
+        let quality = EntryQuality::solved(temperature_quality, &compositions);
 
         let entry = Arc::new(ColumnEntry {
-            timestamp: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
+            timestamp,
             temperatures: inter_temps,
             compositions,
             percentage_complete: 0.0,
-            distilled_mass,
+            // Taken from the KPI balance when the entry is emitted
+            distilled_mass: 0.0,
+            pressure,
+            quality,
+            run: None,
+            measurements,
         });
 
-        self.history.push(entry.clone());
+        self.last = Some(entry.clone());
 
         Ok(entry)
     }
@@ -120,8 +273,40 @@ impl DataProvider for LiveDataProvider {
         Ok(())
     }
 
+    fn skip_time(&mut self, _millis: i64) -> Result<()> {
+        Ok(())
+    }
+
+    fn seek(&mut self, _target: SeekTarget) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_range(&mut self, _range: Option<TimeRange>) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_looping(&mut self, _looping: bool) {}
+
+    fn set_reverse(&mut self, _reverse: bool) {}
+
+    fn step(&mut self, _forward: bool) -> Result<Option<Arc<ColumnEntry>>> {
+        Ok(None)
+    }
+
+    fn position(&self) -> Option<PlaybackPosition> {
+        None
+    }
+
+    fn next_delay(&self) -> Option<PlaybackDelay> {
+        None
+    }
+
+    fn take_faults(&mut self) -> Vec<SensorFault> {
+        std::mem::take(&mut self.faults)
+    }
+
     fn reset(&mut self) -> Result<()> {
-        self.history.clear();
+        self.last = None;
         Ok(())
     }
 
@@ -129,6 +314,27 @@ impl DataProvider for LiveDataProvider {
         0
     }
 
+    fn is_live(&self) -> bool {
+        true
+    }
+
+    fn apply_settings(&mut self, settings: &ModbusSettings) {
+        let line_changed = settings.transport != self.settings.transport
+            || settings.usb_port != self.settings.usb_port
+            || settings.baudrate != self.settings.baudrate
+            || settings.serial != self.settings.serial
+            || settings.retry != self.settings.retry;
+        if line_changed {
+            warn!("The new connection settings take effect on the next connect");
+        }
+        info!(
+            "Reading {} registers from {} of unit {}",
+            settings.count, settings.initial_address, settings.unit_id
+        );
+        self.modbus_service = Arc::new(ModbusService::new(ModbusClient::from_settings(settings)));
+        self.settings = settings.clone();
+    }
+
     async fn disconnect(&self) -> Result<()> {
         let mut channel_guard = self.modbus_channel.lock().await;
         if let Some(channel) = channel_guard.take() {
@@ -140,9 +346,12 @@ impl DataProvider for LiveDataProvider {
     fn clone_provider(&self) -> Box<dyn DataProvider + Send> {
         Box::new(Self {
             calculation_service: self.calculation_service.clone(),
-            history: self.history.clone(),
+            last: self.last.clone(),
             modbus_channel: self.modbus_channel.clone(),
             modbus_service: self.modbus_service.clone(),
+            settings: self.settings.clone(),
+            monitor: self.monitor.clone(),
+            faults: self.faults.clone(),
         })
     }
 }
@@ -3,4 +3,5 @@ pub mod import_export;
 pub mod live;
 pub mod playback;
 pub mod provider;
+pub mod recompute;
 pub mod types;
@@ -1,3 +1,4 @@
 pub mod service;
+pub mod tests;
 
 pub use service::PlaybackDataProvider;
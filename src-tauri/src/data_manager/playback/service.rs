@@ -1,82 +1,284 @@
+use crate::data_manager::import_export::types::TimeRange;
 use crate::data_manager::provider::DataProvider;
-use crate::data_manager::types::ColumnEntry;
+use crate::data_manager::types::{
+    typical_interval, ColumnEntry, PlaybackDelay, PlaybackPosition, SeekTarget, GAP_FACTOR,
+};
+use crate::diagnostics::types::SensorFault;
 use crate::errors::{DataError, Result};
+use crate::settings::types::ModbusSettings;
 use async_trait::async_trait;
+use log::info;
 use std::sync::Arc;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PlaybackDataProvider {
     data: Vec<Arc<ColumnEntry>>,
-    current_index: usize,
+    /// Next entry to emit, `None` once the end of the range is reached
+    next_index: Option<usize>,
+    last_emitted: Option<usize>,
+    /// In and out points as inclusive indices
+    range: (usize, usize),
+    looping: bool,
+    reverse: bool,
+    interval: Option<u64>,
 }
 
 impl PlaybackDataProvider {
     pub fn new(data: Vec<Arc<ColumnEntry>>) -> Self {
-        Self {
-            data,
-            current_index: 0,
-        }
+        Self::with_index(data, 0)
     }
 
     pub fn with_index(data: Vec<Arc<ColumnEntry>>, index: usize) -> Self {
+        let interval = typical_interval(&data);
+        let range = (0, data.len().saturating_sub(1));
         Self {
+            next_index: (index < data.len()).then_some(index),
             data,
-            current_index: index,
+            last_emitted: None,
+            range,
+            looping: false,
+            reverse: false,
+            interval,
+        }
+    }
+
+    /// Where playback starts in the current direction
+    fn first_index(&self) -> usize {
+        if self.reverse {
+            self.range.1
+        } else {
+            self.range.0
+        }
+    }
+
+    /// The entry after `index` in the current direction, if it is inside the range
+    fn following(&self, index: usize) -> Option<usize> {
+        if self.reverse {
+            index.checked_sub(1).filter(|&i| i >= self.range.0)
+        } else {
+            Some(index + 1).filter(|&i| i <= self.range.1)
         }
     }
+
+    fn clamp(&self, index: usize) -> usize {
+        index.clamp(self.range.0, self.range.1)
+    }
+
+    fn index_at(&self, timestamp: u64) -> usize {
+        self.data
+            .partition_point(|entry| entry.timestamp < timestamp)
+            .min(self.data.len() - 1)
+    }
+
+    fn ensure_data(&self) -> Result<()> {
+        if self.data.is_empty() {
+            return Err(DataError::EmptyDataError.into());
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl DataProvider for PlaybackDataProvider {
     async fn get_next_entry(&mut self, _number_plates: i32) -> Result<Arc<ColumnEntry>> {
-        if self.data.is_empty() {
-            return Err(DataError::EmptyDataError.into());
-        }
+        self.ensure_data()?;
 
-        if self.current_index >= self.data.len() {
-            return Err(DataError::NoDataError.into());
-        }
+        let index = match self.next_index {
+            Some(index) => index,
+            None if self.looping => self.first_index(),
+            None => return Err(DataError::NoDataError.into()),
+        };
 
-        let entry = self.data[self.current_index].clone();
-        self.current_index += 1;
+        let entry = self.data[index].clone();
+        self.last_emitted = Some(index);
+        self.next_index = self.following(index);
         Ok(entry)
     }
 
     fn skip(&mut self, count: i64) -> Result<()> {
-        if self.data.is_empty() {
-            return Err(DataError::EmptyDataError.into());
-        }
+        self.ensure_data()?;
 
+        let current = self.next_index.unwrap_or(self.first_index());
         let new_index = if count.is_negative() {
-            self.current_index
-                .saturating_sub(count.unsigned_abs() as usize)
+            current.saturating_sub(count.unsigned_abs() as usize)
         } else {
-            self.current_index
-                .saturating_add(count as usize)
-                .min(self.data.len() - 1)
+            current.saturating_add(count as usize)
         };
 
-        self.current_index = new_index;
+        self.next_index = Some(self.clamp(new_index));
         Ok(())
     }
 
+    fn skip_time(&mut self, millis: i64) -> Result<()> {
+        self.ensure_data()?;
+
+        // Skips count from the entry on screen, which is the last one emitted
+        let current = self
+            .last_emitted
+            .or(self.next_index)
+            .unwrap_or(self.first_index());
+        let target = self.data[current].timestamp.saturating_add_signed(millis);
+        info!("Skipping {} ms to {}", millis, target);
+
+        self.next_index = Some(self.clamp(self.index_at(target)));
+        Ok(())
+    }
+
+    fn seek(&mut self, target: SeekTarget) -> Result<()> {
+        self.ensure_data()?;
+
+        let timestamp = match target {
+            SeekTarget::Timestamp(timestamp) => timestamp,
+            SeekTarget::Fraction(fraction) => {
+                let first = self.data[self.range.0].timestamp;
+                let span = self.data[self.range.1].timestamp.saturating_sub(first) as f64;
+                first + (span * fraction.clamp(0.0, 1.0)).round() as u64
+            }
+        };
+        info!("Seeking to {}", timestamp);
+
+        self.next_index = Some(self.clamp(self.index_at(timestamp)));
+        Ok(())
+    }
+
+    fn set_range(&mut self, range: Option<TimeRange>) -> Result<()> {
+        self.ensure_data()?;
+
+        self.range = match range {
+            Some(range) if range.from > range.to => {
+                return Err(DataError::CustomError(
+                    "The in point must come before the out point".into(),
+                )
+                .into());
+            }
+            // The out point is the last entry at or before `to`
+            Some(range) => {
+                let start = self.index_at(range.from);
+                let end = self
+                    .data
+                    .partition_point(|entry| entry.timestamp <= range.to)
+                    .saturating_sub(1)
+                    .max(start);
+                (start, end)
+            }
+            None => (0, self.data.len() - 1),
+        };
+        info!("Playback range set to entries {:?}", self.range);
+
+        let next = self
+            .next_index
+            .map_or(self.first_index(), |i| self.clamp(i));
+        self.next_index = Some(next);
+        Ok(())
+    }
+
+    fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    fn set_reverse(&mut self, reverse: bool) {
+        if self.reverse == reverse {
+            return;
+        }
+        self.reverse = reverse;
+
+        // Continue from the entry on screen in the new direction
+        self.next_index = match self.last_emitted {
+            Some(index) => self.following(index),
+            None => Some(self.first_index()),
+        };
+    }
+
+    fn step(&mut self, forward: bool) -> Result<Option<Arc<ColumnEntry>>> {
+        self.ensure_data()?;
+
+        let index = match self.last_emitted {
+            Some(index) if forward => self.clamp(index + 1),
+            Some(index) => self.clamp(index.saturating_sub(1)),
+            None => self.next_index.unwrap_or(self.first_index()),
+        };
+
+        self.last_emitted = Some(index);
+        self.next_index = self.following(index);
+        Ok(Some(self.data[index].clone()))
+    }
+
+    fn position(&self) -> Option<PlaybackPosition> {
+        let index = self
+            .next_index
+            .or(self.last_emitted)
+            .unwrap_or(self.first_index());
+        let entry = self.data.get(index)?;
+        let first = self.data[self.range.0].timestamp;
+        let last = self.data[self.range.1].timestamp;
+
+        let span = last.saturating_sub(first);
+        let fraction = if span > 0 {
+            entry.timestamp.saturating_sub(first) as f64 / span as f64
+        } else {
+            1.0
+        };
+
+        Some(PlaybackPosition {
+            index: index as u32,
+            total: self.data.len() as u32,
+            timestamp: entry.timestamp,
+            fraction,
+            range: TimeRange {
+                from: first,
+                to: last,
+            },
+            looping: self.looping,
+            reverse: self.reverse,
+        })
+    }
+
+    fn next_delay(&self) -> Option<PlaybackDelay> {
+        let previous = self.data.get(self.last_emitted?)?;
+        let Some(next) = self.next_index.and_then(|i| self.data.get(i)) else {
+            // Wrapping around to the start of a loop takes one ordinary interval
+            let interval = self.interval.filter(|_| self.looping)?;
+            return Some(PlaybackDelay {
+                recorded: interval,
+                wait: interval,
+            });
+        };
+        let recorded = next.timestamp.abs_diff(previous.timestamp);
+
+        // A gap in the recording is played as a single ordinary interval
+        let wait = match self.interval {
+            Some(interval) if recorded > interval.max(1) * GAP_FACTOR => interval,
+            _ => recorded,
+        };
+
+        Some(PlaybackDelay { recorded, wait })
+    }
+
+    fn take_faults(&mut self) -> Vec<SensorFault> {
+        Vec::new()
+    }
+
     fn reset(&mut self) -> Result<()> {
-        self.current_index = 0;
+        self.last_emitted = None;
+        self.next_index = (!self.data.is_empty()).then(|| self.first_index());
         Ok(())
     }
 
     fn get_current_index(&self) -> usize {
-        self.current_index
+        self.next_index.unwrap_or(self.data.len())
     }
 
+    fn is_live(&self) -> bool {
+        false
+    }
+
+    fn apply_settings(&mut self, _settings: &ModbusSettings) {}
+
     async fn disconnect(&self) -> Result<()> {
         Ok(())
     }
 
     fn clone_provider(&self) -> Box<dyn DataProvider + Send> {
-        Box::new(Self {
-            data: self.data.clone(),
-            current_index: self.current_index,
-        })
+        Box::new(self.clone())
     }
 }
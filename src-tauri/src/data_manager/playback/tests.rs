@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use crate::data_manager::import_export::types::TimeRange;
+    use crate::data_manager::playback::PlaybackDataProvider;
+    use crate::data_manager::provider::DataProvider;
+    use crate::data_manager::types::{ColumnEntry, SeekTarget};
+    use std::sync::Arc;
+
+    // One sample a second with a ten minute gap after the fourth one
+    fn provider() -> PlaybackDataProvider {
+        let timestamps = [0, 1000, 2000, 3000, 603_000, 604_000];
+        PlaybackDataProvider::new(
+            timestamps
+                .iter()
+                .map(|&timestamp| {
+                    Arc::new(ColumnEntry {
+                        timestamp,
+                        ..ColumnEntry::default()
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_playback_is_paced_by_timestamps() {
+        let mut provider = provider();
+        assert!(provider.next_delay().is_none());
+
+        provider.get_next_entry(0).await.unwrap();
+        let delay = provider.next_delay().unwrap();
+        assert_eq!((delay.recorded, delay.wait), (1000, 1000));
+
+        provider.skip(2).unwrap();
+        provider.get_next_entry(0).await.unwrap();
+        let delay = provider.next_delay().unwrap();
+        assert_eq!((delay.recorded, delay.wait), (600_000, 1000));
+    }
+
+    #[tokio::test]
+    async fn test_seek_and_skip_by_time() {
+        let mut provider = provider();
+
+        provider.seek(SeekTarget::Timestamp(2500)).unwrap();
+        assert_eq!(provider.get_current_index(), 3);
+
+        provider.seek(SeekTarget::Fraction(1.0)).unwrap();
+        assert_eq!(provider.position().unwrap().timestamp, 604_000);
+
+        provider.seek(SeekTarget::Fraction(0.0)).unwrap();
+        provider.get_next_entry(0).await.unwrap();
+        provider.skip_time(2000).unwrap();
+        assert_eq!(provider.get_next_entry(0).await.unwrap().timestamp, 2000);
+
+        provider.skip_time(-60_000).unwrap();
+        assert_eq!(provider.get_current_index(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_range_loops_and_plays_in_reverse() {
+        let mut provider = provider();
+        provider
+            .set_range(Some(TimeRange {
+                from: 1000,
+                to: 2500,
+            }))
+            .unwrap();
+        provider.set_looping(true);
+
+        let mut played = Vec::new();
+        for _ in 0..4 {
+            played.push(provider.get_next_entry(0).await.unwrap().timestamp);
+        }
+        assert_eq!(played, vec![1000, 2000, 1000, 2000]);
+
+        provider.set_looping(false);
+        provider.set_reverse(true);
+        assert_eq!(provider.get_next_entry(0).await.unwrap().timestamp, 1000);
+        assert!(provider.get_next_entry(0).await.is_err());
+        assert!(provider.position().unwrap().reverse);
+    }
+
+    #[tokio::test]
+    async fn test_step_moves_one_sample() {
+        let mut provider = provider();
+        provider.get_next_entry(0).await.unwrap();
+
+        assert_eq!(provider.step(true).unwrap().unwrap().timestamp, 1000);
+        assert_eq!(provider.step(true).unwrap().unwrap().timestamp, 2000);
+        assert_eq!(provider.step(false).unwrap().unwrap().timestamp, 1000);
+        assert_eq!(provider.get_next_entry(0).await.unwrap().timestamp, 2000);
+
+        provider.seek(SeekTarget::Fraction(0.0)).unwrap();
+        provider.get_next_entry(0).await.unwrap();
+        assert_eq!(provider.step(false).unwrap().unwrap().timestamp, 0);
+    }
+}
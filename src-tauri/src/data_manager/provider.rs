@@ -1,5 +1,8 @@
-use crate::data_manager::types::ColumnEntry;
+use crate::data_manager::import_export::types::TimeRange;
+use crate::data_manager::types::{ColumnEntry, PlaybackDelay, PlaybackPosition, SeekTarget};
+use crate::diagnostics::types::SensorFault;
 use crate::errors::Result;
+use crate::settings::types::ModbusSettings;
 use async_trait::async_trait;
 use std::sync::Arc;
 
@@ -7,8 +10,21 @@ use std::sync::Arc;
 pub trait DataProvider {
     async fn get_next_entry(&mut self, number_plates: i32) -> Result<Arc<ColumnEntry>>;
     fn skip(&mut self, count: i64) -> Result<()>;
+    fn skip_time(&mut self, millis: i64) -> Result<()>;
+    fn seek(&mut self, target: SeekTarget) -> Result<()>;
+    fn set_range(&mut self, range: Option<TimeRange>) -> Result<()>;
+    fn set_looping(&mut self, looping: bool);
+    fn set_reverse(&mut self, reverse: bool);
+    fn step(&mut self, forward: bool) -> Result<Option<Arc<ColumnEntry>>>;
+    fn position(&self) -> Option<PlaybackPosition>;
+    fn next_delay(&self) -> Option<PlaybackDelay>;
+    /// Sensor faults raised or cleared since the last call
+    fn take_faults(&mut self) -> Vec<SensorFault>;
     fn reset(&mut self) -> Result<()>;
     fn get_current_index(&self) -> usize;
+    fn is_live(&self) -> bool;
+    /// Takes over changed settings, from the next entry on
+    fn apply_settings(&mut self, settings: &ModbusSettings);
     async fn disconnect(&self) -> Result<()>;
     fn clone_provider(&self) -> Box<dyn DataProvider + Send>;
 }
@@ -0,0 +1,50 @@
+use crate::calculations::service::CalculationService;
+use crate::data_manager::types::{ColumnEntry, EntryQuality};
+use log::info;
+use std::sync::Arc;
+
+/// Solves the compositions and distilled mass of a stored run again from its
+/// temperatures. Readings with a recorded pressure keep it unless
+/// `use_recorded_pressure` is false, then the pressure of the parameters applies.
+/// The distilled mass is balanced against `initial_charge`.
+pub fn recompute_entries(
+    entries: &[Arc<ColumnEntry>],
+    calculation_service: &CalculationService,
+    initial_charge: f64,
+    use_recorded_pressure: bool,
+) -> Vec<Arc<ColumnEntry>> {
+    info!(
+        "Recomputing {} entries with {:?}",
+        entries.len(),
+        calculation_service.params()
+    );
+
+    let compositions: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            let temperatures: Vec<Option<f64>> =
+                entry.temperatures.iter().copied().map(Some).collect();
+            let pressure = entry.pressure.filter(|_| use_recorded_pressure);
+            calculation_service.calculate_compositions(&temperatures, pressure)
+        })
+        .collect();
+    let masses = calculation_service
+        .calculate_distilled_masses(initial_charge, compositions.iter().map(Vec::as_slice));
+
+    entries
+        .iter()
+        .zip(compositions)
+        .zip(masses)
+        .map(|((entry, compositions), distilled_mass)| {
+            let temperature_quality = (0..entry.temperatures.len())
+                .map(|plate| entry.temperature_quality(plate))
+                .collect();
+            Arc::new(ColumnEntry {
+                quality: EntryQuality::solved(temperature_quality, &compositions),
+                compositions,
+                distilled_mass,
+                ..(**entry).clone()
+            })
+        })
+        .collect()
+}
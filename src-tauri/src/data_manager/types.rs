@@ -1,21 +1,244 @@
 use crate::calculations::types::CompositionResult;
-use serde::Serialize;
+use crate::data_manager::import_export::types::{ColumnMapping, TimeRange, TimestampFormat};
+use crate::modbus::types::PointRole;
+use crate::runs::types::RunTag;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::sync::Arc;
 
-#[derive(Default, Clone, Serialize, Debug)]
+#[derive(Default, Clone, Serialize, Deserialize, Debug, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct ColumnEntry {
+    /// Milliseconds since the Unix epoch, or since the start of the run for
+    /// imports with relative times
     pub timestamp: u64,
     pub temperatures: Vec<f64>,
     pub compositions: Vec<CompositionResult>,
     pub percentage_complete: f64,
     pub distilled_mass: f64,
+    #[serde(default)]
+    pub pressure: Option<f64>,
+    #[serde(default)]
+    pub quality: EntryQuality,
+    /// The batch run a live entry was produced in
+    #[serde(default)]
+    pub run: Option<RunTag>,
+    /// Every value of the register map, as configured
+    #[serde(default)]
+    pub measurements: Vec<Measurement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Measurement {
+    pub name: String,
+    pub role: PointRole,
+    pub value: f64,
+    pub unit: String,
+}
+
+impl ColumnEntry {
+    pub fn temperature_quality(&self, plate: usize) -> Quality {
+        self.quality
+            .temperatures
+            .get(plate)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn composition_quality(&self, plate: usize) -> Quality {
+        self.quality
+            .compositions
+            .get(plate)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Quality of a single value, in the style of OPC status codes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", tag = "status", content = "reason")]
+pub enum Quality {
+    #[default]
+    Good,
+    Uncertain(QualityReason),
+    Bad(QualityReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum QualityReason {
+    /// Estimated from the measured plates around it
+    PlateInterpolation,
+    /// Estimated from the readings before and after it
+    TimeInterpolation,
+    /// Repeats the previous reading
+    FilledForward,
+    /// The last known value, the sensor could not be read
+    CommunicationFailure,
+    /// The equilibrium solver did not converge
+    SolverFailure,
+    /// Diagnostics found the sensor, or the profile it is part of, faulty
+    SensorFault,
+    MissingValue,
+}
+
+impl QualityReason {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            QualityReason::PlateInterpolation => "interpolated between plates",
+            QualityReason::TimeInterpolation => "interpolated in time",
+            QualityReason::FilledForward => "filled forward",
+            QualityReason::CommunicationFailure => "communication failure",
+            QualityReason::SolverFailure => "solver failure",
+            QualityReason::SensorFault => "sensor fault",
+            QualityReason::MissingValue => "missing value",
+        }
+    }
+}
+
+impl Quality {
+    pub fn is_good(&self) -> bool {
+        *self == Quality::Good
+    }
+
+    /// A composition is as trustworthy as the temperature it was solved from,
+    /// and bad when there is no result
+    pub fn of_composition(temperature: Quality, composition: &CompositionResult) -> Quality {
+        match temperature {
+            Quality::Bad(reason) => Quality::Bad(reason),
+            _ if !composition.is_complete() => Quality::Bad(QualityReason::SolverFailure),
+            quality => quality,
+        }
+    }
+}
+
+/// Quality of every plate value of an entry. Missing qualities are good, so
+/// entries stored before they were tracked read as measured.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryQuality {
+    pub temperatures: Vec<Quality>,
+    pub compositions: Vec<Quality>,
+}
+
+impl EntryQuality {
+    pub fn is_good(&self) -> bool {
+        self.temperatures
+            .iter()
+            .chain(&self.compositions)
+            .all(Quality::is_good)
+    }
+
+    /// Qualities of compositions solved from temperatures of `temperatures` quality
+    pub fn solved(temperatures: Vec<Quality>, compositions: &[CompositionResult]) -> Self {
+        let compositions = temperatures
+            .iter()
+            .zip(compositions)
+            .map(|(&temperature, composition)| Quality::of_composition(temperature, composition))
+            .collect();
+        Self {
+            temperatures,
+            compositions,
+        }
+    }
+}
+
+/// Where to move playback to. Fractions run from 0 at the first entry to 1 at the last.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", tag = "mode", content = "value")]
+pub enum SeekTarget {
+    Timestamp(u64),
+    Fraction(f64),
+}
+
+/// The entry playback emits next, with the in and out points it plays between
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackPosition {
+    pub index: u32,
+    pub total: u32,
+    pub timestamp: u64,
+    pub fraction: f64,
+    pub range: TimeRange,
+    pub looping: bool,
+    pub reverse: bool,
+}
+
+/// Time between the last emitted entry and the next one. `wait` equals `recorded`
+/// unless the recording has a gap there, which is not waited out.
+#[derive(Debug, Clone, Copy)]
+pub struct PlaybackDelay {
+    pub recorded: u64,
+    pub wait: u64,
 }
 
 pub struct ColumnStructure {
     pub number_plates: usize,
-    pub has_compositions: bool,
     pub timestamp_column: usize,
-    pub temperatures_start: usize,
-    pub compositions_x_start: Option<usize>,
-    pub compositions_y_start: Option<usize>,
+    pub timestamp_format: Option<TimestampFormat>,
+    pub temperature_columns: Vec<usize>,
+    pub pressure_column: Option<usize>,
+    pub compositions_x_columns: Option<Vec<usize>>,
+    pub compositions_y_columns: Option<Vec<usize>>,
+}
+
+impl ColumnStructure {
+    pub fn has_compositions(&self) -> bool {
+        self.compositions_x_columns.is_some() || self.compositions_y_columns.is_some()
+    }
+
+    pub fn last_column(&self) -> usize {
+        self.temperature_columns
+            .iter()
+            .chain(self.compositions_x_columns.iter().flatten())
+            .chain(self.compositions_y_columns.iter().flatten())
+            .chain(self.pressure_column.iter())
+            .fold(self.timestamp_column, |last, &column| last.max(column))
+    }
+
+    pub fn from_mapping(mapping: &ColumnMapping) -> Self {
+        let columns = |columns: &Vec<u32>| columns.iter().map(|&c| c as usize).collect();
+
+        ColumnStructure {
+            number_plates: mapping.temperatures.len(),
+            timestamp_column: mapping.timestamp as usize,
+            timestamp_format: mapping.timestamp_format,
+            temperature_columns: columns(&mapping.temperatures),
+            pressure_column: mapping.pressure.map(|c| c as usize),
+            compositions_x_columns: mapping.compositions_x.as_ref().map(columns),
+            compositions_y_columns: mapping.compositions_y.as_ref().map(columns),
+        }
+    }
+
+    pub fn to_mapping(&self) -> ColumnMapping {
+        let columns = |columns: &Vec<usize>| columns.iter().map(|&c| c as u32).collect();
+
+        ColumnMapping {
+            timestamp: self.timestamp_column as u32,
+            temperatures: columns(&self.temperature_columns),
+            pressure: self.pressure_column.map(|c| c as u32),
+            compositions_x: self.compositions_x_columns.as_ref().map(columns),
+            compositions_y: self.compositions_y_columns.as_ref().map(columns),
+            timestamp_format: self.timestamp_format,
+        }
+    }
+}
+
+/// Gaps longer than this many typical sample intervals are reported on import
+/// and skipped over in playback
+pub const GAP_FACTOR: u64 = 5;
+
+/// Median time between consecutive entries
+pub fn typical_interval(entries: &[Arc<ColumnEntry>]) -> Option<u64> {
+    let mut intervals: Vec<u64> = entries
+        .windows(2)
+        .map(|pair| pair[1].timestamp.saturating_sub(pair[0].timestamp))
+        .collect();
+    if intervals.is_empty() {
+        return None;
+    }
+
+    let middle = intervals.len() / 2;
+    Some(*intervals.select_nth_unstable(middle).1)
 }
@@ -0,0 +1,236 @@
+use super::types::{ChannelReading, FaultKind, RawReading, SensorFault};
+use crate::settings::types::DiagnosticsSettings;
+use log::warn;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Readings the spike and rate checks look back over
+const SHORT_WINDOW: usize = 5;
+/// Another sensor must move this many flatline bands for a flatline to count
+const FLATLINE_CONTRAST: f64 = 5.0;
+const MAX_SAMPLES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    timestamp: u64,
+    temperature: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ChannelHistory {
+    samples: VecDeque<Sample>,
+    last_raw: Option<RawReading>,
+    repeats: u32,
+}
+
+impl ChannelHistory {
+    /// Spread of the readings over the last `window` ms, if the history covers it
+    fn span(&self, now: u64, window: u64) -> Option<f64> {
+        let start = now.checked_sub(window)?;
+        if self.samples.front()?.timestamp > start {
+            return None;
+        }
+
+        let (min, max) = self
+            .samples
+            .iter()
+            .filter(|sample| sample.timestamp >= start)
+            .fold((f64::MAX, f64::MIN), |(min, max), sample| {
+                (min.min(sample.temperature), max.max(sample.temperature))
+            });
+        (min <= max).then_some(max - min)
+    }
+
+    fn recent(&self) -> impl Iterator<Item = &Sample> {
+        self.samples
+            .iter()
+            .skip(self.samples.len().saturating_sub(SHORT_WINDOW))
+    }
+}
+
+/// Watches the raw thermocouple readings of a live run. Faults are reported once
+/// when raised and once when cleared, and stay active in between.
+#[derive(Debug, Clone)]
+pub struct SensorMonitor {
+    settings: DiagnosticsSettings,
+    /// History of every plate read so far, kept while its sensor misses a reading
+    channels: BTreeMap<u32, ChannelHistory>,
+    /// Active faults with the plate they were raised on
+    active: BTreeSet<(FaultKind, Option<u32>)>,
+}
+
+impl SensorMonitor {
+    pub fn new(settings: DiagnosticsSettings) -> Self {
+        Self {
+            settings,
+            channels: BTreeMap::new(),
+            active: BTreeSet::new(),
+        }
+    }
+
+    /// Checks one set of readings, ordered from the bottom of the column to the
+    /// top, and returns the faults that were raised or cleared by it
+    pub fn check(&mut self, timestamp: u64, readings: &[ChannelReading]) -> Vec<SensorFault> {
+        let mut found: Vec<(FaultKind, Option<u32>, Option<f64>)> = Vec::new();
+        let mut spans = Vec::with_capacity(readings.len());
+        for reading in readings {
+            let history = self.channels.entry(reading.plate).or_default();
+            history.repeats = match history.last_raw {
+                Some(raw) if raw == reading.raw => history.repeats + 1,
+                _ => 1,
+            };
+            history.last_raw = Some(reading.raw);
+
+            // A broken thermocouple has no temperature to check any further
+            if reading.raw.is_one_of(&self.settings.open_circuit_codes) {
+                found.push((FaultKind::OpenCircuit, Some(reading.plate), None));
+                spans.push(None);
+                continue;
+            }
+
+            for kind in detect(&self.settings, history, timestamp, reading) {
+                found.push((kind, Some(reading.plate), Some(reading.temperature)));
+            }
+
+            history.samples.push_back(Sample {
+                timestamp,
+                temperature: reading.temperature,
+            });
+            let keep_from = timestamp.saturating_sub(self.settings.flatline_window_ms);
+            while history.samples.len() > MAX_SAMPLES
+                || (history.samples.len() > SHORT_WINDOW
+                    && history.samples[1].timestamp <= keep_from)
+            {
+                history.samples.pop_front();
+            }
+            spans.push(history.span(timestamp, self.settings.flatline_window_ms));
+        }
+
+        // A sensor that does not move while the others do has flatlined
+        let moving = spans
+            .iter()
+            .flatten()
+            .any(|&span| span > self.settings.flatline_band * FLATLINE_CONTRAST);
+        for (reading, span) in readings.iter().zip(&spans) {
+            let stuck = found
+                .iter()
+                .any(|&(kind, plate, _)| kind == FaultKind::Stuck && plate == Some(reading.plate));
+            if moving && !stuck && span.is_some_and(|span| span <= self.settings.flatline_band) {
+                found.push((
+                    FaultKind::Flatline,
+                    Some(reading.plate),
+                    Some(reading.temperature),
+                ));
+            }
+        }
+
+        // The reboiler is the hottest point of the column
+        let usable: Vec<&ChannelReading> = readings
+            .iter()
+            .filter(|reading| !reading.raw.is_one_of(&self.settings.open_circuit_codes))
+            .collect();
+        if let (Some(bottom), Some(top)) = (usable.first(), usable.last()) {
+            if usable.len() > 1
+                && top.temperature > bottom.temperature + self.settings.profile_tolerance
+            {
+                found.push((FaultKind::InvertedProfile, None, Some(top.temperature)));
+            }
+        }
+
+        self.update(timestamp, found)
+    }
+
+    /// Plates with an active fault of their own
+    pub fn faulty_plates(&self) -> Vec<u32> {
+        let plates: BTreeSet<u32> = self.active.iter().filter_map(|&(_, plate)| plate).collect();
+        plates.into_iter().collect()
+    }
+
+    pub fn has_profile_fault(&self) -> bool {
+        self.active.iter().any(|&(_, plate)| plate.is_none())
+    }
+
+    fn update(
+        &mut self,
+        timestamp: u64,
+        found: Vec<(FaultKind, Option<u32>, Option<f64>)>,
+    ) -> Vec<SensorFault> {
+        let mut changes = Vec::new();
+
+        let current: BTreeMap<(FaultKind, Option<u32>), Option<f64>> = found
+            .into_iter()
+            .map(|(kind, plate, value)| ((kind, plate), value))
+            .collect();
+        for (&(kind, plate), &value) in &current {
+            if self.active.insert((kind, plate)) {
+                warn!("Sensor fault {:?} on plate {:?}", kind, plate);
+                changes.push(SensorFault {
+                    plate,
+                    kind,
+                    timestamp,
+                    value,
+                    active: true,
+                });
+            }
+        }
+
+        let cleared: Vec<(FaultKind, Option<u32>)> = self
+            .active
+            .iter()
+            .filter(|key| !current.contains_key(key))
+            .copied()
+            .collect();
+        for (kind, plate) in cleared {
+            self.active.remove(&(kind, plate));
+            changes.push(SensorFault {
+                plate,
+                kind,
+                timestamp,
+                value: None,
+                active: false,
+            });
+        }
+
+        changes
+    }
+}
+
+/// The faults of a single channel that only need its own history
+fn detect(
+    settings: &DiagnosticsSettings,
+    history: &ChannelHistory,
+    timestamp: u64,
+    reading: &ChannelReading,
+) -> Vec<FaultKind> {
+    let mut faults = Vec::new();
+    let temperature = reading.temperature;
+
+    if temperature < settings.min_temperature || temperature > settings.max_temperature {
+        faults.push(FaultKind::OutOfRange);
+    }
+    if history.repeats >= settings.stuck_samples {
+        faults.push(FaultKind::Stuck);
+    }
+
+    let mut recent: Vec<f64> = history.recent().map(|s| s.temperature).collect();
+    if recent.len() >= SHORT_WINDOW - 1 {
+        recent.sort_by(f64::total_cmp);
+        let median = recent[recent.len() / 2];
+        if (temperature - median).abs() > settings.spike_delta {
+            faults.push(FaultKind::Spike);
+            return faults;
+        }
+    }
+
+    // A sustained ramp over the short window, a single jump is a spike
+    if let Some(oldest) = history.recent().next() {
+        let seconds = timestamp.saturating_sub(oldest.timestamp) as f64 / 1000.0;
+        if history.samples.len() >= SHORT_WINDOW - 1
+            && seconds > 0.0
+            && ((temperature - oldest.temperature) / seconds).abs() > settings.max_rate
+        {
+            faults.push(FaultKind::RateOfChange);
+        }
+    }
+
+    faults
+}
@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::types::{ChannelReading, FaultKind, RawReading, SensorFault};
+    use crate::diagnostics::SensorMonitor;
+    use crate::settings::types::DiagnosticsSettings;
+
+    // Bottom sensor on plate 0, top sensor on plate 4
+    fn check(monitor: &mut SensorMonitor, second: u64, bottom: f64, top: f64) -> Vec<SensorFault> {
+        let readings = [(0, bottom), (4, top)].map(|(plate, temperature)| ChannelReading {
+            plate,
+            raw: RawReading::Word((temperature * 100.0).round() as u16),
+            temperature,
+        });
+        monitor.check(second * 1000, &readings)
+    }
+
+    fn raised(faults: &[SensorFault]) -> Vec<(FaultKind, Option<u32>)> {
+        faults
+            .iter()
+            .filter(|fault| fault.active)
+            .map(|fault| (fault.kind, fault.plate))
+            .collect()
+    }
+
+    #[test]
+    fn test_open_circuit_and_inverted_profile() {
+        let mut monitor = SensorMonitor::new(DiagnosticsSettings::default());
+        assert!(check(&mut monitor, 0, 80.0, 70.0).is_empty());
+
+        let faults = check(&mut monitor, 1, 327.67, 70.1);
+        assert_eq!(raised(&faults), vec![(FaultKind::OpenCircuit, Some(0))]);
+        assert_eq!(monitor.faulty_plates(), vec![0]);
+
+        // The sensor is back but reads colder than the top
+        let faults = check(&mut monitor, 2, 69.0, 72.5);
+        assert_eq!(raised(&faults), vec![(FaultKind::InvertedProfile, None)]);
+        assert!(faults
+            .iter()
+            .any(|fault| fault.kind == FaultKind::OpenCircuit && !fault.active));
+        assert!(monitor.has_profile_fault());
+        assert!(monitor.faulty_plates().is_empty());
+    }
+
+    #[test]
+    fn test_spike_rate_stuck_and_flatline() {
+        let mut monitor = SensorMonitor::new(DiagnosticsSettings::default());
+        for second in 0..5 {
+            check(
+                &mut monitor,
+                second,
+                80.0 + second as f64 * 0.1,
+                70.0 + second as f64 * 0.1,
+            );
+        }
+        let faults = check(&mut monitor, 5, 100.0, 70.5);
+        assert_eq!(raised(&faults), vec![(FaultKind::Spike, Some(0))]);
+
+        // A steady 3 °C a second is too fast, but no single reading jumps
+        let mut monitor = SensorMonitor::new(DiagnosticsSettings::default());
+        let faults: Vec<SensorFault> = (0..6)
+            .flat_map(|second| check(&mut monitor, second, 80.0 + second as f64 * 3.0, 70.0))
+            .collect();
+        assert_eq!(raised(&faults), vec![(FaultKind::RateOfChange, Some(0))]);
+
+        // The top stays put while the bottom heats up
+        let mut monitor = SensorMonitor::new(DiagnosticsSettings {
+            stuck_samples: 4,
+            flatline_window_ms: 10_000,
+            ..DiagnosticsSettings::default()
+        });
+        let faults: Vec<SensorFault> = (0..=10)
+            .flat_map(|second| {
+                let top = if second % 2 == 0 { 70.0 } else { 70.01 };
+                check(&mut monitor, second, 80.0 + second as f64 * 0.5, top)
+            })
+            .collect();
+        assert_eq!(raised(&faults), vec![(FaultKind::Flatline, Some(4))]);
+
+        let faults: Vec<SensorFault> = (11..15)
+            .flat_map(|second| check(&mut monitor, second, 85.5 + second as f64 * 0.5, 70.0))
+            .collect();
+        assert!(raised(&faults).contains(&(FaultKind::Stuck, Some(4))));
+    }
+
+    #[test]
+    fn test_history_is_kept_per_plate() {
+        let mut monitor = SensorMonitor::new(DiagnosticsSettings::default());
+        for second in 0..5 {
+            check(&mut monitor, second, 80.0, 70.0);
+        }
+        // The bottom sensor misses a reading, the top one keeps its plate
+        let top = ChannelReading {
+            plate: 4,
+            raw: RawReading::Word(7000),
+            temperature: 70.0,
+        };
+        assert!(monitor.check(5000, &[top]).is_empty());
+
+        let faults = check(&mut monitor, 6, 100.0, 70.0);
+        assert_eq!(raised(&faults), vec![(FaultKind::Spike, Some(0))]);
+    }
+
+    #[test]
+    fn test_float_channels_compare_both_registers() {
+        // As floats 78.5 and 78.6 only differ in the low register
+        let mut monitor = SensorMonitor::new(DiagnosticsSettings::default());
+        let faults: Vec<SensorFault> = (0..40)
+            .flat_map(|second| {
+                let temperature: f32 = if second % 2 == 0 { 78.5 } else { 78.6 };
+                let reading = ChannelReading {
+                    plate: 0,
+                    raw: RawReading::DoubleWord(temperature.to_bits()),
+                    temperature: temperature as f64,
+                };
+                monitor.check(second * 1000, &[reading])
+            })
+            .collect();
+        assert!(raised(&faults).is_empty());
+    }
+}
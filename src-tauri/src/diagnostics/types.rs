@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Type,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum FaultKind {
+    /// The transmitter reports the code of a broken thermocouple
+    OpenCircuit,
+    OutOfRange,
+    /// The raw reading has not changed at all for many samples
+    Stuck,
+    /// The reading barely moves while the rest of the column does
+    Flatline,
+    /// A single reading far from the ones before it
+    Spike,
+    /// The temperature changes faster than the column physically can
+    RateOfChange,
+    /// The top of the column is hotter than the bottom
+    InvertedProfile,
+}
+
+/// A fault that was raised or cleared. `plate` is `None` for faults of the
+/// whole profile.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SensorFault {
+    pub plate: Option<u32>,
+    pub kind: FaultKind,
+    pub timestamp: u64,
+    pub value: Option<f64>,
+    pub active: bool,
+}
+
+/// One raw thermocouple channel. `plate` is the plate the sensor sits on.
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelReading {
+    pub plate: u32,
+    pub raw: RawReading,
+    pub temperature: f64,
+}
+
+/// The registers of a channel as read, before they are scaled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawReading {
+    Word(u16),
+    /// Both registers of a 32 bit value
+    DoubleWord(u32),
+}
+
+impl RawReading {
+    /// Open circuit codes are single registers, wider values never match one
+    pub fn is_one_of(&self, codes: &[u16]) -> bool {
+        matches!(self, RawReading::Word(raw) if codes.contains(raw))
+    }
+}
@@ -19,6 +19,12 @@ pub enum Error {
     DataError(#[from] DataError),
     #[error("Import error")]
     ImportError(#[from] ImportError),
+    #[error("Storage error")]
+    StorageError(#[from] StorageError),
+    #[error("Alarm error")]
+    AlarmError(#[from] AlarmError),
+    #[error("Run error")]
+    RunError(#[from] RunError),
 }
 
 #[derive(Debug, Error, Serialize, Deserialize, Type)]
@@ -30,6 +36,18 @@ pub enum SettingsError {
     SaveError(String),
     #[error("Failed to get usb devices")]
     GetUsbDevicesError(String),
+    #[error("Unknown time zone: {0}")]
+    InvalidTimeZone(String),
+    #[error("Invalid processing pipeline: {0}")]
+    InvalidPipeline(String),
+    #[error("Invalid KPI settings: {0}")]
+    InvalidKpi(String),
+    #[error("Invalid cuts: {0}")]
+    InvalidCuts(String),
+    #[error("Invalid alarm rules: {0}")]
+    InvalidAlarms(String),
+    #[error("Invalid Modbus connection: {0}")]
+    InvalidModbus(String),
 }
 
 #[derive(Debug, Error, Serialize, Deserialize, Type)]
@@ -58,12 +76,16 @@ pub enum FileError {
 pub enum ModbusError {
     #[error("Failed to connect modbus {0}")]
     ConnectionError(String),
+    #[error("Modbus handshake failed: {0}")]
+    HandshakeError(String),
     #[error("Failed to read coils {0}")]
     ReadCoilsError(String),
     #[error("Failed to write coils {0}")]
     WriteCoilsError(String),
     #[error("Failed to read holding registers {0}")]
     ReadHoldingRegistersError(String),
+    #[error("Failed to read input registers {0}")]
+    ReadInputRegistersError(String),
     #[error("Failed to write holding registers {0}")]
     WriteHoldingRegistersError(String),
     #[error("Failed to write single coil {0}")]
@@ -88,6 +110,18 @@ pub enum RootError {
 pub enum ImportError {
     #[error("Invalid format {0}")]
     InvalidFormat(String),
+    #[error("Invalid column mapping {0}")]
+    InvalidMapping(String),
+    #[error("Plate count mismatch {0}")]
+    PlateMismatch(String),
+    #[error("Invalid row {0}")]
+    InvalidRow(String),
+    #[error("No import waiting to be committed")]
+    NothingPending,
+    #[error("Import cancelled")]
+    Cancelled,
+    #[error("Import task failed {0}")]
+    TaskError(String),
 }
 
 #[derive(Error, Serialize, Debug, Deserialize, Type)]
@@ -101,6 +135,49 @@ pub enum DataError {
     NoDataError,
     #[error("Data error {0}")]
     CustomError(String),
+    #[error("No cut to switch to")]
+    NoNextCut,
+}
+
+#[derive(Error, Serialize, Debug, Deserialize, Type)]
+#[serde(tag = "type", content = "data")]
+pub enum StorageError {
+    #[error("Failed to open session database: {0}")]
+    OpenError(String),
+    #[error("Session query failed: {0}")]
+    QueryError(String),
+    #[error("Failed to serialize session entry: {0}")]
+    SerializeError(String),
+    #[error("Session {0} not found")]
+    SessionNotFound(i64),
+    #[error("Annotation {0} not found")]
+    AnnotationNotFound(i64),
+    #[error("Annotations need a text")]
+    EmptyAnnotation,
+    #[error("No session to annotate")]
+    NoSession,
+    #[error("Run {0} not found")]
+    RunNotFound(i64),
+}
+
+#[derive(Error, Serialize, Debug, Deserialize, Type)]
+#[serde(tag = "type", content = "data")]
+pub enum AlarmError {
+    #[error("Unknown alarm {0}")]
+    UnknownAlarm(String),
+    #[error("Alarm {0} has nothing to acknowledge")]
+    NothingToAcknowledge(String),
+}
+
+#[derive(Error, Serialize, Debug, Deserialize, Type)]
+#[serde(tag = "type", content = "data")]
+pub enum RunError {
+    #[error("Run {0} is still going, end it first")]
+    AlreadyActive(String),
+    #[error("No run is active")]
+    NoActiveRun,
+    #[error("Invalid run: {0}")]
+    InvalidMetadata(String),
 }
 
 impl From<Error> for String {
@@ -60,7 +60,7 @@ where
     Ok(())
 }
 
-fn ensure_parent_directory(path: &str) -> Result<()> {
+pub fn ensure_parent_directory(path: &str) -> Result<()> {
     let path_dir = Path::new(path).parent().ok_or_else(|| {
         error!("Path has no parent directory: {}", path);
         FileError::InvalidPathError(path.to_string())
@@ -0,0 +1,356 @@
+use super::types::{HistoryBucket, HistoryConfig};
+use crate::data_manager::types::ColumnEntry;
+use crate::errors::{FileError, Result};
+use log::{info, warn};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const HISTORY_DIRECTORY: &str = "history";
+const SPILL_EXTENSION: &str = "jsonl";
+
+/// Entries that left the recent window, one JSON line each. They are queued
+/// while the history is locked and written by `flush` once it is released. The
+/// file is removed when the history and every snapshot reading it let go of it.
+pub struct SpillFile {
+    path: PathBuf,
+    state: Mutex<SpillState>,
+}
+
+#[derive(Default)]
+struct SpillState {
+    writer: Option<BufWriter<File>>,
+    queued: Vec<Arc<ColumnEntry>>,
+}
+
+impl SpillFile {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            state: Mutex::new(SpillState::default()),
+        }
+    }
+
+    fn queue(&self, entry: Arc<ColumnEntry>) {
+        self.lock().queued.push(entry);
+    }
+
+    /// Writes the queued entries, creating the file on first use
+    pub fn flush(&self) -> Result<()> {
+        let mut state = self.lock();
+        let SpillState { writer, queued } = &mut *state;
+        if queued.is_empty() {
+            return Ok(());
+        }
+
+        let writer = match writer {
+            Some(writer) => writer,
+            None => {
+                if let Some(directory) = self.path.parent() {
+                    fs::create_dir_all(directory)
+                        .map_err(|e| FileError::CreateDirError(e.to_string()))?;
+                }
+                let file =
+                    File::create(&self.path).map_err(|e| FileError::WriteError(e.to_string()))?;
+                info!("Spilling history to {}", self.path.display());
+                writer.insert(BufWriter::new(file))
+            }
+        };
+        for entry in queued.drain(..) {
+            serde_json::to_writer(&mut *writer, &*entry)
+                .map_err(|e| FileError::SerializeError(e.to_string()))?;
+            writer
+                .write_all(b"\n")
+                .map_err(|e| FileError::WriteError(e.to_string()))?;
+        }
+        writer
+            .flush()
+            .map_err(|e| FileError::WriteError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SpillState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let created = self.lock().writer.is_some();
+        if created {
+            if let Err(e) = fs::remove_file(&self.path) {
+                warn!(
+                    "Failed to remove history spill {}: {}",
+                    self.path.display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// The entries of the current run. The newest ones are kept as they are, older
+/// ones are folded into buckets and written to a spill file, so memory stays
+/// bounded however long the run goes on.
+pub struct History {
+    config: HistoryConfig,
+    recent: VecDeque<Arc<ColumnEntry>>,
+    buckets: VecDeque<HistoryBucket>,
+    open_bucket: Option<HistoryBucket>,
+    spill_directory: Option<PathBuf>,
+    spill: Option<Arc<SpillFile>>,
+    /// Entries handed to the spill file, written or still queued
+    spilled: usize,
+    latest: u64,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl History {
+    /// Without a spill directory entries older than the recent window are only
+    /// kept as buckets.
+    pub fn new(spill_directory: Option<PathBuf>) -> Self {
+        Self::with_config(HistoryConfig::default(), spill_directory)
+    }
+
+    pub fn with_config(config: HistoryConfig, spill_directory: Option<PathBuf>) -> Self {
+        // Spills of an earlier process are never read again
+        if let Some(Ok(directory)) = spill_directory.as_ref().map(fs::read_dir) {
+            for item in directory.flatten() {
+                let path = item.path();
+                if path.extension().and_then(|e| e.to_str()) == Some(SPILL_EXTENSION) {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+
+        Self {
+            config,
+            recent: VecDeque::new(),
+            buckets: VecDeque::new(),
+            open_bucket: None,
+            spill_directory,
+            spill: None,
+            spilled: 0,
+            latest: 0,
+        }
+    }
+
+    pub fn get_spill_directory(app_handle: &AppHandle) -> Result<PathBuf> {
+        let path = app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|e| FileError::InvalidPathError(e.to_string()))?
+            .join(HISTORY_DIRECTORY);
+        Ok(path)
+    }
+
+    /// Retired entries are only queued, `flush` the spill outside the lock
+    pub fn push(&mut self, entry: Arc<ColumnEntry>) {
+        self.latest = self.latest.max(entry.timestamp);
+        self.recent.push_back(entry);
+
+        while self.recent.len() > self.config.recent_limit
+            || self.recent.front().is_some_and(|front| {
+                front.timestamp.saturating_add(self.config.recent_window) < self.latest
+            })
+        {
+            if let Some(entry) = self.recent.pop_front() {
+                self.retire(entry);
+            }
+        }
+    }
+
+    /// Makes `entries` the current run
+    pub fn replace(&mut self, entries: Vec<Arc<ColumnEntry>>) {
+        self.clear();
+        for entry in entries {
+            self.push(entry);
+        }
+    }
+
+    /// A snapshot taken earlier keeps the old spill file until it is dropped
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.buckets.clear();
+        self.open_bucket = None;
+        self.spill = None;
+        self.spilled = 0;
+        self.latest = 0;
+    }
+
+    pub fn spill(&self) -> Option<Arc<SpillFile>> {
+        self.spill.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.spilled + self.recent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A view of the run that can be read after the lock is released. Only the
+    /// in memory tiers are copied, spilled entries are read from disk.
+    pub fn snapshot(&self) -> HistorySnapshot {
+        HistorySnapshot {
+            bucket_width: self.config.bucket_width,
+            spill: self.spill.clone().map(|spill| (spill, self.spilled)),
+            buckets: self
+                .buckets
+                .iter()
+                .chain(self.open_bucket.as_ref())
+                .cloned()
+                .collect(),
+            recent: self.recent.iter().cloned().collect(),
+        }
+    }
+
+    /// Moves an entry out of the recent window into the spill file and its bucket
+    fn retire(&mut self, entry: Arc<ColumnEntry>) {
+        let width = self.config.bucket_width.max(1);
+        match self.open_bucket.as_mut() {
+            Some(bucket) if bucket.from / width == entry.timestamp / width => {
+                bucket.merge(&HistoryBucket::from_entry(&entry));
+            }
+            _ => {
+                let closed = self.open_bucket.replace(HistoryBucket::from_entry(&entry));
+                self.buckets.extend(closed);
+            }
+        }
+
+        while self.buckets.len() > self.config.bucket_limit
+            || self.buckets.front().is_some_and(|front| {
+                front.to.saturating_add(self.config.downsampled_window) < self.latest
+            })
+        {
+            self.buckets.pop_front();
+        }
+
+        self.spill_entry(entry);
+    }
+
+    fn spill_entry(&mut self, entry: Arc<ColumnEntry>) {
+        if self.spill.is_none() {
+            let Some(directory) = &self.spill_directory else {
+                return;
+            };
+            let id = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos();
+            let path = directory.join(format!("{}.{}", id, SPILL_EXTENSION));
+            self.spill = Some(Arc::new(SpillFile::new(path)));
+        }
+
+        if let Some(spill) = &self.spill {
+            spill.queue(entry);
+            self.spilled += 1;
+        }
+    }
+}
+
+pub struct HistorySnapshot {
+    bucket_width: u64,
+    /// The spill file and the number of entries it had when taken
+    spill: Option<(Arc<SpillFile>, usize)>,
+    buckets: Vec<HistoryBucket>,
+    recent: Vec<Arc<ColumnEntry>>,
+}
+
+impl HistorySnapshot {
+    pub fn is_empty(&self) -> bool {
+        self.recent.is_empty() && self.spill.as_ref().is_none_or(|(_, count)| *count == 0)
+    }
+
+    /// Every entry of the run at full resolution
+    pub fn entries(&self) -> Result<Vec<Arc<ColumnEntry>>> {
+        let mut entries = Vec::new();
+        self.read_spill(|entry| entries.push(Arc::new(entry)))?;
+        entries.extend(self.recent.iter().cloned());
+        Ok(entries)
+    }
+
+    /// Min/max/mean buckets of `resolution` milliseconds over `from..=to`, one
+    /// bucket per entry when the resolution is zero. Coarse queries over older
+    /// data are served from memory, finer ones read the spill file.
+    pub fn query(&self, from: u64, to: u64, resolution: u64) -> Result<Vec<HistoryBucket>> {
+        let in_range = |bucket: &HistoryBucket| bucket.to >= from && bucket.from <= to;
+        let use_buckets =
+            self.spill.is_none() || (resolution > 0 && resolution >= self.bucket_width);
+
+        // Older data than this comes from the spill file
+        let recent_start = self.recent.first().map(|entry| entry.timestamp);
+        let memory_start = if use_buckets {
+            self.buckets
+                .first()
+                .map(|bucket| bucket.from)
+                .or(recent_start)
+        } else {
+            recent_start
+        };
+
+        let mut bins: BTreeMap<u64, HistoryBucket> = BTreeMap::new();
+        let mut add = |bucket: HistoryBucket| {
+            if !in_range(&bucket) {
+                return;
+            }
+            let key = match resolution {
+                0 => bucket.from,
+                resolution => bucket.from / resolution * resolution,
+            };
+            match bins.entry(key) {
+                Entry::Vacant(bin) => {
+                    bin.insert(bucket);
+                }
+                Entry::Occupied(mut bin) => bin.get_mut().merge(&bucket),
+            }
+        };
+
+        if memory_start.is_none_or(|start| from < start) {
+            self.read_spill(|entry| {
+                if memory_start.is_none_or(|start| entry.timestamp < start) {
+                    add(HistoryBucket::from_entry(&entry));
+                }
+            })?;
+        }
+        if use_buckets {
+            self.buckets.iter().cloned().for_each(&mut add);
+        }
+        for entry in &self.recent {
+            add(HistoryBucket::from_entry(entry));
+        }
+
+        Ok(bins.into_values().collect())
+    }
+
+    fn read_spill<F>(&self, mut on_entry: F) -> Result<()>
+    where
+        F: FnMut(ColumnEntry),
+    {
+        let Some((spill, count)) = &self.spill else {
+            return Ok(());
+        };
+        spill.flush()?;
+        let file = File::open(&spill.path).map_err(|e| FileError::ReadError(e.to_string()))?;
+
+        for line in BufReader::new(file).lines().take(*count) {
+            let line = line.map_err(|e| FileError::ReadError(e.to_string()))?;
+            match serde_json::from_str(&line) {
+                Ok(entry) => on_entry(entry),
+                Err(e) => warn!("Skipping unreadable history line: {}", e),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod tests {
+    use crate::data_manager::types::ColumnEntry;
+    use crate::history::types::HistoryConfig;
+    use crate::history::History;
+    use std::sync::Arc;
+
+    // Full resolution for 10 s, 5 s buckets for 20 s more
+    fn config() -> HistoryConfig {
+        HistoryConfig {
+            recent_window: 10_000,
+            recent_limit: 100,
+            bucket_width: 5_000,
+            downsampled_window: 20_000,
+            bucket_limit: 100,
+        }
+    }
+
+    // One entry a second for a minute, the temperature counts the seconds
+    fn fill(history: &mut History) {
+        for second in 0..60u64 {
+            history.push(Arc::new(ColumnEntry {
+                timestamp: second * 1000,
+                temperatures: vec![second as f64],
+                ..ColumnEntry::default()
+            }));
+        }
+    }
+
+    #[test]
+    fn test_history_spills_and_queries_by_resolution() {
+        let directory = std::env::temp_dir().join(format!("history-test-{}", std::process::id()));
+        let mut history = History::with_config(config(), Some(directory.clone()));
+        fill(&mut history);
+        assert_eq!(history.len(), 60);
+
+        let snapshot = history.snapshot();
+        let entries = snapshot.entries().unwrap();
+        assert_eq!(entries.len(), 60);
+        assert!(entries.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+
+        let full = snapshot.query(20_000, 29_999, 0).unwrap();
+        assert_eq!(full.len(), 10);
+        assert_eq!(full[0].mean, vec![20.0]);
+
+        // Buckets from memory, the start of the run from the spill file
+        let coarse = snapshot.query(0, u64::MAX, 10_000).unwrap();
+        assert_eq!(coarse.len(), 6);
+        assert!(coarse.iter().all(|bucket| bucket.count == 10));
+        assert_eq!(
+            (coarse[3].min[0], coarse[3].max[0], coarse[3].mean[0]),
+            (30.0, 39.0, 34.5)
+        );
+
+        // A snapshot taken before clearing still reads the spilled entries
+        history.clear();
+        assert!(history.is_empty());
+        assert_eq!(snapshot.entries().unwrap().len(), 60);
+        let spill_files = || std::fs::read_dir(&directory).unwrap().count();
+        assert_eq!(spill_files(), 1);
+        drop(snapshot);
+        assert_eq!(spill_files(), 0);
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_history_without_spill_stays_bounded() {
+        let mut history = History::with_config(config(), None);
+        fill(&mut history);
+        assert_eq!(history.len(), 11);
+
+        let coarse = history.snapshot().query(0, u64::MAX, 5_000).unwrap();
+        assert_eq!(coarse.first().map(|bucket| bucket.from), Some(35_000));
+        assert_eq!(coarse.iter().map(|bucket| bucket.count).sum::<u32>(), 25);
+    }
+}
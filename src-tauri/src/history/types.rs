@@ -0,0 +1,91 @@
+use crate::data_manager::types::ColumnEntry;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// How much of a run is kept in memory. Times are in milliseconds.
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryConfig {
+    /// Span of the newest entries kept at full resolution
+    pub recent_window: u64,
+    pub recent_limit: usize,
+    /// Width of the min/max/mean buckets older entries are folded into
+    pub bucket_width: u64,
+    /// Span of buckets kept in memory, older data is only on disk
+    pub downsampled_window: u64,
+    pub bucket_limit: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            recent_window: 60 * 60 * 1000,
+            recent_limit: 20_000,
+            bucket_width: 60 * 1000,
+            downsampled_window: 7 * 24 * 60 * 60 * 1000,
+            bucket_limit: 20_000,
+        }
+    }
+}
+
+/// Temperatures of every plate over `from..=to`. A bucket of a single entry has
+/// the same minimum, maximum and mean.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryBucket {
+    pub from: u64,
+    pub to: u64,
+    pub count: u32,
+    pub min: Vec<f64>,
+    pub max: Vec<f64>,
+    pub mean: Vec<f64>,
+    /// Distilled mass of the newest entry in the bucket
+    pub distilled_mass: f64,
+}
+
+impl HistoryBucket {
+    pub fn from_entry(entry: &ColumnEntry) -> Self {
+        Self {
+            from: entry.timestamp,
+            to: entry.timestamp,
+            count: 1,
+            min: entry.temperatures.clone(),
+            max: entry.temperatures.clone(),
+            mean: entry.temperatures.clone(),
+            distilled_mass: entry.distilled_mass,
+        }
+    }
+
+    pub fn merge(&mut self, other: &HistoryBucket) {
+        let total = (self.count + other.count) as f64;
+        let plates = self.mean.len().max(other.mean.len());
+        for plate in 0..plates {
+            let combine = |own: &mut Vec<f64>, theirs: &[f64], pick: fn(f64, f64) -> f64| match (
+                own.get(plate).copied(),
+                theirs.get(plate).copied(),
+            ) {
+                (Some(a), Some(b)) => own[plate] = pick(a, b),
+                (None, Some(b)) => own.push(b),
+                _ => {}
+            };
+            combine(&mut self.min, &other.min, f64::min);
+            combine(&mut self.max, &other.max, f64::max);
+
+            let (own, theirs) = (self.count as f64, other.count as f64);
+            match (
+                self.mean.get(plate).copied(),
+                other.mean.get(plate).copied(),
+            ) {
+                (Some(a), Some(b)) => self.mean[plate] = (a * own + b * theirs) / total,
+                (None, Some(b)) => self.mean.push(b),
+                _ => {}
+            }
+        }
+
+        if other.to >= self.to {
+            self.distilled_mass = other.distilled_mass;
+        }
+        self.from = self.from.min(other.from);
+        self.to = self.to.max(other.to);
+        self.count += other.count;
+    }
+}
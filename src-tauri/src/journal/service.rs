@@ -0,0 +1,211 @@
+use super::types::{JournalRecord, JournalSummary};
+use crate::data_manager::types::ColumnEntry;
+use crate::errors::{FileError, Result};
+use crate::settings::types::FsyncPolicy;
+use log::{info, warn};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const JOURNAL_DIRECTORY: &str = "journal";
+const JOURNAL_EXTENSION: &str = "jsonl";
+
+pub struct JournalService {
+    directory: PathBuf,
+}
+
+impl JournalService {
+    pub fn new(directory: &str) -> Self {
+        Self {
+            directory: PathBuf::from(directory),
+        }
+    }
+
+    pub fn get_journal_directory(app_handle: &AppHandle) -> Result<String> {
+        let path = app_handle
+            .path()
+            .app_data_dir()
+            .expect("Failed to get app data directory")
+            .join(JOURNAL_DIRECTORY);
+
+        path.to_str().map(|s| s.to_string()).ok_or_else(|| {
+            FileError::ReadError("Failed to convert path to string".to_string()).into()
+        })
+    }
+
+    pub fn create(&self, number_plates: u32, policy: FsyncPolicy) -> Result<JournalWriter> {
+        fs::create_dir_all(&self.directory)
+            .map_err(|e| FileError::CreateDirError(e.to_string()))?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let id = now.as_millis().to_string();
+        let path = self.journal_path(&id)?;
+
+        let file = OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| FileError::WriteError(e.to_string()))?;
+        info!("Recording journal {}", path.display());
+
+        let mut writer = JournalWriter {
+            file,
+            path,
+            policy,
+            unsynced: 0,
+        };
+        writer.write_record(&JournalRecord::Header {
+            started_at: now.as_millis() as u64,
+            number_plates,
+        })?;
+        writer.sync()?;
+
+        Ok(writer)
+    }
+
+    pub fn list_unfinished(&self) -> Result<Vec<JournalSummary>> {
+        if !self.directory.exists() {
+            return Ok(Vec::new());
+        }
+
+        let directory =
+            fs::read_dir(&self.directory).map_err(|e| FileError::ReadError(e.to_string()))?;
+
+        let mut summaries = Vec::new();
+        for item in directory.flatten() {
+            let path = item.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(JOURNAL_EXTENSION) {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+
+            match self.read(id) {
+                Ok((summary, _)) => summaries.push(summary),
+                Err(e) => warn!("Ignoring unreadable journal {}: {}", path.display(), e),
+            }
+        }
+
+        summaries.sort_by_key(|s| s.started_at);
+        Ok(summaries)
+    }
+
+    pub fn read(&self, id: &str) -> Result<(JournalSummary, Vec<Arc<ColumnEntry>>)> {
+        let path = self.journal_path(id)?;
+        let file = File::open(&path).map_err(|e| FileError::ReadError(e.to_string()))?;
+
+        let mut header = None;
+        let mut entries = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| FileError::ReadError(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // A crash can leave a partially written last line behind
+            match serde_json::from_str::<JournalRecord>(&line) {
+                Ok(JournalRecord::Header {
+                    started_at,
+                    number_plates,
+                }) => header = Some((started_at, number_plates)),
+                Ok(JournalRecord::Entry(entry)) => entries.push(Arc::new(entry)),
+                Err(e) => warn!("Skipping journal line {} of {}: {}", index + 1, id, e),
+            }
+        }
+
+        let (started_at, number_plates) = header
+            .ok_or_else(|| FileError::ParseJsonError(format!("Journal {} has no header", id)))?;
+
+        let summary = JournalSummary {
+            id: id.to_string(),
+            started_at,
+            number_plates,
+            entry_count: entries.len() as u32,
+            last_timestamp: entries.last().map(|e| e.timestamp),
+        };
+
+        Ok((summary, entries))
+    }
+
+    pub fn discard(&self, id: &str) -> Result<()> {
+        let path = self.journal_path(id)?;
+        fs::remove_file(&path).map_err(|e| FileError::WriteError(e.to_string()))?;
+        info!("Discarded journal {}", path.display());
+        Ok(())
+    }
+
+    fn journal_path(&self, id: &str) -> Result<PathBuf> {
+        // Journal ids are creation times in milliseconds, anything else is rejected
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+            return Err(FileError::InvalidPathError(id.to_string()).into());
+        }
+
+        Ok(self.directory.join(format!("{}.{}", id, JOURNAL_EXTENSION)))
+    }
+}
+
+pub struct JournalWriter {
+    file: File,
+    path: PathBuf,
+    policy: FsyncPolicy,
+    unsynced: u32,
+}
+
+impl JournalWriter {
+    pub fn id(&self) -> &str {
+        self.path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+    }
+
+    pub fn append(&mut self, entry: &ColumnEntry) -> Result<()> {
+        self.write_record(&JournalRecord::Entry(entry.clone()))?;
+        self.unsynced += 1;
+
+        let should_sync = match self.policy {
+            FsyncPolicy::EveryEntry => true,
+            FsyncPolicy::EveryEntries(count) => self.unsynced >= count.max(1),
+            FsyncPolicy::Never => false,
+        };
+        if should_sync {
+            self.sync()?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the journal once the recording ended normally.
+    pub fn finish(self) -> Result<()> {
+        drop(self.file);
+        fs::remove_file(&self.path).map_err(|e| FileError::WriteError(e.to_string()))?;
+        info!("Closed journal {}", self.path.display());
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &JournalRecord) -> Result<()> {
+        let mut line =
+            serde_json::to_string(record).map_err(|e| FileError::SerializeError(e.to_string()))?;
+        line.push('\n');
+
+        // One write per record keeps a torn write confined to the last line
+        self.file
+            .write_all(line.as_bytes())
+            .map_err(|e| FileError::WriteError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        self.file
+            .sync_data()
+            .map_err(|e| FileError::WriteError(e.to_string()))?;
+        self.unsynced = 0;
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use crate::data_manager::types::ColumnEntry;
+    use crate::journal::service::JournalService;
+    use crate::settings::types::FsyncPolicy;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    #[test]
+    fn test_journal_recovers_entries_after_torn_write() {
+        let directory = std::env::temp_dir().join(format!("journal-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let service = JournalService::new(directory.to_str().unwrap());
+
+        let mut writer = service.create(3, FsyncPolicy::EveryEntries(2)).unwrap();
+        for timestamp in [10, 11] {
+            writer
+                .append(&ColumnEntry {
+                    timestamp,
+                    temperatures: vec![78.0, 80.0, 92.5],
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let id = writer.id().to_string();
+
+        // Simulate a crash in the middle of writing the next record
+        let path = directory.join(format!("{}.jsonl", id));
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"type\":\"entry\",\"timest").unwrap();
+
+        let unfinished = service.list_unfinished().unwrap();
+        assert_eq!(unfinished.len(), 1);
+        assert_eq!(unfinished[0].number_plates, 3);
+        assert_eq!(unfinished[0].entry_count, 2);
+        assert_eq!(unfinished[0].last_timestamp, Some(11));
+
+        let (_, entries) = service.read(&id).unwrap();
+        assert_eq!(entries[1].temperatures, vec![78.0, 80.0, 92.5]);
+
+        writer.finish().unwrap();
+        assert!(service.list_unfinished().unwrap().is_empty());
+        assert!(service.read("../settings").is_err());
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
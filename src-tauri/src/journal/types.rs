@@ -0,0 +1,24 @@
+use crate::data_manager::types::ColumnEntry;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum JournalRecord {
+    #[serde(rename_all = "camelCase")]
+    Header {
+        started_at: u64,
+        number_plates: u32,
+    },
+    Entry(ColumnEntry),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalSummary {
+    pub id: String,
+    pub started_at: u64,
+    pub number_plates: u32,
+    pub entry_count: u32,
+    pub last_timestamp: Option<u64>,
+}
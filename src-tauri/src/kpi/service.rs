@@ -0,0 +1,175 @@
+use super::types::KpiSnapshot;
+use crate::calculations::service::StillBalance;
+use crate::data_manager::types::ColumnEntry;
+use crate::math::{integrate_trapezoidal, linear_slope};
+use crate::settings::types::KpiSettings;
+use std::collections::VecDeque;
+
+const TRAPEZOIDS: usize = 200;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    timestamp: u64,
+    distilled_mass: f64,
+    distillate_composition: f64,
+}
+
+/// Follows a batch entry by entry. The still is balanced with the Rayleigh
+/// equation over the measured compositions, the same balance that gives the
+/// distilled mass of imported entries.
+#[derive(Debug, Clone, Default)]
+pub struct KpiTracker {
+    settings: KpiSettings,
+    balance: StillBalance,
+    recent: VecDeque<Sample>,
+    last_timestamp: Option<u64>,
+    /// Playback runs back in time, so timestamps fall from one entry to the next
+    reverse: bool,
+}
+
+impl KpiTracker {
+    pub fn new(settings: KpiSettings) -> Self {
+        Self {
+            balance: StillBalance::new(settings.initial_charge),
+            settings,
+            ..Self::default()
+        }
+    }
+
+    /// The figures of every entry of a finished run, e.g. for an export
+    pub fn replay<'a, I>(settings: KpiSettings, entries: I) -> Vec<KpiSnapshot>
+    where
+        I: IntoIterator<Item = &'a ColumnEntry>,
+    {
+        let mut tracker = Self::new(settings);
+        entries
+            .into_iter()
+            .map(|entry| tracker.update(entry))
+            .collect()
+    }
+
+    /// Starts the batch over, in the same direction
+    pub fn reset(&mut self) {
+        *self = Self {
+            reverse: self.reverse,
+            ..Self::new(self.settings.clone())
+        };
+    }
+
+    /// Follows the direction of playback, turning around starts the batch over
+    pub fn set_reverse(&mut self, reverse: bool) {
+        if self.reverse != reverse {
+            self.reverse = reverse;
+            self.reset();
+        }
+    }
+
+    pub fn update(&mut self, entry: &ColumnEntry) -> KpiSnapshot {
+        // An entry against the direction of playback means it wrapped around
+        let wrapped = self.last_timestamp.is_some_and(|last| match self.reverse {
+            true => entry.timestamp > last,
+            false => entry.timestamp < last,
+        });
+        if wrapped {
+            self.reset();
+        }
+        self.last_timestamp = Some(entry.timestamp);
+
+        let still_composition = entry.compositions.first().and_then(|c| c.x_1);
+        let distillate_composition = entry.compositions.last().and_then(|c| c.y_1);
+        let mut snapshot = KpiSnapshot {
+            timestamp: entry.timestamp,
+            still_composition,
+            distillate_composition,
+            ..KpiSnapshot::default()
+        };
+        let (Some(x_b), Some(x_d)) = (still_composition, distillate_composition) else {
+            return snapshot;
+        };
+
+        let still_mass = self.balance.step(x_b, x_d);
+        let distilled_mass = self.balance.distilled_mass();
+        let initial_charge = self.balance.initial_charge();
+        let x_b0 = self.balance.initial_composition().unwrap_or(x_b);
+
+        self.recent.push_back(Sample {
+            timestamp: entry.timestamp,
+            distilled_mass,
+            distillate_composition: x_d,
+        });
+        let window = self.settings.trend_window_ms;
+        while self
+            .recent
+            .front()
+            .is_some_and(|sample| sample.timestamp.abs_diff(entry.timestamp) > window)
+        {
+            self.recent.pop_front();
+        }
+
+        // Slopes per minute, relative to this entry
+        let trend_of = |value: fn(&Sample) -> f64| {
+            let points: Vec<(f64, f64)> = self
+                .recent
+                .iter()
+                .map(|s| {
+                    let minutes = (s.timestamp as f64 - entry.timestamp as f64) / 60_000.0;
+                    (minutes, value(s))
+                })
+                .collect();
+            linear_slope(&points)
+        };
+        let flow_rate = trend_of(|s| s.distilled_mass).map(|rate| rate.max(0.0));
+        let trend = trend_of(|s| s.distillate_composition);
+
+        let target = self.settings.target_purity;
+        let rayleigh_time_to_target = if x_d <= target {
+            Some(0.0)
+        } else {
+            rayleigh_mass_at_target(x_b, x_d, target)
+                .zip(flow_rate.filter(|&rate| rate > 0.0))
+                .map(|(ratio, rate)| still_mass * (1.0 - ratio) / rate)
+        };
+        let trend_time_to_target = match trend {
+            _ if x_d <= target => Some(0.0),
+            Some(slope) if slope < 0.0 => Some((x_d - target) / -slope),
+            _ => None,
+        };
+
+        snapshot.still_mass = Some(still_mass);
+        snapshot.distilled_mass = Some(distilled_mass);
+        snapshot.flow_rate = flow_rate;
+        snapshot.recovery = (x_b0 > 0.0)
+            .then(|| (x_b0 * initial_charge - x_b * still_mass) / (x_b0 * initial_charge))
+            .map(|recovery| recovery.clamp(0.0, 1.0));
+        // The earlier of the two predictions, to be on the safe side
+        snapshot.time_to_target = match (rayleigh_time_to_target, trend_time_to_target) {
+            (Some(rayleigh), Some(trend)) => Some(rayleigh.min(trend)),
+            (rayleigh, trend) => rayleigh.or(trend),
+        };
+        snapshot.rayleigh_time_to_target = rayleigh_time_to_target;
+        snapshot.trend_time_to_target = trend_time_to_target;
+        snapshot
+    }
+}
+
+/// Share of the current still mass left once the distillate reaches `target`.
+/// The equilibrium is taken as a constant relative volatility fitted to the
+/// current still and distillate compositions.
+fn rayleigh_mass_at_target(x_b: f64, x_d: f64, target: f64) -> Option<f64> {
+    if !(0.0 < x_b && x_b < 1.0 && 0.0 < x_d && x_d < 1.0) {
+        return None;
+    }
+    let alpha = x_d * (1.0 - x_b) / (x_b * (1.0 - x_d));
+    if alpha <= 1.0 {
+        return None;
+    }
+
+    let x_b_target = target / (alpha - (alpha - 1.0) * target);
+    if x_b_target >= x_b {
+        return Some(1.0);
+    }
+    let equilibrium = |x: f64| alpha * x / (1.0 + (alpha - 1.0) * x);
+    let integral =
+        integrate_trapezoidal(|x| 1.0 / (equilibrium(x) - x), x_b, x_b_target, TRAPEZOIDS);
+    Some(integral.exp())
+}
@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod tests {
+    use crate::calculations::service::CalculationService;
+    use crate::calculations::types::CompositionResult;
+    use crate::data_manager::types::ColumnEntry;
+    use crate::kpi::KpiTracker;
+    use crate::settings::types::KpiSettings;
+
+    // Still and distillate of a mixture with a relative volatility of 20, the
+    // still loses 0.01 of light component a minute
+    fn entries() -> Vec<ColumnEntry> {
+        (0..20u64)
+            .map(|minute| {
+                let x_b = 0.6 - minute as f64 * 0.01;
+                let y_d = 20.0 * x_b / (1.0 + 19.0 * x_b);
+                ColumnEntry {
+                    timestamp: minute * 60_000,
+                    compositions: vec![
+                        CompositionResult {
+                            x_1: Some(x_b),
+                            y_1: None,
+                        },
+                        CompositionResult {
+                            x_1: None,
+                            y_1: Some(y_d),
+                        },
+                    ],
+                    ..ColumnEntry::default()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rayleigh_balance_and_predictions() {
+        let kpis = KpiTracker::replay(KpiSettings::default(), &entries());
+        let first = &kpis[0];
+        assert_eq!(first.distilled_mass, Some(0.0));
+        assert_eq!(first.flow_rate, None);
+
+        let last = kpis.last().unwrap();
+        let distilled = last.distilled_mass.unwrap();
+        assert!(distilled > 0.0 && distilled < 1000.0);
+        assert!(last.flow_rate.unwrap() > 0.0);
+        let recovery = last.recovery.unwrap();
+        assert!(recovery > distilled / 1000.0 && recovery < 1.0);
+
+        // The distillate is still above 0.9 and falling
+        assert!(last.distillate_composition.unwrap() > 0.9);
+        let rayleigh = last.rayleigh_time_to_target.unwrap();
+        let trend = last.trend_time_to_target.unwrap();
+        assert!(rayleigh > 0.0 && trend > 0.0);
+        assert_eq!(last.time_to_target, Some(rayleigh.min(trend)));
+
+        let reached = KpiTracker::replay(
+            KpiSettings {
+                target_purity: 0.95,
+                ..KpiSettings::default()
+            },
+            &entries(),
+        );
+        assert_eq!(reached.last().unwrap().time_to_target, Some(0.0));
+    }
+
+    #[test]
+    fn test_distilled_mass_matches_the_entries() {
+        let entries = entries();
+        let settings = KpiSettings {
+            initial_charge: 250.0,
+            ..KpiSettings::default()
+        };
+        let kpis = KpiTracker::replay(settings.clone(), &entries);
+        let masses = CalculationService::new().calculate_distilled_masses(
+            settings.initial_charge,
+            entries.iter().map(|e| e.compositions.as_slice()),
+        );
+        for (kpi, mass) in kpis.iter().zip(masses) {
+            assert_eq!(kpi.distilled_mass, Some(mass));
+        }
+    }
+
+    #[test]
+    fn test_jump_back_starts_over() {
+        let entries = entries();
+        let mut tracker = KpiTracker::new(KpiSettings::default());
+        for entry in &entries {
+            tracker.update(entry);
+        }
+        let restarted = tracker.update(&entries[0]);
+        assert_eq!(restarted.distilled_mass, Some(0.0));
+
+        // Played in reverse the batch carries on until playback wraps around
+        tracker.set_reverse(true);
+        tracker.update(&entries[19]);
+        let carried = tracker.update(&entries[18]);
+        assert!(carried.trend_time_to_target.is_some());
+        let wrapped = tracker.update(&entries[19]);
+        assert_eq!(wrapped.trend_time_to_target, None);
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// Operating figures of a batch at one entry. Masses are in the unit of the
+/// initial charge, times in minutes. Values are `None` until the compositions
+/// they need have been solved.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct KpiSnapshot {
+    pub timestamp: u64,
+    /// Liquid composition of the light component in the still
+    pub still_composition: Option<f64>,
+    /// Vapour composition at the top of the column
+    pub distillate_composition: Option<f64>,
+    /// Mass left in the still after the Rayleigh balance
+    pub still_mass: Option<f64>,
+    pub distilled_mass: Option<f64>,
+    /// Distillate mass per minute over the trend window
+    pub flow_rate: Option<f64>,
+    /// Share of the light component of the charge that was distilled
+    pub recovery: Option<f64>,
+    /// Time until the distillate falls below the target purity
+    pub time_to_target: Option<f64>,
+    /// The same prediction from the Rayleigh model alone
+    pub rayleigh_time_to_target: Option<f64>,
+    /// And from the recent trend of the distillate composition alone
+    pub trend_time_to_target: Option<f64>,
+}
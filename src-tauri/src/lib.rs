@@ -1,29 +1,74 @@
+mod alarms;
 mod calculations;
 mod commands;
+mod comparison;
+mod cuts;
 mod data_manager;
+mod diagnostics;
 mod errors;
 mod files;
+mod history;
+mod journal;
+mod kpi;
 mod math;
 mod modbus;
+mod processing;
+mod reports;
+mod runs;
+mod sessions;
 mod settings;
 
-use crate::commands::data_handle::{export_data, import_data, import_temperatures};
+use crate::commands::alarms::{
+    acknowledge_alarm, alarm_journal, alarm_status, export_alarm_journal, shelve_alarm,
+    unshelve_alarm,
+};
+use crate::commands::comparison::{clear_comparison, comparison_metrics, load_comparison};
+use crate::commands::cuts::{cut_status, switch_cut};
+use crate::commands::data_handle::{
+    cancel_import, commit_import, discard_import, export_data, export_report, import_data,
+    import_files, import_temperatures, list_sheets, preview_import,
+};
 use crate::commands::dialogs::{file_path, folder_path};
 use crate::commands::emitter::{
-    cancel_column_data, handle_skip, send_column_data, set_speed, toggle_column_data,
+    cancel_column_data, cancel_comparison_data, handle_skip, seek_playback, send_column_data,
+    send_comparison_data, set_looping, set_playback_range, set_reverse, set_speed, step_playback,
+    toggle_column_data, toggle_comparison_data,
 };
+use crate::commands::history::query_history;
 use crate::commands::modbus::{connect_modbus, disconnect_modbus};
-use crate::commands::settings::{available_ports, get_settings, save_settings};
-use crate::data_manager::types::ColumnEntry;
+use crate::commands::recovery::{
+    discard_recording, export_recording, list_unfinished_recordings, recover_recording,
+};
+use crate::commands::runs::{active_run, end_run, list_runs, set_run_phase, start_run};
+use crate::commands::sessions::{
+    add_annotation, default_equation_params, delete_annotation, delete_session, list_annotations,
+    list_sessions, open_session, recompute_session, rename_session, replay_session,
+    update_annotation,
+};
+use crate::commands::settings::{
+    available_ports, delete_import_mapping, get_settings, list_import_mappings,
+    save_import_mapping, save_settings,
+};
+use crate::comparison::Comparison;
+use crate::data_manager::import_export::types::PendingImport;
 use crate::modbus::client::ModbusClient;
 use crate::modbus::service::ModbusService;
+use alarms::{AlarmEngine, AlarmJournal};
+use cuts::CutTracker;
 use data_manager::factory::ProviderFactory;
 use data_manager::provider::DataProvider;
-use log::info;
+use history::History;
+use journal::{JournalService, JournalWriter};
+use kpi::KpiTracker;
+use log::{error, info};
+use processing::Pipeline;
 use rodbus::client::Channel;
+use runs::ActiveRun;
+use sessions::{AnnotationTrack, SessionService};
 use settings::types::Settings;
 use settings::SettingsService;
-use specta_typescript::Typescript;
+use specta_typescript::{BigIntExportBehavior, Typescript};
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use tauri::Manager;
 use tauri_specta::{collect_commands, Builder};
@@ -34,19 +79,28 @@ pub struct AppState {
     transmission_state: Arc<Mutex<TransmissionState>>,
     history: Arc<Mutex<History>>,
     modbus_channel: Arc<Mutex<Option<Channel>>>,
+    session_service: Arc<Mutex<SessionService>>,
+    journal_service: Arc<JournalService>,
+    journal_writer: Arc<Mutex<Option<JournalWriter>>>,
+    import_cancel: Arc<AtomicBool>,
+    pending_import: Arc<Mutex<Option<PendingImport>>>,
+    comparison: Arc<Mutex<Option<Comparison>>>,
+    alarm_journal: Arc<AlarmJournal>,
     settings_path: String,
 }
 
-#[derive(Default, Clone, Debug)]
-pub struct History {
-    pub history: Vec<Arc<ColumnEntry>>,
-}
-
 pub struct TransmissionState {
     pub data_provider: Box<dyn DataProvider + Send>,
     pub is_running: bool,
     pub is_paused: bool,
-    pub speed: u64,
+    pub speed_factor: f64,
+    pub recording_session: Option<i64>,
+    pub pipeline: Pipeline,
+    pub kpis: KpiTracker,
+    pub cuts: CutTracker,
+    pub alarms: AlarmEngine,
+    pub annotations: AnnotationTrack,
+    pub run: Option<ActiveRun>,
 }
 
 impl Clone for TransmissionState {
@@ -55,7 +109,14 @@ impl Clone for TransmissionState {
             data_provider: self.data_provider.clone_provider(),
             is_running: self.is_running,
             is_paused: self.is_paused,
-            speed: self.speed,
+            speed_factor: self.speed_factor,
+            recording_session: self.recording_session,
+            pipeline: self.pipeline.clone(),
+            kpis: self.kpis.clone(),
+            cuts: self.cuts.clone(),
+            alarms: self.alarms.clone(),
+            annotations: self.annotations.clone(),
+            run: self.run.clone(),
         }
     }
 }
@@ -66,7 +127,14 @@ impl TransmissionState {
             data_provider,
             is_running: false,
             is_paused: false,
-            speed: 1000,
+            speed_factor: 1.0,
+            recording_session: None,
+            pipeline: Pipeline::default(),
+            kpis: KpiTracker::default(),
+            cuts: CutTracker::default(),
+            alarms: AlarmEngine::default(),
+            annotations: AnnotationTrack::default(),
+            run: None,
         }
     }
     pub fn start(&mut self) {
@@ -83,6 +151,20 @@ impl TransmissionState {
 
     pub fn set_data_provider(&mut self, data_provider: Box<dyn DataProvider + Send>) {
         self.data_provider = data_provider;
+        self.recording_session = self.run_session();
+        match self.recording_session {
+            Some(session_id) if self.annotations.session_id() == Some(session_id) => {}
+            Some(session_id) => self.annotations = AnnotationTrack::new(session_id, Vec::new()),
+            None => self.annotations = AnnotationTrack::default(),
+        }
+    }
+
+    /// Live data of an active run is recorded into the session of the run
+    pub fn run_session(&self) -> Option<i64> {
+        self.run
+            .as_ref()
+            .filter(|_| self.data_provider.is_live())
+            .map(ActiveRun::session_id)
     }
 
     pub fn set_is_running(&mut self, is_running: bool) {
@@ -97,12 +179,28 @@ impl TransmissionState {
         self.data_provider.reset()?;
         self.is_running = false;
         self.is_paused = false;
-        self.speed = 1000;
+        self.speed_factor = 1.0;
+        self.recording_session = self.run_session();
         Ok(())
     }
 
-    pub fn set_speed(&mut self, speed: u64) {
-        self.speed = speed;
+    pub fn set_speed(&mut self, speed_factor: f64) {
+        self.speed_factor = speed_factor;
+    }
+
+    /// Everything worked out from the entries follows the direction of playback
+    pub fn set_reverse(&mut self, reverse: bool) {
+        self.pipeline.set_reverse(reverse);
+        self.kpis.set_reverse(reverse);
+        self.cuts.set_reverse(reverse);
+        self.alarms.set_reverse(reverse);
+    }
+
+    /// Playback jumped, so the filters, the batch and the alarm delays start over
+    pub fn playback_moved(&mut self) {
+        self.pipeline.reset();
+        self.kpis.reset();
+        self.alarms.restart_delays();
     }
 }
 
@@ -114,21 +212,74 @@ pub fn run() {
         connect_modbus,
         disconnect_modbus,
         export_data,
+        export_report,
         import_data,
+        import_files,
+        list_sheets,
+        preview_import,
+        cancel_import,
+        commit_import,
+        discard_import,
         file_path,
         folder_path,
         send_column_data,
         cancel_column_data,
         handle_skip,
         set_speed,
+        seek_playback,
+        set_playback_range,
+        set_looping,
+        set_reverse,
+        step_playback,
         import_temperatures,
         available_ports,
-        toggle_column_data
+        toggle_column_data,
+        list_sessions,
+        open_session,
+        rename_session,
+        delete_session,
+        replay_session,
+        recompute_session,
+        add_annotation,
+        update_annotation,
+        delete_annotation,
+        list_annotations,
+        start_run,
+        set_run_phase,
+        end_run,
+        active_run,
+        list_runs,
+        default_equation_params,
+        cut_status,
+        switch_cut,
+        alarm_status,
+        acknowledge_alarm,
+        shelve_alarm,
+        unshelve_alarm,
+        alarm_journal,
+        export_alarm_journal,
+        list_unfinished_recordings,
+        recover_recording,
+        export_recording,
+        discard_recording,
+        list_import_mappings,
+        save_import_mapping,
+        delete_import_mapping,
+        load_comparison,
+        comparison_metrics,
+        clear_comparison,
+        send_comparison_data,
+        toggle_comparison_data,
+        cancel_comparison_data,
+        query_history
     ]);
 
     #[cfg(debug_assertions)]
     builder
-        .export(Typescript::default(), "../src/bindings.ts")
+        .export(
+            Typescript::default().bigint(BigIntExportBehavior::Number),
+            "../src/bindings.ts",
+        )
         .expect("Failed to export typescript bindings");
 
     tauri::Builder::default()
@@ -165,13 +316,51 @@ pub fn run() {
                 });
             info!("Initial settings: {:?}", settings);
 
+            // Open the session database
+            let database_path = SessionService::get_database_path(app_handle)?;
+            let session_service = SessionService::open(&database_path)?;
+
+            // Look for recordings left behind by a crash
+            let journal_directory = JournalService::get_journal_directory(app_handle)?;
+            let journal_service = JournalService::new(&journal_directory);
+            // The UI offers to recover them, empty ones have nothing to offer
+            match journal_service.list_unfinished() {
+                Ok(journals) => {
+                    for journal in journals.iter().filter(|journal| journal.entry_count == 0) {
+                        if let Err(err) = journal_service.discard(&journal.id) {
+                            error!("Failed to discard empty journal {}: {}", journal.id, err);
+                        }
+                    }
+                    let unfinished = journals
+                        .iter()
+                        .filter(|journal| journal.entry_count > 0)
+                        .count();
+                    if unfinished > 0 {
+                        info!("Found {} unfinished recording(s)", unfinished)
+                    }
+                }
+                Err(err) => error!("Failed to scan recording journals: {}", err),
+            }
+
+            // Older entries of long runs are kept on disk
+            let history_directory = History::get_spill_directory(app_handle)?;
+
             let provider_factory = ProviderFactory::new();
             let provider = provider_factory.create_playback_provider(vec![], 0);
             // Initialize the app state
             let app_state = AppState {
                 transmission_state: Arc::new(Mutex::new(TransmissionState::new(provider))),
-                history: Arc::new(Mutex::new(History::default())),
+                history: Arc::new(Mutex::new(History::new(Some(history_directory)))),
                 modbus_channel: Arc::new(Mutex::new(None)),
+                session_service: Arc::new(Mutex::new(session_service)),
+                journal_service: Arc::new(journal_service),
+                journal_writer: Arc::new(Mutex::new(None)),
+                import_cancel: Arc::new(AtomicBool::new(false)),
+                pending_import: Arc::new(Mutex::new(None)),
+                comparison: Arc::new(Mutex::new(None)),
+                alarm_journal: Arc::new(AlarmJournal::new(AlarmJournal::get_journal_path(
+                    app_handle,
+                ))),
                 settings_path,
             };
 
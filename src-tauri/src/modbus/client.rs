@@ -1,47 +1,81 @@
+use std::net::IpAddr;
 use std::time::Duration;
 
 use super::types::ModbusConnection;
 use crate::errors::{ModbusError, Result};
-use crate::settings::types::ModbusSettings;
+use crate::settings::types::{ModbusSettings, ModbusTransport, SerialFlowControl, SerialParity};
+use log::{info, warn};
 use rodbus::client::*;
 use rodbus::*;
 
-#[derive(Debug, Clone)]
-pub struct ModbusClient;
+#[derive(Debug, Clone, Default)]
+pub struct ModbusClient {
+    inter_frame_delay: Duration,
+}
 
 impl ModbusClient {
     pub fn new() -> Self {
-        ModbusClient
+        Self::default()
+    }
+
+    /// A client pacing its requests the way the settings ask for
+    pub fn from_settings(settings: &ModbusSettings) -> Self {
+        let inter_frame_delay = match settings.transport {
+            ModbusTransport::Rtu => settings.serial.inter_frame_delay_ms,
+            ModbusTransport::Tcp { .. } => 0,
+        };
+        Self {
+            inter_frame_delay: Duration::from_millis(inter_frame_delay as u64),
+        }
+    }
+
+    async fn pause(&self) {
+        if !self.inter_frame_delay.is_zero() {
+            tokio::time::sleep(self.inter_frame_delay).await;
+        }
     }
 }
 
 impl ModbusConnection for ModbusClient {
     fn new() -> Self {
-        ModbusClient
+        Self::default()
     }
 
     async fn connect(&self, settings: &ModbusSettings) -> Result<Channel> {
-        let modbus_settings = SerialSettings {
-            baud_rate: settings.baudrate,
-            data_bits: rodbus::DataBits::Eight,
-            stop_bits: rodbus::StopBits::One,
-            parity: rodbus::Parity::None,
-            flow_control: rodbus::FlowControl::None,
-        };
-
         let max_queue_size = 1;
-        let retry_strategy = default_retry_strategy();
-        let decode = DecodeLevel::default();
-        let listener = None;
-
-        let mut channel = client::spawn_rtu_client_task(
-            &settings.usb_port,
-            modbus_settings,
-            max_queue_size,
-            retry_strategy,
-            decode,
-            listener,
+        let retry_strategy = doubling_retry_strategy(
+            Duration::from_millis(settings.retry.min_delay_ms as u64),
+            Duration::from_millis(settings.retry.max_delay_ms as u64),
         );
+        let decode = DecodeLevel::default();
+
+        let mut channel = match &settings.transport {
+            ModbusTransport::Rtu => {
+                info!(
+                    "Opening {} at {} baud {}",
+                    settings.usb_port,
+                    settings.baudrate,
+                    settings.serial.describe()
+                );
+                let serial_settings = serial_settings(settings);
+                client::spawn_rtu_client_task(
+                    &settings.usb_port,
+                    serial_settings,
+                    max_queue_size,
+                    retry_strategy,
+                    decode,
+                    None,
+                )
+            }
+            ModbusTransport::Tcp { host, port } => {
+                let host = match host.trim().parse::<IpAddr>() {
+                    Ok(ip) => HostAddr::ip(ip, *port),
+                    Err(_) => HostAddr::dns(host.trim().to_string(), *port),
+                };
+                info!("Connecting to Modbus TCP server {}", host);
+                client::spawn_tcp_client_task(host, max_queue_size, retry_strategy, decode, None)
+            }
+        };
 
         channel
             .enable()
@@ -55,24 +89,36 @@ impl ModbusConnection for ModbusClient {
             response_timeout: Duration::from_millis(settings.timeout as u64),
         };
 
-        for attempt in 1..=3 {
+        let attempts = settings.retry.handshake_attempts.max(1);
+        let mut last_error = None;
+        for attempt in 1..=attempts {
+            self.pause().await;
             match channel
                 .read_coils(param, AddressRange::try_from(1, 1).unwrap())
                 .await
             {
                 Ok(_) => {
-                    println!("Connected successfully");
+                    info!("Connected successfully");
+                    return Ok(channel);
+                }
+                // Whatever the device refused, it understood the request
+                Err(RequestError::Exception(code)) => {
+                    warn!("Connected, the probe was answered with {}", code);
                     return Ok(channel);
                 }
                 Err(err) => {
-                    println!("Attempt {}/3 failed: {:?}", attempt, err);
-                    if attempt < 3 {
-                        tokio::time::sleep(std::time::Duration::from_secs(1)).await
+                    warn!("Attempt {}/{} failed: {:?}", attempt, attempts, err);
+                    last_error = Some(err);
+                    if attempt < attempts {
+                        tokio::time::sleep(settings.retry.delay(attempt)).await
                     }
                 }
             }
         }
-        Err(ModbusError::ConnectionError("Failed to connect".to_string()).into())
+        let reason = last_error
+            .map(|err| handshake_failure(settings, err))
+            .unwrap_or_else(|| "Failed to connect".to_string());
+        Err(ModbusError::HandshakeError(reason).into())
     }
 
     async fn disconnect(&self, channel: Channel) -> Result<()> {
@@ -91,6 +137,7 @@ impl ModbusConnection for ModbusClient {
         range: AddressRange,
     ) -> Result<Vec<Indexed<bool>>> {
         // Implementation of read_coils method
+        self.pause().await;
         let result = channel
             .read_coils(param, range)
             .await
@@ -106,6 +153,7 @@ impl ModbusConnection for ModbusClient {
         range: AddressRange,
     ) -> Result<Vec<Indexed<u16>>> {
         // Implementation of read_holding_registers method
+        self.pause().await;
         let result = channel
             .read_holding_registers(param, range)
             .await
@@ -114,6 +162,21 @@ impl ModbusConnection for ModbusClient {
         Ok(result)
     }
 
+    async fn read_input_registers(
+        &self,
+        channel: &mut Channel,
+        param: RequestParam,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<u16>>> {
+        self.pause().await;
+        let result = channel
+            .read_input_registers(param, range)
+            .await
+            .map_err(|e| ModbusError::ReadInputRegistersError(e.to_string()))?;
+
+        Ok(result)
+    }
+
     async fn write_single_coil(
         &self,
         channel: &mut Channel,
@@ -121,6 +184,7 @@ impl ModbusConnection for ModbusClient {
         request: Indexed<bool>,
     ) -> Result<Indexed<bool>> {
         // Implementation of write_single_coil method
+        self.pause().await;
         let result = channel
             .write_single_coil(param, request)
             .await
@@ -135,6 +199,7 @@ impl ModbusConnection for ModbusClient {
         request: Indexed<u16>,
     ) -> Result<Indexed<u16>> {
         // Implementation of write_single_register method
+        self.pause().await;
         let result = channel
             .write_single_register(param, request)
             .await
@@ -142,3 +207,67 @@ impl ModbusConnection for ModbusClient {
         Ok(result)
     }
 }
+
+fn serial_settings(settings: &ModbusSettings) -> SerialSettings {
+    let line = &settings.serial;
+    SerialSettings {
+        baud_rate: settings.baudrate,
+        data_bits: match line.data_bits {
+            5 => DataBits::Five,
+            6 => DataBits::Six,
+            7 => DataBits::Seven,
+            _ => DataBits::Eight,
+        },
+        stop_bits: match line.stop_bits {
+            2 => StopBits::Two,
+            _ => StopBits::One,
+        },
+        parity: match line.parity {
+            SerialParity::None => Parity::None,
+            SerialParity::Even => Parity::Even,
+            SerialParity::Odd => Parity::Odd,
+        },
+        flow_control: match line.flow_control {
+            SerialFlowControl::None => FlowControl::None,
+            SerialFlowControl::Software => FlowControl::Software,
+            SerialFlowControl::Hardware => FlowControl::Hardware,
+        },
+    }
+}
+
+/// Names the settings most likely behind a failed handshake
+pub fn handshake_failure(settings: &ModbusSettings, err: RequestError) -> String {
+    let unit = settings.unit_id;
+    match (&settings.transport, err) {
+        (ModbusTransport::Rtu, RequestError::Io(_) | RequestError::NoConnection) => format!(
+            "Could not open serial port {}, check that the adapter is plugged in",
+            settings.usb_port
+        ),
+        (ModbusTransport::Tcp { host, port }, RequestError::Io(_) | RequestError::NoConnection) => {
+            format!("Could not reach {}:{}, check the host and port", host, port)
+        }
+        (ModbusTransport::Rtu, RequestError::ResponseTimeout) => format!(
+            "Unit {} did not answer within {} ms, check the unit id, the baud rate ({}) and the wiring",
+            unit, settings.timeout, settings.baudrate
+        ),
+        (ModbusTransport::Tcp { .. }, RequestError::ResponseTimeout) => format!(
+            "Unit {} did not answer within {} ms, check the unit id",
+            unit, settings.timeout
+        ),
+        (ModbusTransport::Rtu, RequestError::BadFrame(FrameParseError::CrcValidationFailure(..))) => {
+            format!(
+                "Answers fail the CRC check, the framing {} likely does not match the device",
+                settings.serial.describe()
+            )
+        }
+        (ModbusTransport::Tcp { .. }, RequestError::BadFrame(_)) => {
+            "Answers are not Modbus TCP, set the gateway to translate RTU to Modbus TCP".to_string()
+        }
+        (ModbusTransport::Rtu, RequestError::BadFrame(_) | RequestError::BadResponse(_)) => format!(
+            "Answers are garbled, check the baud rate ({}) and the framing {}",
+            settings.baudrate,
+            settings.serial.describe()
+        ),
+        (_, err) => err.to_string(),
+    }
+}
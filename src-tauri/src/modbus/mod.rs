@@ -1,4 +1,6 @@
 pub mod client;
+pub mod register_map;
 pub mod server;
 pub mod service;
+pub mod tests;
 pub mod types;
@@ -0,0 +1,73 @@
+use super::types::{RegisterFunction, RegisterPoint};
+use rodbus::Indexed;
+use std::collections::HashMap;
+
+/// A single read request returns at most 125 registers
+const MAX_BLOCK_SIZE: u16 = 125;
+
+/// Registers read in one request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterBlock {
+    pub function: RegisterFunction,
+    pub start: u16,
+    pub count: u16,
+}
+
+/// The requests that read every point. Points next to each other share a
+/// request, gaps are never read since devices may refuse those addresses.
+pub fn read_blocks(points: &[RegisterPoint]) -> Vec<RegisterBlock> {
+    let mut spans: Vec<(RegisterFunction, u16, u16)> = points
+        .iter()
+        .map(|point| {
+            let end = point.address.saturating_add(point.register_count() - 1);
+            (point.function, point.address, end)
+        })
+        .collect();
+    spans.sort_by_key(|&(function, start, _)| (function == RegisterFunction::Input, start));
+
+    let mut blocks: Vec<RegisterBlock> = Vec::new();
+    for (function, start, end) in spans {
+        if let Some(block) = blocks.last_mut() {
+            let block_end = block.start + (block.count - 1);
+            let merged = end.max(block_end) - block.start + 1;
+            if block.function == function
+                && start <= block_end.saturating_add(1)
+                && merged <= MAX_BLOCK_SIZE
+            {
+                block.count = merged;
+                continue;
+            }
+        }
+        blocks.push(RegisterBlock {
+            function,
+            start,
+            count: end - start + 1,
+        });
+    }
+    blocks
+}
+
+/// The registers of one poll by function and address
+#[derive(Debug, Default)]
+pub struct RegisterValues {
+    values: HashMap<(RegisterFunction, u16), u16>,
+}
+
+impl RegisterValues {
+    pub fn insert(&mut self, function: RegisterFunction, registers: &[Indexed<u16>]) {
+        for register in registers {
+            self.values
+                .insert((function, register.index), register.value);
+        }
+    }
+
+    /// The registers of the point, `None` if any of them was not read
+    pub fn registers(&self, point: &RegisterPoint) -> Option<Vec<u16>> {
+        (0..point.register_count())
+            .map(|offset| {
+                let address = point.address.checked_add(offset)?;
+                self.values.get(&(point.function, address)).copied()
+            })
+            .collect()
+    }
+}
@@ -70,6 +70,20 @@ impl<T: ModbusConnection> ModbusService<T> {
         Ok(response)
     }
 
+    pub async fn read_input_registers(
+        &self,
+        channel: &mut Channel,
+        param: RequestParam,
+        address: AddressRange,
+    ) -> Result<Vec<Indexed<u16>>> {
+        let response = self
+            .client
+            .read_input_registers(channel, param, address)
+            .await?;
+
+        Ok(response)
+    }
+
     pub async fn read_coils(
         &self,
         channel: &mut Channel,
@@ -0,0 +1,334 @@
+#[cfg(test)]
+mod tests {
+    use crate::calculations::service::CalculationService;
+    use crate::data_manager::live::LiveDataProvider;
+    use crate::data_manager::provider::DataProvider;
+    use crate::data_manager::types::{Quality, QualityReason};
+    use crate::diagnostics::SensorMonitor;
+    use crate::modbus::client::{handshake_failure, ModbusClient};
+    use crate::modbus::register_map::{read_blocks, RegisterBlock};
+    use crate::modbus::service::ModbusService;
+    use crate::modbus::types::{
+        ByteOrder, PointRole, RegisterDataType, RegisterFunction, RegisterPoint, WordOrder,
+    };
+    use crate::settings::types::{
+        DiagnosticsSettings, ModbusSettings, ModbusTransport, RetrySettings, SerialLineSettings,
+        SerialParity,
+    };
+    use rodbus::client::RequestParam;
+    use rodbus::server::*;
+    use rodbus::*;
+    use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::Mutex;
+
+    struct Registers;
+
+    impl RequestHandler for Registers {
+        fn read_coil(&self, _address: u16) -> Result<bool, ExceptionCode> {
+            Ok(false)
+        }
+
+        fn read_holding_register(&self, address: u16) -> Result<u16, ExceptionCode> {
+            Ok(address * 10)
+        }
+
+        fn read_input_register(&self, address: u16) -> Result<u16, ExceptionCode> {
+            Ok(800 + address)
+        }
+    }
+
+    fn free_port() -> u16 {
+        TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    fn point(address: u16, function: RegisterFunction, role: PointRole) -> RegisterPoint {
+        RegisterPoint {
+            name: format!("{:?} {}", function, address),
+            address,
+            function,
+            data_type: RegisterDataType::U16,
+            word_order: WordOrder::HighFirst,
+            byte_order: ByteOrder::BigEndian,
+            scale: 0.1,
+            offset: 0.0,
+            unit: String::new(),
+            role,
+        }
+    }
+
+    /// A local server of unit 10, with the settings to reach it
+    async fn local_server() -> (ServerHandle, ModbusSettings) {
+        let port = free_port();
+        let server = spawn_tcp_server_task(
+            1,
+            SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+            ServerHandlerMap::single(UnitId::new(10), Registers.wrap()),
+            AddressFilter::Any,
+            DecodeLevel::default(),
+        )
+        .await
+        .unwrap();
+
+        let settings = ModbusSettings {
+            transport: ModbusTransport::Tcp {
+                host: "127.0.0.1".into(),
+                port,
+            },
+            ..ModbusSettings::default()
+        };
+        (server, settings)
+    }
+
+    #[tokio::test]
+    async fn test_connects_to_a_tcp_server() {
+        let (_server, settings) = local_server().await;
+        let service = ModbusService::new(ModbusClient::new());
+        let mut channel = service.connect(&settings).await.unwrap();
+
+        let registers = service
+            .read_holding_registers(
+                &mut channel,
+                RequestParam::new(UnitId::new(10), Duration::from_secs(1)),
+                AddressRange::try_from(100, 2).unwrap(),
+            )
+            .await
+            .unwrap();
+        let values: Vec<u16> = registers.iter().map(|register| register.value).collect();
+        assert_eq!(values, vec![1000, 1010]);
+    }
+
+    #[tokio::test]
+    async fn test_live_provider_reads_the_configured_range() {
+        let (_server, settings) = local_server().await;
+        let service = ModbusService::new(ModbusClient::new());
+        let channel = service.connect(&settings).await.unwrap();
+
+        let settings = ModbusSettings {
+            initial_address: 100,
+            count: 3,
+            ..settings
+        };
+        let mut provider = LiveDataProvider::new(
+            Arc::new(Mutex::new(Some(channel))),
+            Arc::new(CalculationService::new()),
+            settings.clone(),
+            SensorMonitor::new(DiagnosticsSettings::default()),
+        );
+        // One register per plate, the spare one is not read
+        let entry = provider.get_next_entry(2).await.unwrap();
+        assert_eq!(entry.temperatures, vec![10.0, 10.1]);
+        // Spread over the column, the plates in between are interpolated
+        let entry = provider.get_next_entry(5).await.unwrap();
+        assert_eq!(entry.temperatures, vec![10.0, 10.05, 10.1, 10.15, 10.2]);
+        assert!(entry.temperature_quality(2).is_good());
+        assert!(!entry.temperature_quality(1).is_good());
+
+        // Applied from the next poll on, without reconnecting
+        provider.apply_settings(&ModbusSettings {
+            initial_address: 200,
+            count: 2,
+            ..settings
+        });
+        let entry = provider.get_next_entry(2).await.unwrap();
+        assert_eq!(entry.temperatures, vec![20.0, 20.1]);
+    }
+
+    #[tokio::test]
+    async fn test_live_provider_decodes_the_register_map() {
+        let (_server, settings) = local_server().await;
+        let service = ModbusService::new(ModbusClient::new());
+        let channel = service.connect(&settings).await.unwrap();
+
+        let plate = |plate| PointRole::PlateTemperature { plate };
+        let settings = ModbusSettings {
+            register_map: vec![
+                point(0, RegisterFunction::Input, plate(0)),
+                point(1, RegisterFunction::Input, plate(2)),
+                RegisterPoint {
+                    scale: 0.02,
+                    ..point(5, RegisterFunction::Holding, PointRole::Pressure)
+                },
+                // Beyond the column, kept as a measurement only
+                point(2, RegisterFunction::Input, plate(7)),
+            ],
+            ..settings
+        };
+        assert!(settings.validate().is_ok());
+        let mut provider = LiveDataProvider::new(
+            Arc::new(Mutex::new(Some(channel))),
+            Arc::new(CalculationService::new()),
+            settings,
+            SensorMonitor::new(DiagnosticsSettings::default()),
+        );
+        let entry = provider.get_next_entry(3).await.unwrap();
+        assert_eq!(entry.temperatures, vec![80.0, 80.05, 80.1]);
+        assert_eq!(entry.pressure, Some(1.0));
+        assert_eq!(entry.measurements.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_live_provider_marks_unreadable_plates() {
+        let (_server, settings) = local_server().await;
+        let service = ModbusService::new(ModbusClient::new());
+        let channel = service.connect(&settings).await.unwrap();
+
+        let plate = |plate| PointRole::PlateTemperature { plate };
+        let settings = ModbusSettings {
+            register_map: vec![
+                point(1, RegisterFunction::Input, plate(2)),
+                // Input registers 31904 and 31905 read 0x7FC0 0x7FC1, a NaN
+                RegisterPoint {
+                    data_type: RegisterDataType::F32,
+                    scale: 1.0,
+                    ..point(31904, RegisterFunction::Input, plate(1))
+                },
+                point(0, RegisterFunction::Input, plate(0)),
+            ],
+            ..settings
+        };
+        let mut provider = LiveDataProvider::new(
+            Arc::new(Mutex::new(Some(channel))),
+            Arc::new(CalculationService::new()),
+            settings,
+            SensorMonitor::new(DiagnosticsSettings::default()),
+        );
+        let entry = provider.get_next_entry(3).await.unwrap();
+        assert_eq!(entry.temperatures, vec![80.0, 80.05, 80.1]);
+        assert_eq!(
+            entry.temperature_quality(1),
+            Quality::Bad(QualityReason::MissingValue)
+        );
+        assert!(entry.temperature_quality(0).is_good());
+        assert!(entry.temperature_quality(2).is_good());
+    }
+
+    #[test]
+    fn test_register_points_decode_and_share_reads() {
+        let raw = RegisterPoint {
+            scale: 1.0,
+            ..point(0, RegisterFunction::Holding, PointRole::Other)
+        };
+        let signed = RegisterPoint {
+            data_type: RegisterDataType::I16,
+            ..raw.clone()
+        };
+        assert_eq!(signed.decode(&[0xFF9C]), Some(-100.0));
+        let double_word = RegisterPoint {
+            data_type: RegisterDataType::U32,
+            ..raw.clone()
+        };
+        assert_eq!(double_word.decode(&[0x0001, 0x0002]), Some(65538.0));
+        let float = RegisterPoint {
+            data_type: RegisterDataType::F32,
+            word_order: WordOrder::LowFirst,
+            byte_order: ByteOrder::LittleEndian,
+            ..raw.clone()
+        };
+        let bits = 78.5f32.to_bits();
+        let swapped = [
+            (bits as u16).swap_bytes(),
+            ((bits >> 16) as u16).swap_bytes(),
+        ];
+        assert_eq!(float.decode(&swapped), Some(78.5));
+        assert_eq!(float.decode(&swapped[..1]), None);
+        // Both registers make up the raw value, not just the one sharing the exponent
+        assert_eq!(float.raw(&swapped), Some(bits));
+        assert_eq!(double_word.raw(&[0x0001, 0x0002]), Some(0x0001_0002));
+        assert_eq!(signed.raw(&[0xFF9C]), Some(0xFF9C));
+
+        let blocks = read_blocks(&[
+            point(12, RegisterFunction::Holding, PointRole::Other),
+            double_word.clone(),
+            point(2, RegisterFunction::Holding, PointRole::Other),
+            point(1, RegisterFunction::Input, PointRole::Other),
+        ]);
+        let block = |function, start, count| RegisterBlock {
+            function,
+            start,
+            count,
+        };
+        assert_eq!(
+            blocks,
+            vec![
+                block(RegisterFunction::Holding, 0, 3),
+                block(RegisterFunction::Holding, 12, 1),
+                block(RegisterFunction::Input, 1, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tcp_settings_need_a_host() {
+        let settings = ModbusSettings {
+            transport: ModbusTransport::Tcp {
+                host: " ".into(),
+                port: 502,
+            },
+            ..ModbusSettings::default()
+        };
+        assert!(settings.validate().is_err());
+        assert!(ModbusSettings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_handshake_failures_name_the_setting() {
+        let settings = ModbusSettings {
+            usb_port: "/dev/ttyUSB0".into(),
+            serial: SerialLineSettings {
+                parity: SerialParity::Even,
+                ..SerialLineSettings::default()
+            },
+            ..ModbusSettings::default()
+        };
+        assert!(settings.validate().is_ok());
+
+        let crc = handshake_failure(
+            &settings,
+            RequestError::BadFrame(FrameParseError::CrcValidationFailure(1, 2)),
+        );
+        assert!(crc.contains("8E1"));
+        let timeout = handshake_failure(&settings, RequestError::ResponseTimeout);
+        assert!(timeout.contains("9600"));
+        let closed = handshake_failure(&settings, RequestError::NoConnection);
+        assert!(closed.contains("/dev/ttyUSB0"));
+
+        // A transparent gateway passes the RTU frames through unchanged
+        let gateway = ModbusSettings {
+            transport: ModbusTransport::Tcp {
+                host: "10.0.0.5".into(),
+                port: 502,
+            },
+            ..settings.clone()
+        };
+        let tunnelled = handshake_failure(
+            &gateway,
+            RequestError::BadFrame(FrameParseError::UnknownProtocolId(0x0A03)),
+        );
+        assert!(tunnelled.contains("gateway"));
+
+        let seven_stop_bits = ModbusSettings {
+            serial: SerialLineSettings {
+                stop_bits: 7,
+                ..SerialLineSettings::default()
+            },
+            ..ModbusSettings::default()
+        };
+        assert!(seven_stop_bits.validate().is_err());
+
+        // Probes back off like the reconnects, up to the maximum delay
+        let retry = RetrySettings {
+            min_delay_ms: 500,
+            max_delay_ms: 1500,
+            handshake_attempts: 4,
+        };
+        assert_eq!(retry.delay(1), Duration::from_millis(500));
+        assert_eq!(retry.delay(2), Duration::from_millis(1000));
+        assert_eq!(retry.delay(3), Duration::from_millis(1500));
+    }
+}
@@ -3,6 +3,8 @@ use rodbus::*;
 
 use crate::errors::Result;
 use crate::settings::types::ModbusSettings;
+use serde::{Deserialize, Serialize};
+use specta::Type;
 
 pub trait ModbusConnection {
     fn new() -> Self;
@@ -20,6 +22,12 @@ pub trait ModbusConnection {
         param: RequestParam,
         range: AddressRange,
     ) -> Result<Vec<Indexed<u16>>>;
+    async fn read_input_registers(
+        &self,
+        channel: &mut Channel,
+        param: RequestParam,
+        range: AddressRange,
+    ) -> Result<Vec<Indexed<u16>>>;
     async fn write_single_coil(
         &self,
         channel: &mut Channel,
@@ -33,3 +41,125 @@ pub trait ModbusConnection {
         request: Indexed<u16>,
     ) -> Result<Indexed<u16>>;
 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum RegisterFunction {
+    /// Function code 3
+    #[default]
+    Holding,
+    /// Function code 4
+    Input,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum RegisterDataType {
+    #[default]
+    U16,
+    I16,
+    /// Two registers
+    U32,
+    /// Two registers, IEEE 754
+    F32,
+}
+
+/// Which register of a two register value holds the high word
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum WordOrder {
+    #[default]
+    HighFirst,
+    LowFirst,
+}
+
+/// Order of the two bytes within every register
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ByteOrder {
+    #[default]
+    BigEndian,
+    /// Bytes swapped, as some transmitters send them
+    LittleEndian,
+}
+
+/// What a point measures. Plates count from the bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum PointRole {
+    /// In °C once scaled
+    #[serde(rename_all = "camelCase")]
+    PlateTemperature {
+        plate: u32,
+    },
+    Pressure,
+    Flow,
+    Level,
+    Other,
+}
+
+/// A value of the register map. The decoded number is turned into the
+/// engineering value as `raw * scale + offset`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterPoint {
+    pub name: String,
+    pub address: u16,
+    #[serde(default)]
+    pub function: RegisterFunction,
+    #[serde(default)]
+    pub data_type: RegisterDataType,
+    #[serde(default)]
+    pub word_order: WordOrder,
+    #[serde(default)]
+    pub byte_order: ByteOrder,
+    pub scale: f64,
+    #[serde(default)]
+    pub offset: f64,
+    #[serde(default)]
+    pub unit: String,
+    pub role: PointRole,
+}
+
+impl RegisterPoint {
+    pub fn register_count(&self) -> u16 {
+        match self.data_type {
+            RegisterDataType::U16 | RegisterDataType::I16 => 1,
+            RegisterDataType::U32 | RegisterDataType::F32 => 2,
+        }
+    }
+
+    /// The point's registers as one number, after byte and word order, with
+    /// both registers of a 32 bit point
+    pub fn raw(&self, registers: &[u16]) -> Option<u32> {
+        let word = |index: usize| -> Option<u16> {
+            let register = *registers.get(index)?;
+            Some(match self.byte_order {
+                ByteOrder::BigEndian => register,
+                ByteOrder::LittleEndian => register.swap_bytes(),
+            })
+        };
+        if self.register_count() == 1 {
+            return word(0).map(u32::from);
+        }
+
+        let (high, low) = match self.word_order {
+            WordOrder::HighFirst => (word(0)?, word(1)?),
+            WordOrder::LowFirst => (word(1)?, word(0)?),
+        };
+        Some(((high as u32) << 16) | low as u32)
+    }
+
+    /// The engineering value of the point's registers, `None` for a value that
+    /// is not a number
+    pub fn decode(&self, registers: &[u16]) -> Option<f64> {
+        let raw = self.raw(registers)?;
+        let raw = match self.data_type {
+            RegisterDataType::U16 | RegisterDataType::U32 => raw as f64,
+            RegisterDataType::I16 => raw as u16 as i16 as f64,
+            RegisterDataType::F32 => f32::from_bits(raw) as f64,
+        };
+        let value = raw * self.scale + self.offset;
+        value.is_finite().then_some(value)
+    }
+}
@@ -0,0 +1,252 @@
+use super::types::{DerivedKind, DerivedValue, ProcessedEntry, StageConfig, TemperatureUnit};
+use crate::data_manager::types::ColumnEntry;
+use crate::settings::types::ProcessingSettings;
+use std::collections::VecDeque;
+
+/// A configured stage with the state it keeps per plate
+#[derive(Debug, Clone)]
+enum Stage {
+    MovingAverage {
+        window: usize,
+        samples: Vec<VecDeque<f64>>,
+    },
+    Ewma {
+        alpha: f64,
+        values: Vec<Option<f64>>,
+    },
+    Median {
+        window: usize,
+        samples: Vec<VecDeque<f64>>,
+    },
+    Deadband {
+        band: f64,
+        values: Vec<Option<f64>>,
+    },
+    OutlierRejection {
+        window: usize,
+        max_deviation: f64,
+        samples: Vec<VecDeque<f64>>,
+    },
+    UnitConversion {
+        unit: TemperatureUnit,
+    },
+    Derived {
+        name: String,
+        kind: DerivedKind,
+        previous: Option<(u64, f64)>,
+    },
+}
+
+impl Stage {
+    fn new(config: &StageConfig) -> Self {
+        match config {
+            StageConfig::MovingAverage { window } => Stage::MovingAverage {
+                window: *window as usize,
+                samples: Vec::new(),
+            },
+            StageConfig::Ewma { alpha } => Stage::Ewma {
+                alpha: *alpha,
+                values: Vec::new(),
+            },
+            StageConfig::Median { window } => Stage::Median {
+                window: *window as usize,
+                samples: Vec::new(),
+            },
+            StageConfig::Deadband { band } => Stage::Deadband {
+                band: *band,
+                values: Vec::new(),
+            },
+            StageConfig::OutlierRejection {
+                window,
+                max_deviation,
+            } => Stage::OutlierRejection {
+                window: *window as usize,
+                max_deviation: *max_deviation,
+                samples: Vec::new(),
+            },
+            StageConfig::UnitConversion { unit } => Stage::UnitConversion { unit: *unit },
+            StageConfig::Derived { name, value } => Stage::Derived {
+                name: name.clone(),
+                kind: *value,
+                previous: None,
+            },
+        }
+    }
+
+    fn process(&mut self, entry: &mut ProcessedEntry) {
+        let plates = entry.temperatures.len();
+        match self {
+            Stage::MovingAverage { window, samples } => {
+                samples.resize_with(plates, VecDeque::new);
+                for (value, samples) in entry.temperatures.iter_mut().zip(samples) {
+                    push_bounded(samples, *value, *window);
+                    *value = samples.iter().sum::<f64>() / samples.len() as f64;
+                }
+            }
+            Stage::Ewma { alpha, values } => {
+                values.resize(plates, None);
+                for (value, previous) in entry.temperatures.iter_mut().zip(values) {
+                    let smoothed = match previous {
+                        Some(previous) => *alpha * *value + (1.0 - *alpha) * *previous,
+                        None => *value,
+                    };
+                    *previous = Some(smoothed);
+                    *value = smoothed;
+                }
+            }
+            Stage::Median { window, samples } => {
+                samples.resize_with(plates, VecDeque::new);
+                for (value, samples) in entry.temperatures.iter_mut().zip(samples) {
+                    push_bounded(samples, *value, *window);
+                    *value = median(samples);
+                }
+            }
+            Stage::Deadband { band, values } => {
+                values.resize(plates, None);
+                for (value, held) in entry.temperatures.iter_mut().zip(values) {
+                    match held {
+                        Some(held) if (*value - *held).abs() <= *band => *value = *held,
+                        _ => *held = Some(*value),
+                    }
+                }
+            }
+            Stage::OutlierRejection {
+                window,
+                max_deviation,
+                samples,
+            } => {
+                samples.resize_with(plates, VecDeque::new);
+                for (value, samples) in entry.temperatures.iter_mut().zip(samples) {
+                    // A lasting step moves the median within half a window
+                    let reference = (!samples.is_empty()).then(|| median(samples));
+                    push_bounded(samples, *value, *window);
+                    if let Some(reference) = reference {
+                        if (*value - reference).abs() > *max_deviation {
+                            *value = reference;
+                        }
+                    }
+                }
+            }
+            Stage::UnitConversion { unit } => {
+                for value in entry.temperatures.iter_mut() {
+                    *value = unit.convert(entry.unit.to_celsius(*value));
+                }
+                entry.unit = *unit;
+            }
+            Stage::Derived {
+                name,
+                kind,
+                previous,
+            } => {
+                let value = match kind {
+                    DerivedKind::ColumnDelta => entry
+                        .temperatures
+                        .first()
+                        .zip(entry.temperatures.last())
+                        .map(|(bottom, top)| bottom - top),
+                    DerivedKind::MeanTemperature => {
+                        (plates > 0).then(|| entry.temperatures.iter().sum::<f64>() / plates as f64)
+                    }
+                    DerivedKind::PlateRate { plate } => {
+                        let current = entry.temperatures.get(*plate as usize).copied();
+                        let rate = previous.zip(current).and_then(|((timestamp, old), new)| {
+                            // Signed, so reverse playback gives the recorded rate
+                            let minutes = (entry.timestamp as f64 - timestamp as f64) / 60_000.0;
+                            (minutes != 0.0).then(|| (new - old) / minutes)
+                        });
+                        *previous = current.map(|current| (entry.timestamp, current));
+                        rate
+                    }
+                };
+                entry.derived.push(DerivedValue {
+                    name: name.clone(),
+                    value,
+                });
+            }
+        }
+    }
+}
+
+fn push_bounded(samples: &mut VecDeque<f64>, value: f64, window: usize) {
+    samples.push_back(value);
+    while samples.len() > window {
+        samples.pop_front();
+    }
+}
+
+fn median(samples: &VecDeque<f64>) -> f64 {
+    let mut sorted: Vec<f64> = samples.iter().copied().collect();
+    sorted.sort_by(f64::total_cmp);
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+
+/// Runs the stages from the settings over every entry, in order. Raw entries are
+/// left untouched, recording and history keep working on them.
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    configs: Vec<StageConfig>,
+    stages: Vec<Stage>,
+    last_timestamp: Option<u64>,
+    /// Playback runs back in time, so timestamps fall from one entry to the next
+    reverse: bool,
+}
+
+impl Pipeline {
+    pub fn new(settings: &ProcessingSettings) -> Self {
+        Self {
+            configs: settings.stages.clone(),
+            stages: settings.stages.iter().map(Stage::new).collect(),
+            last_timestamp: None,
+            reverse: false,
+        }
+    }
+
+    /// Processes the entry unless no stages are set up, the raw entry already
+    /// has everything then
+    pub fn process_if_configured(&mut self, entry: &ColumnEntry) -> Option<ProcessedEntry> {
+        (!self.stages.is_empty()).then(|| self.process(entry))
+    }
+
+    /// Forgets the filter state. Callers do this when playback is moved, a loop
+    /// wrapping around is caught by `process` itself.
+    pub fn reset(&mut self) {
+        self.stages = self.configs.iter().map(Stage::new).collect();
+        self.last_timestamp = None;
+    }
+
+    /// Follows the direction of playback, turning around starts the filters over
+    pub fn set_reverse(&mut self, reverse: bool) {
+        if self.reverse != reverse {
+            self.reverse = reverse;
+            self.reset();
+        }
+    }
+
+    pub fn process(&mut self, entry: &ColumnEntry) -> ProcessedEntry {
+        // An entry against the direction of playback means it wrapped around
+        let wrapped = self.last_timestamp.is_some_and(|last| match self.reverse {
+            true => entry.timestamp > last,
+            false => entry.timestamp < last,
+        });
+        if wrapped {
+            self.reset();
+        }
+        self.last_timestamp = Some(entry.timestamp);
+
+        let mut processed = ProcessedEntry {
+            timestamp: entry.timestamp,
+            temperatures: entry.temperatures.clone(),
+            unit: TemperatureUnit::Celsius,
+            derived: Vec::new(),
+        };
+        for stage in self.stages.iter_mut() {
+            stage.process(&mut processed);
+        }
+        processed
+    }
+}
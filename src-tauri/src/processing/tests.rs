@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use crate::data_manager::types::ColumnEntry;
+    use crate::processing::types::{DerivedKind, StageConfig, TemperatureUnit};
+    use crate::processing::Pipeline;
+    use crate::settings::types::ProcessingSettings;
+
+    fn run(stages: Vec<StageConfig>, samples: &[(u64, Vec<f64>)]) -> Vec<Vec<f64>> {
+        let mut pipeline = Pipeline::new(&ProcessingSettings { stages });
+        samples
+            .iter()
+            .map(|(second, temperatures)| {
+                pipeline
+                    .process(&ColumnEntry {
+                        timestamp: second * 1000,
+                        temperatures: temperatures.clone(),
+                        ..ColumnEntry::default()
+                    })
+                    .temperatures
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_filters() {
+        let samples: Vec<(u64, Vec<f64>)> = [80.0, 82.0, 120.0, 84.0, 84.05]
+            .iter()
+            .enumerate()
+            .map(|(second, &value)| (second as u64, vec![value]))
+            .collect();
+
+        let averaged = run(vec![StageConfig::MovingAverage { window: 2 }], &samples);
+        assert_eq!(averaged[1], vec![81.0]);
+        assert_eq!(averaged[3], vec![102.0]);
+
+        let smoothed = run(vec![StageConfig::Ewma { alpha: 0.5 }], &samples);
+        assert_eq!(smoothed[1], vec![81.0]);
+
+        let medians = run(vec![StageConfig::Median { window: 3 }], &samples);
+        assert_eq!(medians[2], vec![82.0]);
+
+        // The spike is replaced, the deadband then holds the small step
+        let cleaned = run(
+            vec![
+                StageConfig::OutlierRejection {
+                    window: 3,
+                    max_deviation: 10.0,
+                },
+                StageConfig::Deadband { band: 0.1 },
+            ],
+            &samples,
+        );
+        assert_eq!(cleaned[2], vec![81.0]);
+        assert_eq!(cleaned[3], vec![84.0]);
+        assert_eq!(cleaned[4], vec![84.0]);
+    }
+
+    #[test]
+    fn test_unit_conversion_and_derived_values() {
+        let mut pipeline = Pipeline::new(&ProcessingSettings {
+            stages: vec![
+                StageConfig::UnitConversion {
+                    unit: TemperatureUnit::Kelvin,
+                },
+                StageConfig::Derived {
+                    name: "delta".to_string(),
+                    value: DerivedKind::ColumnDelta,
+                },
+                StageConfig::Derived {
+                    name: "top rate".to_string(),
+                    value: DerivedKind::PlateRate { plate: 1 },
+                },
+            ],
+        });
+        let mut process = |timestamp: u64, temperatures: Vec<f64>| {
+            pipeline.process(&ColumnEntry {
+                timestamp,
+                temperatures,
+                ..ColumnEntry::default()
+            })
+        };
+
+        let first = process(0, vec![90.0, 70.0]);
+        assert_eq!(first.unit, TemperatureUnit::Kelvin);
+        assert!((first.temperatures[0] - 363.15).abs() < 1e-9);
+        assert!((first.derived[0].value.unwrap() - 20.0).abs() < 1e-9);
+        assert_eq!(first.derived[1].value, None);
+
+        let second = process(30_000, vec![90.0, 71.0]);
+        assert!((second.derived[1].value.unwrap() - 2.0).abs() < 1e-9);
+
+        // Wrapping back to the start of a loop starts the filters over
+        assert_eq!(process(0, vec![90.0, 70.0]).derived[1].value, None);
+
+        // Playing backwards keeps the state from one entry to the next
+        pipeline.set_reverse(true);
+        let mut process = |timestamp: u64, temperatures: Vec<f64>| {
+            pipeline.process(&ColumnEntry {
+                timestamp,
+                temperatures,
+                ..ColumnEntry::default()
+            })
+        };
+        process(60_000, vec![90.0, 72.0]);
+        let back = process(30_000, vec![90.0, 71.0]);
+        assert!((back.derived[1].value.unwrap() - 2.0).abs() < 1e-9);
+
+        assert!(StageConfig::Ewma { alpha: 0.0 }.validate().is_err());
+        assert!(StageConfig::Median { window: 0 }.validate().is_err());
+    }
+}
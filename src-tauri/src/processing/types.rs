@@ -0,0 +1,120 @@
+use crate::errors::{Result, SettingsError};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl TemperatureUnit {
+    pub fn convert(self, celsius: f64) -> f64 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+            TemperatureUnit::Kelvin => celsius + 273.15,
+        }
+    }
+
+    pub fn to_celsius(self, value: f64) -> f64 {
+        match self {
+            TemperatureUnit::Celsius => value,
+            TemperatureUnit::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+            TemperatureUnit::Kelvin => value - 273.15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum DerivedKind {
+    /// Bottom minus top temperature
+    ColumnDelta,
+    /// Mean temperature of all plates
+    MeanTemperature,
+    /// Change of a plate's temperature per minute
+    #[serde(rename_all = "camelCase")]
+    PlateRate { plate: u32 },
+}
+
+/// One step of the pipeline. Filters work on every plate on their own, windows
+/// are counted in samples.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", tag = "stage")]
+pub enum StageConfig {
+    #[serde(rename_all = "camelCase")]
+    MovingAverage { window: u32 },
+    /// Exponentially weighted moving average, `alpha` is the weight of the newest sample
+    #[serde(rename_all = "camelCase")]
+    Ewma { alpha: f64 },
+    #[serde(rename_all = "camelCase")]
+    Median { window: u32 },
+    /// Holds the output until the input moves further than `band` from it
+    #[serde(rename_all = "camelCase")]
+    Deadband { band: f64 },
+    /// Replaces readings further than `max_deviation` from the median of the
+    /// last `window` readings with that median
+    #[serde(rename_all = "camelCase")]
+    OutlierRejection { window: u32, max_deviation: f64 },
+    /// Converts the temperatures, stages after it work in the new unit
+    #[serde(rename_all = "camelCase")]
+    UnitConversion { unit: TemperatureUnit },
+    /// Adds a value computed from the temperatures at this point of the pipeline
+    #[serde(rename_all = "camelCase")]
+    Derived { name: String, value: DerivedKind },
+}
+
+impl StageConfig {
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| -> Result<()> {
+            Err(SettingsError::InvalidPipeline(format!("{:?}: {}", self, message)).into())
+        };
+
+        match self {
+            StageConfig::MovingAverage { window }
+            | StageConfig::Median { window }
+            | StageConfig::OutlierRejection { window, .. }
+                if *window == 0 =>
+            {
+                invalid("the window needs at least one sample")
+            }
+            StageConfig::Ewma { alpha } if !(*alpha > 0.0 && *alpha <= 1.0) => {
+                invalid("alpha must be in (0, 1]")
+            }
+            StageConfig::Deadband { band } if !(band.is_finite() && *band >= 0.0) => {
+                invalid("the band can't be negative")
+            }
+            StageConfig::OutlierRejection { max_deviation, .. }
+                if !(max_deviation.is_finite() && *max_deviation > 0.0) =>
+            {
+                invalid("the deviation must be positive")
+            }
+            StageConfig::Derived { name, .. } if name.trim().is_empty() => {
+                invalid("derived values need a name")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DerivedValue {
+    pub name: String,
+    pub value: Option<f64>,
+}
+
+/// Temperatures of an entry after the pipeline, next to the raw entry they
+/// were computed from
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessedEntry {
+    pub timestamp: u64,
+    pub temperatures: Vec<f64>,
+    pub unit: TemperatureUnit,
+    pub derived: Vec<DerivedValue>,
+}
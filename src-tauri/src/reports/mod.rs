@@ -0,0 +1,6 @@
+pub mod service;
+pub mod svg;
+pub mod tests;
+pub mod types;
+
+pub use service::ReportService;
@@ -0,0 +1,231 @@
+use super::svg::{escape, line_chart, ChartSeries};
+use super::types::{ReportEvent, ReportEventKind, ReportMetadata};
+use crate::data_manager::types::ColumnEntry;
+use std::fmt::Write;
+use std::sync::Arc;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:32px;color:#111827}\
+h1{margin-bottom:4px}h2{margin-top:32px;border-bottom:1px solid #e5e7eb;padding-bottom:4px}\
+table{border-collapse:collapse;margin-top:8px}td,th{border:1px solid #e5e7eb;padding:4px 10px;text-align:left}\
+th{background:#f3f4f6}.muted{color:#6b7280}";
+
+pub struct ReportService;
+
+impl ReportService {
+    pub fn new() -> Self {
+        ReportService
+    }
+
+    pub fn render_html(
+        &self,
+        entries: &[Arc<ColumnEntry>],
+        metadata: &ReportMetadata,
+        events: &[ReportEvent],
+    ) -> String {
+        let start = entries.first().map(|e| e.timestamp).unwrap_or_default();
+        let end = entries.last().map(|e| e.timestamp).unwrap_or_default();
+        let number_plates = entries.first().map(|e| e.temperatures.len()).unwrap_or(0);
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>",
+            escape(&metadata.title),
+            STYLE
+        );
+        let _ = write!(html, "<h1>{}</h1>", escape(&metadata.title));
+
+        // Run metadata
+        html.push_str("<h2>Run</h2><table>");
+        let summary = [
+            ("Start timestamp".to_string(), start.to_string()),
+            ("End timestamp".to_string(), end.to_string()),
+            (
+                "Duration".to_string(),
+                format_elapsed(end.saturating_sub(start)),
+            ),
+            ("Samples".to_string(), entries.len().to_string()),
+            ("Plates".to_string(), number_plates.to_string()),
+        ];
+        for (name, value) in metadata.fields.iter().chain(summary.iter()) {
+            let _ = write!(
+                html,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape(name),
+                escape(value)
+            );
+        }
+        html.push_str("</table>");
+
+        // Charts
+        let elapsed = |entry: &ColumnEntry| entry.timestamp.saturating_sub(start) as f64 / 1000.0;
+
+        let temperature_series: Vec<ChartSeries> = (0..number_plates)
+            .map(|plate| ChartSeries {
+                name: format!("Plate {}", plate + 1),
+                points: entries
+                    .iter()
+                    .filter_map(|e| e.temperatures.get(plate).map(|&t| (elapsed(e), t)))
+                    .collect(),
+            })
+            .collect();
+
+        let composition_series: Vec<ChartSeries> = (0..number_plates)
+            .map(|plate| ChartSeries {
+                name: format!("x_1 plate {}", plate + 1),
+                points: entries
+                    .iter()
+                    .filter_map(|e| {
+                        e.compositions
+                            .get(plate)
+                            .and_then(|c| c.x_1)
+                            .map(|x| (elapsed(e), x))
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let mass_series = vec![ChartSeries {
+            name: "Distilled mass".to_string(),
+            points: entries
+                .iter()
+                .map(|e| (elapsed(e), e.distilled_mass))
+                .collect(),
+        }];
+
+        let _ = write!(
+            html,
+            "<h2>Temperatures</h2>{}",
+            line_chart(&temperature_series, "Elapsed time (s)", "Temperature (°C)")
+        );
+        let _ = write!(
+            html,
+            "<h2>Compositions</h2>{}",
+            line_chart(
+                &composition_series,
+                "Elapsed time (s)",
+                "Liquid mole fraction x_1"
+            )
+        );
+        let _ = write!(
+            html,
+            "<h2>Distilled mass</h2>{}",
+            line_chart(&mass_series, "Elapsed time (s)", "Mass")
+        );
+
+        // Event tables
+        let mut key_events = self.key_events(entries);
+        key_events.extend(
+            events
+                .iter()
+                .filter(|e| matches!(e.kind, ReportEventKind::Process))
+                .cloned(),
+        );
+        key_events.sort_by_key(|e| e.timestamp);
+
+        let alarms: Vec<&ReportEvent> = events
+            .iter()
+            .filter(|e| matches!(e.kind, ReportEventKind::Alarm))
+            .collect();
+        let notes: Vec<&ReportEvent> = events
+            .iter()
+            .filter(|e| matches!(e.kind, ReportEventKind::Note))
+            .collect();
+
+        html.push_str(&event_table(
+            "Key events",
+            &key_events.iter().collect::<Vec<_>>(),
+            start,
+        ));
+        html.push_str(&event_table("Alarms", &alarms, start));
+        html.push_str(&event_table("Operator notes", &notes, start));
+
+        html.push_str("</body></html>");
+        html
+    }
+
+    pub fn key_events(&self, entries: &[Arc<ColumnEntry>]) -> Vec<ReportEvent> {
+        let mut events = Vec::new();
+        let (Some(first), Some(last)) = (entries.first(), entries.last()) else {
+            return events;
+        };
+
+        events.push(ReportEvent {
+            timestamp: first.timestamp,
+            kind: ReportEventKind::Process,
+            description: "Recording started".to_string(),
+        });
+
+        if let Some(entry) = entries.iter().find(|e| e.distilled_mass > 0.0) {
+            events.push(ReportEvent {
+                timestamp: entry.timestamp,
+                kind: ReportEventKind::Process,
+                description: "First distillate collected".to_string(),
+            });
+        }
+
+        // The last plate is the top of the column
+        if let Some((entry, temperature)) = entries
+            .iter()
+            .filter_map(|e| e.temperatures.last().map(|&t| (e, t)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+        {
+            events.push(ReportEvent {
+                timestamp: entry.timestamp,
+                kind: ReportEventKind::Process,
+                description: format!("Peak top-plate temperature {:.2} °C", temperature),
+            });
+        }
+
+        if let Some((entry, purity)) = entries
+            .iter()
+            .filter_map(|e| e.compositions.last().and_then(|c| c.y_1).map(|y| (e, y)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+        {
+            events.push(ReportEvent {
+                timestamp: entry.timestamp,
+                kind: ReportEventKind::Process,
+                description: format!("Peak distillate purity y_1 = {:.3}", purity),
+            });
+        }
+
+        events.push(ReportEvent {
+            timestamp: last.timestamp,
+            kind: ReportEventKind::Process,
+            description: format!("Recording ended, distilled mass {:.2}", last.distilled_mass),
+        });
+
+        events
+    }
+}
+
+fn event_table(title: &str, events: &[&ReportEvent], start: u64) -> String {
+    let mut html = format!("<h2>{}</h2>", escape(title));
+    if events.is_empty() {
+        html.push_str("<p class=\"muted\">None recorded.</p>");
+        return html;
+    }
+
+    html.push_str("<table><tr><th>Elapsed</th><th>Timestamp</th><th>Description</th></tr>");
+    for event in events {
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            format_elapsed(event.timestamp.saturating_sub(start)),
+            event.timestamp,
+            escape(&event.description)
+        );
+    }
+    html.push_str("</table>");
+    html
+}
+
+fn format_elapsed(millis: u64) -> String {
+    let seconds = millis / 1000;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds % 3600) / 60,
+        seconds % 60
+    )
+}
@@ -0,0 +1,157 @@
+use std::fmt::Write;
+
+const WIDTH: f64 = 880.0;
+const HEIGHT: f64 = 320.0;
+const MARGIN_LEFT: f64 = 64.0;
+const MARGIN_RIGHT: f64 = 150.0;
+const MARGIN_TOP: f64 = 20.0;
+const MARGIN_BOTTOM: f64 = 44.0;
+const TICKS: usize = 5;
+const MAX_POINTS: usize = 600;
+
+const PALETTE: [&str; 8] = [
+    "#2563eb", "#dc2626", "#16a34a", "#d97706", "#7c3aed", "#0891b2", "#db2777", "#4b5563",
+];
+
+pub struct ChartSeries {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+pub fn line_chart(series: &[ChartSeries], x_label: &str, y_label: &str) -> String {
+    let points = series.iter().flat_map(|s| s.points.iter());
+    let (mut x_min, mut x_max, mut y_min, mut y_max) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+    for &(x, y) in points {
+        if !x.is_finite() || !y.is_finite() {
+            continue;
+        }
+        x_min = x_min.min(x);
+        x_max = x_max.max(x);
+        y_min = y_min.min(y);
+        y_max = y_max.max(y);
+    }
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {WIDTH} {HEIGHT}" width="100%" role="img" font-family="sans-serif" font-size="11">"#
+    );
+
+    if x_min > x_max {
+        let _ = write!(
+            svg,
+            r##"<text x="{}" y="{}" text-anchor="middle" fill="#6b7280">No data</text></svg>"##,
+            WIDTH / 2.0,
+            HEIGHT / 2.0
+        );
+        return svg;
+    }
+
+    // Avoid a zero-height or zero-width plot area for constant series
+    if (x_max - x_min).abs() < f64::EPSILON {
+        x_max = x_min + 1.0;
+    }
+    if (y_max - y_min).abs() < f64::EPSILON {
+        y_min -= 0.5;
+        y_max += 0.5;
+    }
+
+    let plot_w = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_h = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let sx = |x: f64| MARGIN_LEFT + (x - x_min) / (x_max - x_min) * plot_w;
+    let sy = |y: f64| MARGIN_TOP + plot_h - (y - y_min) / (y_max - y_min) * plot_h;
+
+    // Grid and tick labels
+    for i in 0..=TICKS {
+        let fraction = i as f64 / TICKS as f64;
+        let y_value = y_min + (y_max - y_min) * fraction;
+        let x_value = x_min + (x_max - x_min) * fraction;
+        let y = sy(y_value);
+        let x = sx(x_value);
+        let _ = write!(
+            svg,
+            r##"<line x1="{MARGIN_LEFT}" y1="{y:.1}" x2="{:.1}" y2="{y:.1}" stroke="#e5e7eb"/><text x="{:.1}" y="{:.1}" text-anchor="end" fill="#374151">{}</text>"##,
+            MARGIN_LEFT + plot_w,
+            MARGIN_LEFT - 6.0,
+            y + 4.0,
+            format_tick(y_value)
+        );
+        let _ = write!(
+            svg,
+            r##"<text x="{x:.1}" y="{:.1}" text-anchor="middle" fill="#374151">{}</text>"##,
+            MARGIN_TOP + plot_h + 16.0,
+            format_tick(x_value)
+        );
+    }
+
+    let _ = write!(
+        svg,
+        r##"<rect x="{MARGIN_LEFT}" y="{MARGIN_TOP}" width="{plot_w}" height="{plot_h}" fill="none" stroke="#9ca3af"/>"##
+    );
+    let _ = write!(
+        svg,
+        r##"<text x="{:.1}" y="{:.1}" text-anchor="middle" fill="#111827">{}</text>"##,
+        MARGIN_LEFT + plot_w / 2.0,
+        HEIGHT - 8.0,
+        escape(x_label)
+    );
+    let _ = write!(
+        svg,
+        r##"<text transform="translate(14 {:.1}) rotate(-90)" text-anchor="middle" fill="#111827">{}</text>"##,
+        MARGIN_TOP + plot_h / 2.0,
+        escape(y_label)
+    );
+
+    // Series and legend
+    for (index, s) in series.iter().enumerate() {
+        let color = PALETTE[index % PALETTE.len()];
+        let step = (s.points.len() / MAX_POINTS).max(1);
+        let path: Vec<String> = s
+            .points
+            .iter()
+            .step_by(step)
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .map(|&(x, y)| format!("{:.1},{:.1}", sx(x), sy(y)))
+            .collect();
+
+        if !path.is_empty() {
+            let _ = write!(
+                svg,
+                r#"<polyline fill="none" stroke="{color}" stroke-width="1.5" points="{}"/>"#,
+                path.join(" ")
+            );
+        }
+
+        let legend_y = MARGIN_TOP + 6.0 + index as f64 * 16.0;
+        let legend_x = MARGIN_LEFT + plot_w + 12.0;
+        let _ = write!(
+            svg,
+            r##"<rect x="{legend_x:.1}" y="{:.1}" width="10" height="10" fill="{color}"/><text x="{:.1}" y="{legend_y:.1}" fill="#111827">{}</text>"##,
+            legend_y - 9.0,
+            legend_x + 14.0,
+            escape(&s.name)
+        );
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+pub fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn format_tick(value: f64) -> String {
+    if value.abs() >= 100.0 {
+        format!("{:.0}", value)
+    } else if value.abs() >= 1.0 {
+        format!("{:.1}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}
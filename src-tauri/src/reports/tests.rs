@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use crate::data_manager::types::ColumnEntry;
+    use crate::reports::types::{ReportEvent, ReportEventKind, ReportMetadata};
+    use crate::reports::ReportService;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_renders_sections_and_escapes_text() {
+        let entries: Vec<Arc<ColumnEntry>> = (0..3u64)
+            .map(|second| {
+                Arc::new(ColumnEntry {
+                    timestamp: second * 1000,
+                    temperatures: vec![90.0, 80.0 + second as f64],
+                    distilled_mass: second as f64,
+                    ..ColumnEntry::default()
+                })
+            })
+            .collect();
+        let metadata = ReportMetadata::new("Batch <7>").with_field("Operator", "A & B");
+        let events = [
+            ReportEvent {
+                timestamp: 1000,
+                kind: ReportEventKind::Alarm,
+                description: "Top plate high".into(),
+            },
+            ReportEvent {
+                timestamp: 2000,
+                kind: ReportEventKind::Note,
+                description: "<script>reflux</script>".into(),
+            },
+        ];
+
+        let html = ReportService::new().render_html(&entries, &metadata, &events);
+        for section in [
+            "<h2>Run</h2>",
+            "<h2>Temperatures</h2>",
+            "<h2>Distilled mass</h2>",
+            "<h2>Key events</h2>",
+            "<h2>Alarms</h2>",
+            "<h2>Operator notes</h2>",
+        ] {
+            assert!(html.contains(section), "missing {}", section);
+        }
+        assert!(html.contains("<h1>Batch &lt;7&gt;</h1>"));
+        assert!(html.contains("<td>A &amp; B</td>"));
+        assert!(html.contains("Top plate high"));
+        assert!(html.contains("&lt;script&gt;reflux&lt;/script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("Peak top-plate temperature 82.00 °C"));
+    }
+}
@@ -0,0 +1,38 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportEventKind {
+    Process,
+    Alarm,
+    Note,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportEvent {
+    pub timestamp: u64,
+    pub kind: ReportEventKind,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportMetadata {
+    pub title: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl ReportMetadata {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn with_field(mut self, name: &str, value: impl ToString) -> Self {
+        self.fields.push((name.to_string(), value.to_string()));
+        self
+    }
+}
@@ -0,0 +1,55 @@
+use super::types::{PhaseChange, RunInfo, RunPhase, RunTag};
+use crate::data_manager::types::ColumnEntry;
+use log::info;
+use std::sync::Arc;
+
+/// The run live entries are attributed to until it is ended
+#[derive(Debug, Clone)]
+pub struct ActiveRun {
+    info: RunInfo,
+}
+
+impl ActiveRun {
+    pub fn new(info: RunInfo) -> Self {
+        Self { info }
+    }
+
+    pub fn info(&self) -> &RunInfo {
+        &self.info
+    }
+
+    pub fn session_id(&self) -> i64 {
+        self.info.session_id
+    }
+
+    pub fn tag(&self) -> RunTag {
+        RunTag {
+            run_id: self.info.id,
+            batch_id: self.info.metadata.batch_id.clone(),
+            phase: self.info.phase(),
+        }
+    }
+
+    /// The entry as recorded for this run
+    pub fn tag_entry(&self, entry: Arc<ColumnEntry>) -> Arc<ColumnEntry> {
+        Arc::new(ColumnEntry {
+            run: Some(self.tag()),
+            ..(*entry).clone()
+        })
+    }
+
+    /// Enters `phase`, `None` when the run already is in it
+    pub fn set_phase(&mut self, phase: RunPhase, timestamp: u64) -> Option<PhaseChange> {
+        if self.info.phase() == Some(phase) {
+            return None;
+        }
+        info!(
+            "Run {} enters phase {}",
+            self.info.metadata.batch_id,
+            phase.as_str()
+        );
+        let change = PhaseChange { phase, timestamp };
+        self.info.phases.push(change);
+        Some(change)
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+    use crate::data_manager::types::ColumnEntry;
+    use crate::runs::types::{RunMetadata, RunPhase};
+    use crate::runs::ActiveRun;
+    use crate::sessions::service::SessionService;
+    use crate::sessions::types::SessionSource;
+    use std::sync::Arc;
+
+    fn metadata() -> RunMetadata {
+        RunMetadata {
+            batch_id: "B-042".into(),
+            operator: "Operator".into(),
+            feed_description: "Ethanol / water".into(),
+            charge_mass: 250.0,
+        }
+    }
+
+    #[test]
+    fn test_run_metadata_needs_a_positive_charge() {
+        assert!(metadata().validate().is_ok());
+        let empty_batch = RunMetadata {
+            batch_id: " ".into(),
+            ..metadata()
+        };
+        assert!(empty_batch.validate().is_err());
+        let no_charge = RunMetadata {
+            charge_mass: 0.0,
+            ..metadata()
+        };
+        assert!(no_charge.validate().is_err());
+    }
+
+    #[test]
+    fn test_run_lifecycle_is_stored_with_its_session() {
+        let path = std::env::temp_dir().join(format!("run-lifecycle-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let service = SessionService::open(path.to_str().unwrap()).unwrap();
+
+        let session = service
+            .create_session("B-042", SessionSource::Live, 2)
+            .unwrap();
+        let run = service.create_run(session.id, &metadata(), 1_000).unwrap();
+        let mut active = ActiveRun::new(run);
+
+        let change = active.set_phase(RunPhase::HeatUp, 1_500).unwrap();
+        service.add_run_phase(active.info().id, &change).unwrap();
+        assert!(active.set_phase(RunPhase::HeatUp, 1_600).is_none());
+        let change = active.set_phase(RunPhase::Production, 2_000).unwrap();
+        service.add_run_phase(active.info().id, &change).unwrap();
+
+        let entry = active.tag_entry(Arc::new(ColumnEntry {
+            timestamp: 2_100,
+            ..Default::default()
+        }));
+        service.append_entry(session.id, &entry).unwrap();
+        let loaded = service.load_entries(session.id).unwrap();
+        let tag = loaded[0].run.as_ref().unwrap();
+        assert_eq!(tag.batch_id, "B-042");
+        assert_eq!(tag.phase, Some(RunPhase::Production));
+
+        let ended = service.end_run(active.info().id, 3_000).unwrap();
+        assert_eq!(ended.ended_at, Some(3_000));
+        assert_eq!(ended.phases.len(), 2);
+        assert_eq!(ended.phases[1].phase, RunPhase::Production);
+
+        let stored = service.get_session(session.id).unwrap();
+        assert_eq!(stored.batch_id.as_deref(), Some("B-042"));
+        assert_eq!(service.run_for_session(session.id).unwrap(), Some(ended));
+        assert_eq!(service.list_runs().unwrap().len(), 1);
+
+        service.delete_session(session.id).unwrap();
+        assert!(service.list_runs().unwrap().is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
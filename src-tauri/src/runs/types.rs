@@ -0,0 +1,116 @@
+use crate::errors::RunError;
+use crate::settings::types::KpiSettings;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// What the operator enters when starting a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMetadata {
+    pub batch_id: String,
+    pub operator: String,
+    #[serde(default)]
+    pub feed_description: String,
+    /// Mass charged to the still, in the unit of the distilled mass
+    pub charge_mass: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum RunPhase {
+    HeatUp,
+    TotalReflux,
+    Production,
+    Shutdown,
+}
+
+impl RunPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunPhase::HeatUp => "heatUp",
+            RunPhase::TotalReflux => "totalReflux",
+            RunPhase::Production => "production",
+            RunPhase::Shutdown => "shutdown",
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            RunPhase::HeatUp => "Heat-up",
+            RunPhase::TotalReflux => "Total reflux",
+            RunPhase::Production => "Production",
+            RunPhase::Shutdown => "Shutdown",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "heatUp" => Some(RunPhase::HeatUp),
+            "totalReflux" => Some(RunPhase::TotalReflux),
+            "production" => Some(RunPhase::Production),
+            "shutdown" => Some(RunPhase::Shutdown),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseChange {
+    pub phase: RunPhase,
+    pub timestamp: u64,
+}
+
+/// A batch from start to end. Its entries are recorded in `session_id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RunInfo {
+    pub id: i64,
+    pub session_id: i64,
+    pub metadata: RunMetadata,
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    /// Oldest first
+    pub phases: Vec<PhaseChange>,
+}
+
+impl RunInfo {
+    pub fn phase(&self) -> Option<RunPhase> {
+        self.phases.last().map(|change| change.phase)
+    }
+
+    /// The KPIs of the run start from its own charge
+    pub fn kpi_settings(&self, settings: &KpiSettings) -> KpiSettings {
+        KpiSettings {
+            initial_charge: self.metadata.charge_mass,
+            ..settings.clone()
+        }
+    }
+}
+
+/// The run an entry was produced in, as stored with the entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RunTag {
+    pub run_id: i64,
+    pub batch_id: String,
+    pub phase: Option<RunPhase>,
+}
+
+impl RunMetadata {
+    pub fn validate(&self) -> Result<(), RunError> {
+        if self.batch_id.trim().is_empty() {
+            return Err(RunError::InvalidMetadata("a batch ID is required".into()));
+        }
+        if self.operator.trim().is_empty() {
+            return Err(RunError::InvalidMetadata("an operator is required".into()));
+        }
+        if !self.charge_mass.is_finite() || self.charge_mass <= 0.0 {
+            return Err(RunError::InvalidMetadata(format!(
+                "charge mass {} must be positive",
+                self.charge_mass
+            )));
+        }
+        Ok(())
+    }
+}
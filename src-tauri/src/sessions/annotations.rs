@@ -0,0 +1,71 @@
+use super::types::Annotation;
+
+/// The annotations of the session being recorded or played, to show them when
+/// the transmission passes their timestamp
+#[derive(Debug, Clone, Default)]
+pub struct AnnotationTrack {
+    session_id: Option<i64>,
+    annotations: Vec<Annotation>,
+    last_timestamp: Option<u64>,
+}
+
+impl AnnotationTrack {
+    pub fn new(session_id: i64, mut annotations: Vec<Annotation>) -> Self {
+        annotations.sort_by_key(|annotation| (annotation.timestamp, annotation.id));
+        Self {
+            session_id: Some(session_id),
+            annotations,
+            last_timestamp: None,
+        }
+    }
+
+    pub fn session_id(&self) -> Option<i64> {
+        self.session_id
+    }
+
+    pub fn last_timestamp(&self) -> Option<u64> {
+        self.last_timestamp
+    }
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    /// Adds or replaces an annotation of the tracked session
+    pub fn upsert(&mut self, annotation: Annotation) {
+        if Some(annotation.session_id) != self.session_id {
+            return;
+        }
+        self.annotations.retain(|other| other.id != annotation.id);
+        let index = self.annotations.partition_point(|other| {
+            (other.timestamp, other.id) < (annotation.timestamp, annotation.id)
+        });
+        self.annotations.insert(index, annotation);
+    }
+
+    pub fn remove(&mut self, annotation_id: i64) {
+        self.annotations.retain(|other| other.id != annotation_id);
+    }
+
+    /// Annotations between the previous timestamp and this one, in the direction
+    /// the transmission moved. The first timestamp only passes its own annotations.
+    pub fn advance(&mut self, timestamp: u64) -> Vec<Annotation> {
+        let last = self.last_timestamp.replace(timestamp);
+        let mut passed: Vec<Annotation> = self
+            .annotations
+            .iter()
+            .filter(|annotation| match last {
+                Some(last) if last <= timestamp => {
+                    last < annotation.timestamp && annotation.timestamp <= timestamp
+                }
+                Some(last) => timestamp <= annotation.timestamp && annotation.timestamp < last,
+                None => annotation.timestamp == timestamp,
+            })
+            .cloned()
+            .collect();
+        if last.is_some_and(|last| timestamp < last) {
+            passed.reverse();
+        }
+        passed
+    }
+}
@@ -0,0 +1,7 @@
+pub mod annotations;
+pub mod service;
+pub mod tests;
+pub mod types;
+
+pub use annotations::AnnotationTrack;
+pub use service::SessionService;
@@ -0,0 +1,565 @@
+use super::types::{Annotation, SessionInfo, SessionSource};
+use crate::cuts::types::CutSwitch;
+use crate::data_manager::types::ColumnEntry;
+use crate::errors::{FileError, Result, StorageError};
+use crate::files::ensure_parent_directory;
+use crate::runs::types::{PhaseChange, RunInfo, RunMetadata, RunPhase};
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const DATABASE_FILE: &str = "sessions.db";
+
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+    CREATE TABLE IF NOT EXISTS sessions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        source TEXT NOT NULL,
+        number_plates INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        timestamp INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS entries_session ON entries(session_id, id);
+    CREATE TABLE IF NOT EXISTS annotations (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        timestamp INTEGER NOT NULL,
+        text TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER
+    );
+    CREATE INDEX IF NOT EXISTS annotations_session ON annotations(session_id, timestamp);
+    CREATE TABLE IF NOT EXISTS runs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        session_id INTEGER NOT NULL UNIQUE REFERENCES sessions(id) ON DELETE CASCADE,
+        batch_id TEXT NOT NULL,
+        operator TEXT NOT NULL,
+        feed_description TEXT NOT NULL,
+        charge_mass REAL NOT NULL,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS run_phases (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+        phase TEXT NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS cut_switches (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        timestamp INTEGER NOT NULL,
+        from_cut TEXT NOT NULL,
+        to_cut TEXT NOT NULL,
+        recommended INTEGER NOT NULL
+    );
+";
+
+const SESSION_QUERY: &str = "
+    SELECT s.id, s.name, s.source, s.number_plates, s.created_at,
+           MIN(e.timestamp), MAX(e.timestamp), COUNT(e.id), r.id, r.batch_id
+    FROM sessions s
+    LEFT JOIN runs r ON r.session_id = s.id
+    LEFT JOIN entries e ON e.session_id = s.id
+";
+
+const RUN_QUERY: &str = "
+    SELECT id, session_id, batch_id, operator, feed_description, charge_mass, started_at, ended_at
+    FROM runs
+";
+
+const ANNOTATION_QUERY: &str =
+    "SELECT id, session_id, timestamp, text, created_at, updated_at FROM annotations";
+
+pub struct SessionService {
+    connection: Connection,
+}
+
+impl SessionService {
+    pub fn open(path: &str) -> Result<Self> {
+        info!("Opening session database at {}", path);
+        ensure_parent_directory(path)?;
+
+        let connection =
+            Connection::open(path).map_err(|e| StorageError::OpenError(e.to_string()))?;
+        connection
+            .execute_batch(SCHEMA)
+            .map_err(|e| StorageError::OpenError(e.to_string()))?;
+
+        Ok(Self { connection })
+    }
+
+    pub fn get_database_path(app_handle: &AppHandle) -> Result<String> {
+        let path = app_handle
+            .path()
+            .app_data_dir()
+            .expect("Failed to get app data directory")
+            .join(DATABASE_FILE);
+
+        path.to_str().map(|s| s.to_string()).ok_or_else(|| {
+            FileError::ReadError("Failed to convert path to string".to_string()).into()
+        })
+    }
+
+    pub fn create_session(
+        &self,
+        name: &str,
+        source: SessionSource,
+        number_plates: u32,
+    ) -> Result<SessionInfo> {
+        let created_at = now_millis();
+
+        self.connection
+            .execute(
+                "INSERT INTO sessions (name, source, number_plates, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![name, source.as_str(), number_plates, created_at],
+            )
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        let id = self.connection.last_insert_rowid();
+        info!("Created {} session {} ({})", source.as_str(), id, name);
+
+        self.get_session(id)
+    }
+
+    pub fn append_entry(&self, session_id: i64, entry: &ColumnEntry) -> Result<()> {
+        let data = serde_json::to_string(entry)
+            .map_err(|e| StorageError::SerializeError(e.to_string()))?;
+
+        self.connection
+            .execute(
+                "INSERT INTO entries (session_id, timestamp, data) VALUES (?1, ?2, ?3)",
+                params![session_id, entry.timestamp as i64, data],
+            )
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub fn append_entries(&mut self, session_id: i64, entries: &[Arc<ColumnEntry>]) -> Result<()> {
+        let transaction = self
+            .connection
+            .transaction()
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+        {
+            let mut statement = transaction
+                .prepare("INSERT INTO entries (session_id, timestamp, data) VALUES (?1, ?2, ?3)")
+                .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+            for entry in entries {
+                let data = serde_json::to_string(entry.as_ref())
+                    .map_err(|e| StorageError::SerializeError(e.to_string()))?;
+                statement
+                    .execute(params![session_id, entry.timestamp as i64, data])
+                    .map_err(|e| StorageError::QueryError(e.to_string()))?;
+            }
+        }
+        transaction
+            .commit()
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub fn list_sessions(&self) -> Result<Vec<SessionInfo>> {
+        let mut statement = self
+            .connection
+            .prepare(&format!(
+                "{} GROUP BY s.id ORDER BY s.created_at DESC, s.id DESC",
+                SESSION_QUERY
+            ))
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        let sessions = statement
+            .query_map([], session_from_row)
+            .map_err(|e| StorageError::QueryError(e.to_string()))?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        Ok(sessions)
+    }
+
+    pub fn get_session(&self, session_id: i64) -> Result<SessionInfo> {
+        self.connection
+            .query_row(
+                &format!("{} WHERE s.id = ?1 GROUP BY s.id", SESSION_QUERY),
+                params![session_id],
+                session_from_row,
+            )
+            .optional()
+            .map_err(|e| StorageError::QueryError(e.to_string()))?
+            .ok_or_else(|| StorageError::SessionNotFound(session_id).into())
+    }
+
+    pub fn load_entries(&self, session_id: i64) -> Result<Vec<Arc<ColumnEntry>>> {
+        // Fail early for unknown ids instead of returning an empty session
+        self.get_session(session_id)?;
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT data FROM entries WHERE session_id = ?1 ORDER BY id")
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        let rows = statement
+            .query_map(params![session_id], |row| row.get::<_, String>(0))
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let data = row.map_err(|e| StorageError::QueryError(e.to_string()))?;
+            let entry: ColumnEntry = serde_json::from_str(&data)
+                .map_err(|e| StorageError::SerializeError(e.to_string()))?;
+            entries.push(Arc::new(entry));
+        }
+
+        Ok(entries)
+    }
+
+    pub fn rename_session(&self, session_id: i64, name: &str) -> Result<SessionInfo> {
+        let updated = self
+            .connection
+            .execute(
+                "UPDATE sessions SET name = ?1 WHERE id = ?2",
+                params![name, session_id],
+            )
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        if updated == 0 {
+            return Err(StorageError::SessionNotFound(session_id).into());
+        }
+
+        self.get_session(session_id)
+    }
+
+    pub fn delete_session(&self, session_id: i64) -> Result<()> {
+        let deleted = self
+            .connection
+            .execute("DELETE FROM sessions WHERE id = ?1", params![session_id])
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        if deleted == 0 {
+            return Err(StorageError::SessionNotFound(session_id).into());
+        }
+
+        info!("Deleted session {}", session_id);
+        Ok(())
+    }
+
+    pub fn add_annotation(
+        &self,
+        session_id: i64,
+        timestamp: u64,
+        text: &str,
+    ) -> Result<Annotation> {
+        self.get_session(session_id)?;
+        let text = annotation_text(text)?;
+
+        self.connection
+            .execute(
+                "INSERT INTO annotations (session_id, timestamp, text, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![session_id, timestamp as i64, text, now_millis()],
+            )
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        let id = self.connection.last_insert_rowid();
+        info!("Annotated session {} at {}", session_id, timestamp);
+        self.get_annotation(id)
+    }
+
+    /// Changes the text of an annotation, and its timestamp if given
+    pub fn update_annotation(
+        &self,
+        annotation_id: i64,
+        timestamp: Option<u64>,
+        text: &str,
+    ) -> Result<Annotation> {
+        let current = self.get_annotation(annotation_id)?;
+        let text = annotation_text(text)?;
+        let timestamp = timestamp.unwrap_or(current.timestamp);
+
+        self.connection
+            .execute(
+                "UPDATE annotations SET timestamp = ?1, text = ?2, updated_at = ?3 WHERE id = ?4",
+                params![timestamp as i64, text, now_millis(), annotation_id],
+            )
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        self.get_annotation(annotation_id)
+    }
+
+    pub fn delete_annotation(&self, annotation_id: i64) -> Result<Annotation> {
+        let annotation = self.get_annotation(annotation_id)?;
+        self.connection
+            .execute(
+                "DELETE FROM annotations WHERE id = ?1",
+                params![annotation_id],
+            )
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+        Ok(annotation)
+    }
+
+    /// Annotations of a session, ordered by their timestamp
+    pub fn list_annotations(&self, session_id: i64) -> Result<Vec<Annotation>> {
+        self.get_session(session_id)?;
+
+        let mut statement = self
+            .connection
+            .prepare(&format!(
+                "{} WHERE session_id = ?1 ORDER BY timestamp, id",
+                ANNOTATION_QUERY
+            ))
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        let annotations = statement
+            .query_map(params![session_id], annotation_from_row)
+            .map_err(|e| StorageError::QueryError(e.to_string()))?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        Ok(annotations)
+    }
+
+    fn get_annotation(&self, annotation_id: i64) -> Result<Annotation> {
+        self.connection
+            .query_row(
+                &format!("{} WHERE id = ?1", ANNOTATION_QUERY),
+                params![annotation_id],
+                annotation_from_row,
+            )
+            .optional()
+            .map_err(|e| StorageError::QueryError(e.to_string()))?
+            .ok_or_else(|| StorageError::AnnotationNotFound(annotation_id).into())
+    }
+
+    pub fn add_cut_switch(&self, session_id: i64, switch: &CutSwitch) -> Result<()> {
+        self.connection
+            .execute(
+                "INSERT INTO cut_switches (session_id, timestamp, from_cut, to_cut, recommended) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    session_id,
+                    switch.timestamp as i64,
+                    switch.from,
+                    switch.to,
+                    switch.recommended
+                ],
+            )
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Cut switches of a session, in the order they were made
+    pub fn list_cut_switches(&self, session_id: i64) -> Result<Vec<CutSwitch>> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT timestamp, from_cut, to_cut, recommended FROM cut_switches WHERE session_id = ?1 ORDER BY id",
+            )
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        let switches = statement
+            .query_map(params![session_id], |row| {
+                Ok(CutSwitch {
+                    timestamp: row.get::<_, i64>(0)? as u64,
+                    from: row.get(1)?,
+                    to: row.get(2)?,
+                    recommended: row.get(3)?,
+                })
+            })
+            .map_err(|e| StorageError::QueryError(e.to_string()))?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        Ok(switches)
+    }
+
+    /// Starts a run recorded into `session_id`
+    pub fn create_run(
+        &self,
+        session_id: i64,
+        metadata: &RunMetadata,
+        started_at: u64,
+    ) -> Result<RunInfo> {
+        self.get_session(session_id)?;
+
+        self.connection
+            .execute(
+                "INSERT INTO runs (session_id, batch_id, operator, feed_description, charge_mass, started_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    session_id,
+                    metadata.batch_id.trim(),
+                    metadata.operator.trim(),
+                    metadata.feed_description.trim(),
+                    metadata.charge_mass,
+                    started_at as i64
+                ],
+            )
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        let id = self.connection.last_insert_rowid();
+        info!("Started run {} ({})", id, metadata.batch_id);
+        self.get_run(id)
+    }
+
+    pub fn add_run_phase(&self, run_id: i64, change: &PhaseChange) -> Result<()> {
+        self.connection
+            .execute(
+                "INSERT INTO run_phases (run_id, phase, timestamp) VALUES (?1, ?2, ?3)",
+                params![run_id, change.phase.as_str(), change.timestamp as i64],
+            )
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn end_run(&self, run_id: i64, ended_at: u64) -> Result<RunInfo> {
+        let updated = self
+            .connection
+            .execute(
+                "UPDATE runs SET ended_at = ?1 WHERE id = ?2",
+                params![ended_at as i64, run_id],
+            )
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        if updated == 0 {
+            return Err(StorageError::RunNotFound(run_id).into());
+        }
+
+        info!("Ended run {}", run_id);
+        self.get_run(run_id)
+    }
+
+    pub fn get_run(&self, run_id: i64) -> Result<RunInfo> {
+        let run = self
+            .connection
+            .query_row(
+                &format!("{} WHERE id = ?1", RUN_QUERY),
+                params![run_id],
+                run_from_row,
+            )
+            .optional()
+            .map_err(|e| StorageError::QueryError(e.to_string()))?
+            .ok_or(StorageError::RunNotFound(run_id))?;
+        self.with_phases(run)
+    }
+
+    /// The run recorded into a session, if it was recorded for one
+    pub fn run_for_session(&self, session_id: i64) -> Result<Option<RunInfo>> {
+        let run = self
+            .connection
+            .query_row(
+                &format!("{} WHERE session_id = ?1", RUN_QUERY),
+                params![session_id],
+                run_from_row,
+            )
+            .optional()
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+        run.map(|run| self.with_phases(run)).transpose()
+    }
+
+    /// Every run, newest first
+    pub fn list_runs(&self) -> Result<Vec<RunInfo>> {
+        let mut statement = self
+            .connection
+            .prepare(&format!("{} ORDER BY started_at DESC, id DESC", RUN_QUERY))
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        let runs = statement
+            .query_map([], run_from_row)
+            .map_err(|e| StorageError::QueryError(e.to_string()))?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        runs.into_iter().map(|run| self.with_phases(run)).collect()
+    }
+
+    fn with_phases(&self, mut run: RunInfo) -> Result<RunInfo> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT phase, timestamp FROM run_phases WHERE run_id = ?1 ORDER BY id")
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        let rows = statement
+            .query_map(params![run.id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })
+            .map_err(|e| StorageError::QueryError(e.to_string()))?;
+
+        for row in rows {
+            let (phase, timestamp) = row.map_err(|e| StorageError::QueryError(e.to_string()))?;
+            if let Some(phase) = RunPhase::parse(&phase) {
+                run.phases.push(PhaseChange {
+                    phase,
+                    timestamp: timestamp as u64,
+                });
+            }
+        }
+        Ok(run)
+    }
+}
+
+fn annotation_text(text: &str) -> Result<&str> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(StorageError::EmptyAnnotation.into());
+    }
+    Ok(text)
+}
+
+pub(crate) fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+fn annotation_from_row(row: &Row) -> rusqlite::Result<Annotation> {
+    Ok(Annotation {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        timestamp: row.get::<_, i64>(2)? as u64,
+        text: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+fn run_from_row(row: &Row) -> rusqlite::Result<RunInfo> {
+    Ok(RunInfo {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        metadata: RunMetadata {
+            batch_id: row.get(2)?,
+            operator: row.get(3)?,
+            feed_description: row.get(4)?,
+            charge_mass: row.get(5)?,
+        },
+        started_at: row.get::<_, i64>(6)? as u64,
+        ended_at: row
+            .get::<_, Option<i64>>(7)?
+            .map(|ended_at| ended_at as u64),
+        phases: Vec::new(),
+    })
+}
+
+fn session_from_row(row: &Row) -> rusqlite::Result<SessionInfo> {
+    Ok(SessionInfo {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        source: SessionSource::parse(&row.get::<_, String>(2)?),
+        number_plates: row.get(3)?,
+        created_at: row.get(4)?,
+        started_at: row.get(5)?,
+        ended_at: row.get(6)?,
+        entry_count: row.get(7)?,
+        run_id: row.get(8)?,
+        batch_id: row.get(9)?,
+    })
+}
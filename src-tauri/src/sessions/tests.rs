@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use crate::cuts::types::CutSwitch;
+    use crate::data_manager::types::ColumnEntry;
+    use crate::sessions::service::SessionService;
+    use crate::sessions::types::SessionSource;
+    use crate::sessions::AnnotationTrack;
+    use std::sync::Arc;
+
+    fn temp_database(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        let path = temp_database("session-round-trip");
+        let mut service = SessionService::open(&path).unwrap();
+
+        let session = service
+            .create_session("Batch 1", SessionSource::Imported, 2)
+            .unwrap();
+        let entries: Vec<Arc<ColumnEntry>> = (0..3)
+            .map(|i| {
+                Arc::new(ColumnEntry {
+                    timestamp: 100 + i,
+                    temperatures: vec![80.0, 70.0],
+                    ..Default::default()
+                })
+            })
+            .collect();
+        service.append_entries(session.id, &entries).unwrap();
+
+        let stored = service.get_session(session.id).unwrap();
+        assert_eq!(stored.entry_count, 3);
+        assert_eq!(stored.started_at, Some(100));
+        assert_eq!(stored.ended_at, Some(102));
+
+        let loaded = service.load_entries(session.id).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[2].timestamp, 102);
+        assert_eq!(loaded[0].temperatures, vec![80.0, 70.0]);
+
+        let switch = CutSwitch {
+            timestamp: 101,
+            from: "Heads".to_string(),
+            to: "Hearts".to_string(),
+            recommended: true,
+        };
+        service.add_cut_switch(session.id, &switch).unwrap();
+        assert_eq!(service.list_cut_switches(session.id).unwrap(), vec![switch]);
+
+        let renamed = service.rename_session(session.id, "Batch 1b").unwrap();
+        assert_eq!(renamed.name, "Batch 1b");
+
+        service.delete_session(session.id).unwrap();
+        assert!(service.load_entries(session.id).is_err());
+        assert!(service.list_sessions().unwrap().is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_annotations_follow_the_timeline() {
+        let path = temp_database("session-annotations");
+        let service = SessionService::open(&path).unwrap();
+        let session = service
+            .create_session("Batch 2", SessionSource::Live, 2)
+            .unwrap();
+
+        let reflux = service
+            .add_annotation(session.id, 5000, " increased reflux ")
+            .unwrap();
+        assert_eq!(reflux.text, "increased reflux");
+        service
+            .add_annotation(session.id, 2000, "sample taken")
+            .unwrap();
+        assert!(service.add_annotation(session.id, 3000, "  ").is_err());
+
+        let moved = service
+            .update_annotation(reflux.id, Some(1000), "power dip")
+            .unwrap();
+        assert!(moved.updated_at.is_some());
+        let annotations = service.list_annotations(session.id).unwrap();
+        assert_eq!(
+            annotations.iter().map(|a| a.timestamp).collect::<Vec<_>>(),
+            vec![1000, 2000]
+        );
+
+        let mut track = AnnotationTrack::new(session.id, annotations);
+        assert!(track.advance(0).is_empty());
+        assert_eq!(track.advance(1500)[0].text, "power dip");
+        assert_eq!(track.advance(4000)[0].text, "sample taken");
+        // Going back passes them again, newest first
+        let back: Vec<String> = track.advance(500).into_iter().map(|a| a.text).collect();
+        assert_eq!(back, vec!["sample taken", "power dip"]);
+
+        service.delete_annotation(moved.id).unwrap();
+        service.delete_session(session.id).unwrap();
+        assert!(service.list_annotations(session.id).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionSource {
+    Live,
+    Imported,
+}
+
+impl SessionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionSource::Live => "live",
+            SessionSource::Imported => "imported",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "live" => SessionSource::Live,
+            _ => SessionSource::Imported,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub id: i64,
+    pub name: String,
+    pub source: SessionSource,
+    pub number_plates: u32,
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub ended_at: Option<i64>,
+    pub entry_count: u32,
+    /// The batch run recorded into the session
+    pub run_id: Option<i64>,
+    pub batch_id: Option<String>,
+}
+
+/// An operator note attached to a moment of a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    pub id: i64,
+    pub session_id: i64,
+    pub timestamp: u64,
+    pub text: String,
+    pub created_at: i64,
+    pub updated_at: Option<i64>,
+}
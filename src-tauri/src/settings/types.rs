@@ -1,27 +1,511 @@
+use crate::alarms::types::{AlarmCondition, AlarmPriority, AlarmRule};
+use crate::cuts::types::{CutDefinition, CutDirection, CutVariable, SwitchCondition};
+use crate::data_manager::import_export::types::NamedColumnMapping;
+use crate::errors::{Result, SettingsError};
+use crate::modbus::types::{PointRole, RegisterPoint};
+use crate::processing::types::StageConfig;
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use specta::Type;
+use std::time::Duration;
+
+/// How the controller is reached
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Type)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum ModbusTransport {
+    /// Serial line on `usb_port` at `baudrate`
+    #[default]
+    Rtu,
+    /// Modbus TCP, `host` is an IP address or a name to resolve. Serial gateways
+    /// must translate to Modbus TCP, RTU frames tunnelled over TCP are not spoken.
+    #[serde(rename_all = "camelCase")]
+    Tcp { host: String, port: u16 },
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SerialParity {
+    #[default]
+    None,
+    Even,
+    Odd,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SerialFlowControl {
+    #[default]
+    None,
+    /// XON/XOFF
+    Software,
+    /// RTS/CTS
+    Hardware,
+}
+
+/// Framing of the RTU serial line, 8N1 unless the device needs otherwise
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SerialLineSettings {
+    pub data_bits: u8,
+    pub parity: SerialParity,
+    pub stop_bits: u8,
+    pub flow_control: SerialFlowControl,
+    /// Extra silence before every request, for devices slower than the 3.5
+    /// characters Modbus RTU asks for
+    pub inter_frame_delay_ms: u32,
+}
+
+impl Default for SerialLineSettings {
+    fn default() -> Self {
+        Self {
+            data_bits: 8,
+            parity: SerialParity::None,
+            stop_bits: 1,
+            flow_control: SerialFlowControl::None,
+            inter_frame_delay_ms: 0,
+        }
+    }
+}
+
+impl SerialLineSettings {
+    /// Short form such as "8E1"
+    pub fn describe(&self) -> String {
+        let parity = match self.parity {
+            SerialParity::None => 'N',
+            SerialParity::Even => 'E',
+            SerialParity::Odd => 'O',
+        };
+        format!("{}{}{}", self.data_bits, parity, self.stop_bits)
+    }
+}
+
+/// Pause before reopening the port, reconnecting or probing the device again,
+/// doubled after every failure
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetrySettings {
+    pub min_delay_ms: u32,
+    pub max_delay_ms: u32,
+    /// Probes sent when connecting before giving up
+    pub handshake_attempts: u32,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self {
+            min_delay_ms: 1000,
+            max_delay_ms: 60_000,
+            handshake_attempts: 3,
+        }
+    }
+}
+
+impl RetrySettings {
+    /// The pause after the given failed attempt, counting from 1
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let doubled = (self.min_delay_ms as u64) << attempt.saturating_sub(1).min(16);
+        Duration::from_millis(doubled.min(self.max_delay_ms as u64))
+    }
+}
 
 #[derive(Debug, Deserialize, Serialize, Clone, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct ModbusSettings {
+    #[serde(default)]
+    pub transport: ModbusTransport,
     pub usb_port: String,
     pub baudrate: u32,
+    #[serde(default)]
+    pub serial: SerialLineSettings,
+    #[serde(default)]
+    pub retry: RetrySettings,
+    /// Without a register map `initial_address..+count` hold the plate temperatures
+    /// from the bottom up, in hundredths of °C. With fewer registers than plates the
+    /// first one is the bottom plate and the last one the top.
     pub initial_address: u16,
     pub count: u16,
     pub timeout: u32,
     pub unit_id: u8,
+    #[serde(default)]
+    pub register_map: Vec<RegisterPoint>,
 }
 
 impl Default for ModbusSettings {
     fn default() -> Self {
         Self {
+            transport: ModbusTransport::default(),
             usb_port: String::default(),
             baudrate: 9600,
+            serial: SerialLineSettings::default(),
+            retry: RetrySettings::default(),
             initial_address: 100,
             count: 2,
             timeout: 1000,
             unit_id: 10,
+            register_map: Vec::new(),
+        }
+    }
+}
+
+impl ModbusSettings {
+    pub fn validate(&self) -> Result<()> {
+        let invalid =
+            |message: String| -> Result<()> { Err(SettingsError::InvalidModbus(message).into()) };
+
+        match &self.transport {
+            ModbusTransport::Rtu => {
+                if self.baudrate == 0 {
+                    return invalid("the baud rate must be positive".into());
+                }
+                if !(5..=8).contains(&self.serial.data_bits) {
+                    return invalid(format!(
+                        "{} data bits, the line takes 5 to 8",
+                        self.serial.data_bits
+                    ));
+                }
+                if !(1..=2).contains(&self.serial.stop_bits) {
+                    return invalid(format!(
+                        "{} stop bits, the line takes 1 or 2",
+                        self.serial.stop_bits
+                    ));
+                }
+                if self.serial.inter_frame_delay_ms > 10_000 {
+                    return invalid("the inter-frame delay can't exceed 10 s".into());
+                }
+            }
+            ModbusTransport::Tcp { host, port } => {
+                if host.trim().is_empty() {
+                    return invalid("a TCP connection needs a host".into());
+                }
+                if *port == 0 {
+                    return invalid("the TCP port can't be 0".into());
+                }
+            }
+        }
+        if self.timeout == 0 {
+            return invalid("the response timeout must be positive".into());
+        }
+        // A single read request returns at most 125 holding registers
+        if !(1..=125).contains(&self.count) {
+            return invalid(format!("{} registers, a read takes 1 to 125", self.count));
+        }
+        if self.initial_address.checked_add(self.count - 1).is_none() {
+            return invalid("the register range runs past address 65535".into());
+        }
+        if self.retry.min_delay_ms == 0 || self.retry.max_delay_ms < self.retry.min_delay_ms {
+            return invalid(
+                "the retry delays must be positive, with the maximum at least the minimum".into(),
+            );
+        }
+        if !(1..=10).contains(&self.retry.handshake_attempts) {
+            return invalid("the connection needs 1 to 10 handshake attempts".into());
+        }
+        self.validate_register_map()
+    }
+
+    fn validate_register_map(&self) -> Result<()> {
+        let invalid =
+            |message: String| -> Result<()> { Err(SettingsError::InvalidModbus(message).into()) };
+        if self.register_map.is_empty() {
+            return Ok(());
+        }
+
+        let mut plates = Vec::new();
+        for point in &self.register_map {
+            if point.name.trim().is_empty() {
+                return invalid("every register point needs a name".into());
+            }
+            if !(point.scale.is_finite() && point.scale != 0.0 && point.offset.is_finite()) {
+                return invalid(format!("{} needs a finite, non-zero scale", point.name));
+            }
+            if point
+                .address
+                .checked_add(point.register_count() - 1)
+                .is_none()
+            {
+                return invalid(format!("{} runs past address 65535", point.name));
+            }
+            if let PointRole::PlateTemperature { plate } = point.role {
+                if plates.contains(&plate) {
+                    return invalid(format!("plate {} has two temperatures", plate + 1));
+                }
+                plates.push(plate);
+            }
+        }
+        if plates.is_empty() {
+            return invalid("the register map needs at least one plate temperature".into());
+        }
+        let pressures = self
+            .register_map
+            .iter()
+            .filter(|point| point.role == PointRole::Pressure)
+            .count();
+        if pressures > 1 {
+            return invalid("the register map can hold one pressure".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Type)]
+#[serde(rename_all = "camelCase", tag = "mode", content = "value")]
+pub enum FsyncPolicy {
+    EveryEntry,
+    EveryEntries(u32),
+    Never,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingSettings {
+    pub fsync_policy: FsyncPolicy,
+}
+
+impl Default for RecordingSettings {
+    fn default() -> Self {
+        Self {
+            fsync_policy: FsyncPolicy::EveryEntry,
+        }
+    }
+}
+
+/// `time_zone` is an IANA name such as `Europe/Madrid`. It is used to read dates
+/// without an offset on import and to write dates on export.
+#[derive(Debug, Deserialize, Serialize, Clone, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeSettings {
+    pub time_zone: String,
+}
+
+impl TimeSettings {
+    pub fn time_zone(&self) -> Result<Tz> {
+        self.time_zone
+            .parse::<Tz>()
+            .map_err(|_| SettingsError::InvalidTimeZone(self.time_zone.clone()).into())
+    }
+}
+
+impl Default for TimeSettings {
+    fn default() -> Self {
+        Self {
+            time_zone: "UTC".to_string(),
+        }
+    }
+}
+
+/// Limits the thermocouple readings are checked against. Temperatures are in
+/// °C, rates in °C per second.
+#[derive(Debug, Deserialize, Serialize, Clone, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsSettings {
+    pub min_temperature: f64,
+    pub max_temperature: f64,
+    /// Raw register values the transmitters report for a broken thermocouple
+    pub open_circuit_codes: Vec<u16>,
+    /// Identical raw readings in a row that make a sensor stuck
+    pub stuck_samples: u32,
+    /// A sensor flatlines when it stays within `flatline_band` for the whole
+    /// window while another one moves
+    pub flatline_window_ms: u64,
+    pub flatline_band: f64,
+    /// Distance from the recent readings that makes a reading a spike
+    pub spike_delta: f64,
+    pub max_rate: f64,
+    /// How much hotter the top may read than the bottom
+    pub profile_tolerance: f64,
+}
+
+impl Default for DiagnosticsSettings {
+    fn default() -> Self {
+        Self {
+            min_temperature: -20.0,
+            max_temperature: 250.0,
+            open_circuit_codes: vec![0x7FFF, 0x8000, 0xFFFF],
+            stuck_samples: 30,
+            flatline_window_ms: 5 * 60 * 1000,
+            flatline_band: 0.1,
+            spike_delta: 15.0,
+            max_rate: 2.0,
+            profile_tolerance: 2.0,
+        }
+    }
+}
+
+/// Filters and calculators applied to every sample before it is shown, in order
+#[derive(Debug, Deserialize, Serialize, Clone, Default, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessingSettings {
+    pub stages: Vec<StageConfig>,
+}
+
+impl ProcessingSettings {
+    pub fn validate(&self) -> Result<()> {
+        self.stages.iter().try_for_each(StageConfig::validate)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct KpiSettings {
+    /// Mass charged into the still, the masses of the KPIs are in its unit
+    pub initial_charge: f64,
+    /// Distillate mole fraction the prediction counts down to
+    pub target_purity: f64,
+    /// Span of the entries flow rate and trends are fitted over
+    pub trend_window_ms: u64,
+}
+
+impl Default for KpiSettings {
+    fn default() -> Self {
+        Self {
+            initial_charge: 1000.0,
+            target_purity: 0.9,
+            trend_window_ms: 10 * 60 * 1000,
+        }
+    }
+}
+
+impl KpiSettings {
+    pub fn validate(&self) -> Result<()> {
+        if !(self.initial_charge.is_finite() && self.initial_charge > 0.0) {
+            return Err(
+                SettingsError::InvalidKpi("the initial charge must be positive".into()).into(),
+            );
+        }
+        if !(self.target_purity > 0.0 && self.target_purity < 1.0) {
+            return Err(SettingsError::InvalidKpi(
+                "the target purity must be between 0 and 1".into(),
+            )
+            .into());
+        }
+        if self.trend_window_ms == 0 {
+            return Err(SettingsError::InvalidKpi("the trend window can't be empty".into()).into());
+        }
+        Ok(())
+    }
+}
+
+/// The cuts of a batch in the order they are collected
+#[derive(Debug, Deserialize, Serialize, Clone, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CutSettings {
+    pub cuts: Vec<CutDefinition>,
+}
+
+impl Default for CutSettings {
+    fn default() -> Self {
+        Self {
+            cuts: vec![
+                CutDefinition {
+                    name: "Heads".to_string(),
+                    switch_when: Some(SwitchCondition {
+                        variable: CutVariable::TopTemperature,
+                        direction: CutDirection::Above,
+                        threshold: 78.0,
+                        hysteresis: 0.5,
+                        hold_ms: 60 * 1000,
+                    }),
+                },
+                CutDefinition {
+                    name: "Hearts".to_string(),
+                    switch_when: Some(SwitchCondition {
+                        variable: CutVariable::TopComposition,
+                        direction: CutDirection::Below,
+                        threshold: 0.8,
+                        hysteresis: 0.02,
+                        hold_ms: 60 * 1000,
+                    }),
+                },
+                CutDefinition {
+                    name: "Tails".to_string(),
+                    switch_when: None,
+                },
+            ],
+        }
+    }
+}
+
+impl CutSettings {
+    pub fn validate(&self) -> Result<()> {
+        let invalid =
+            |message: String| -> Result<()> { Err(SettingsError::InvalidCuts(message).into()) };
+
+        for (index, cut) in self.cuts.iter().enumerate() {
+            if cut.name.trim().is_empty() {
+                return invalid("every cut needs a name".into());
+            }
+            if self.cuts[..index]
+                .iter()
+                .any(|other| other.name == cut.name)
+            {
+                return invalid(format!("{} is defined twice", cut.name));
+            }
+            match (&cut.switch_when, index + 1 == self.cuts.len()) {
+                (None, false) => return invalid(format!("{} has no switch condition", cut.name)),
+                (Some(_), true) => {
+                    return invalid(format!("the last cut {} can't be switched", cut.name))
+                }
+                (Some(condition), false)
+                    if !(condition.threshold.is_finite()
+                        && condition.hysteresis.is_finite()
+                        && condition.hysteresis >= 0.0) =>
+                {
+                    return invalid(format!("{} has an invalid threshold", cut.name))
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmSettings {
+    pub rules: Vec<AlarmRule>,
+}
+
+impl Default for AlarmSettings {
+    fn default() -> Self {
+        Self {
+            rules: vec![AlarmRule {
+                id: "communication-loss".to_string(),
+                name: "Communication loss".to_string(),
+                condition: AlarmCondition::CommunicationLoss,
+                deadband: 0.0,
+                on_delay_ms: 5 * 1000,
+                off_delay_ms: 0,
+                priority: AlarmPriority::High,
+            }],
+        }
+    }
+}
+
+impl AlarmSettings {
+    pub fn validate(&self) -> Result<()> {
+        let invalid =
+            |message: String| -> Result<()> { Err(SettingsError::InvalidAlarms(message).into()) };
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            if rule.id.trim().is_empty() || rule.name.trim().is_empty() {
+                return invalid("every rule needs an id and a name".into());
+            }
+            if self.rules[..index].iter().any(|other| other.id == rule.id) {
+                return invalid(format!("{} is defined twice", rule.id));
+            }
+            if !(rule.deadband.is_finite() && rule.deadband >= 0.0) {
+                return invalid(format!("{} has a negative deadband", rule.id));
+            }
+            let limit = match rule.condition {
+                AlarmCondition::High { limit, .. } | AlarmCondition::Low { limit, .. } => limit,
+                AlarmCondition::RateOfChange { max_rate, .. } => max_rate,
+                AlarmCondition::CommunicationLoss => 0.0,
+            };
+            if !limit.is_finite() {
+                return invalid(format!("{} has an invalid limit", rule.id));
+            }
         }
+        Ok(())
     }
 }
 
@@ -29,12 +513,36 @@ impl Default for ModbusSettings {
 #[serde(rename_all = "camelCase")]
 pub struct Settings {
     pub modbus: ModbusSettings,
+    #[serde(default)]
+    pub recording: RecordingSettings,
+    #[serde(default)]
+    pub import_mappings: Vec<NamedColumnMapping>,
+    #[serde(default)]
+    pub time: TimeSettings,
+    #[serde(default)]
+    pub diagnostics: DiagnosticsSettings,
+    #[serde(default)]
+    pub processing: ProcessingSettings,
+    #[serde(default)]
+    pub kpi: KpiSettings,
+    #[serde(default)]
+    pub cuts: CutSettings,
+    #[serde(default)]
+    pub alarms: AlarmSettings,
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
             modbus: ModbusSettings::default(),
+            recording: RecordingSettings::default(),
+            import_mappings: Vec::new(),
+            time: TimeSettings::default(),
+            diagnostics: DiagnosticsSettings::default(),
+            processing: ProcessingSettings::default(),
+            kpi: KpiSettings::default(),
+            cuts: CutSettings::default(),
+            alarms: AlarmSettings::default(),
         }
     }
 }
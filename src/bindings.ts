@@ -37,7 +37,7 @@ async disconnectModbus() : Promise<Result<null, Error>> {
     else return { status: "error", error: e  as any };
 }
 },
-async exportData(path: string) : Promise<Result<null, string>> {
+async exportData(path: string) : Promise<Result<null, Error>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("export_data", { path }) };
 } catch (e) {
@@ -45,9 +45,65 @@ async exportData(path: string) : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
-async importData(path: string) : Promise<Result<null, string>> {
+async exportReport(path: string, title: string | null) : Promise<Result<null, Error>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("import_data", { path }) };
+    return { status: "ok", data: await TAURI_INVOKE("export_report", { path, title }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async importData(path: string, mapping: ColumnMapping | null, policy: InvalidRowPolicy | null) : Promise<Result<ImportSummary, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("import_data", { path, mapping, policy }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async importFiles(sources: ImportSource[], mapping: ColumnMapping | null, policy: InvalidRowPolicy | null) : Promise<Result<MergeSummary, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("import_files", { sources, mapping, policy }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listSheets(path: string) : Promise<Result<string[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_sheets", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async previewImport(path: string, rows: number | null) : Promise<Result<ImportPreview, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preview_import", { path, rows }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async cancelImport() : Promise<Result<null, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("cancel_import") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async commitImport() : Promise<Result<SessionInfo, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("commit_import") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async discardImport() : Promise<Result<null, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("discard_import") };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -91,7 +147,47 @@ async setSpeed(speedFactor: number) : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
-async importTemperatures(path: string) : Promise<Result<null, string>> {
+async seekPlayback(target: SeekTarget) : Promise<Result<PlaybackPosition | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("seek_playback", { target }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setPlaybackRange(range: TimeRange | null) : Promise<Result<PlaybackPosition | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_playback_range", { range }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setLooping(looping: boolean) : Promise<Result<PlaybackPosition | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_looping", { looping }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setReverse(reverse: boolean) : Promise<Result<PlaybackPosition | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_reverse", { reverse }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async stepPlayback(forward: boolean) : Promise<Result<PlaybackPosition | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("step_playback", { forward }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async importTemperatures(path: string) : Promise<Result<null, Error>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("import_temperatures", { path }) };
 } catch (e) {
@@ -114,6 +210,346 @@ async toggleColumnData() : Promise<Result<string, string>> {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
+},
+async listSessions() : Promise<Result<SessionInfo[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_sessions") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async openSession(sessionId: number) : Promise<Result<SessionInfo, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("open_session", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async renameSession(sessionId: number, name: string) : Promise<Result<SessionInfo, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("rename_session", { sessionId, name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteSession(sessionId: number) : Promise<Result<null, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_session", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async replaySession(sessionId: number) : Promise<Result<SessionInfo, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("replay_session", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * What-if analysis of a stored run. The session itself is left untouched, the
+ * recomputed entries become the current history so they can be charted and exported.
+ */
+async recomputeSession(sessionId: number, params: EquationParams, useRecordedPressure: boolean | null) : Promise<Result<ColumnEntry[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("recompute_session", { sessionId, params, useRecordedPressure }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Attaches a note to a moment of a session. Without a session id the note goes
+ * to the session being recorded or played, without a timestamp to its newest entry.
+ */
+async addAnnotation(sessionId: number | null, timestamp: number | null, text: string) : Promise<Result<Annotation, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("add_annotation", { sessionId, timestamp, text }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateAnnotation(annotationId: number, timestamp: number | null, text: string) : Promise<Result<Annotation, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_annotation", { annotationId, timestamp, text }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteAnnotation(annotationId: number) : Promise<Result<null, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_annotation", { annotationId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listAnnotations(sessionId: number | null) : Promise<Result<Annotation[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_annotations", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Starts a batch run. Live entries from now on are recorded into a session of
+ * their own and tagged with the run until it is ended.
+ */
+async startRun(metadata: RunMetadata, numberPlates: number) : Promise<Result<RunInfo, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("start_run", { metadata, numberPlates }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setRunPhase(phase: RunPhase) : Promise<Result<RunInfo, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_run_phase", { phase }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Ends the active run. Live data keeps being recorded into its session, but
+ * is no longer tagged with it.
+ */
+async endRun() : Promise<Result<RunInfo, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("end_run") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async activeRun() : Promise<Result<RunInfo | null, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("active_run") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listRuns() : Promise<Result<RunInfo[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_runs") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async defaultEquationParams() : Promise<Result<EquationParams, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("default_equation_params") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The current cut of the running batch and the yield of every cut so far
+ */
+async cutStatus() : Promise<Result<CutStatus | null, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("cut_status") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Records that the operator switched to the next cut at the newest entry
+ */
+async switchCut() : Promise<Result<CutEvent, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("switch_cut") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async alarmStatus() : Promise<Result<AlarmStatus[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("alarm_status") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async acknowledgeAlarm(ruleId: string) : Promise<Result<AlarmTransition, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("acknowledge_alarm", { ruleId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Shelves the alarm for `duration_ms`, or until it is unshelved
+ */
+async shelveAlarm(ruleId: string, durationMs: number | null) : Promise<Result<AlarmTransition, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("shelve_alarm", { ruleId, durationMs }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unshelveAlarm(ruleId: string) : Promise<Result<AlarmTransition | null, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unshelve_alarm", { ruleId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The alarm journal over a time range, oldest first
+ */
+async alarmJournal(from: number | null, to: number | null) : Promise<Result<AlarmTransition[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("alarm_journal", { from, to }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async exportAlarmJournal(path: string, from: number | null, to: number | null) : Promise<Result<null, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_alarm_journal", { path, from, to }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listUnfinishedRecordings() : Promise<Result<JournalSummary[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_unfinished_recordings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async recoverRecording(journalId: string) : Promise<Result<JournalSummary, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("recover_recording", { journalId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async exportRecording(journalId: string, path: string) : Promise<Result<null, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_recording", { journalId, path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async discardRecording(journalId: string) : Promise<Result<null, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("discard_recording", { journalId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listImportMappings() : Promise<Result<NamedColumnMapping[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_import_mappings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async saveImportMapping(name: string, mapping: ColumnMapping) : Promise<Result<NamedColumnMapping[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("save_import_mapping", { name, mapping }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteImportMapping(name: string) : Promise<Result<NamedColumnMapping[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_import_mapping", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async loadComparison(sessionIds: number[], alignment: Alignment) : Promise<Result<SeriesInfo[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("load_comparison", { sessionIds, alignment }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async comparisonMetrics() : Promise<Result<ComparisonMetrics, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("comparison_metrics") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async clearComparison() : Promise<Result<null, Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("clear_comparison") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Plays the loaded comparison, one frame with every run per reference sample.
+ * It has its own pause and cancel, so a single run can play next to it.
+ */
+async sendComparisonData() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("send_comparison_data") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async toggleComparisonData() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("toggle_comparison_data") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async cancelComparisonData() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("cancel_comparison_data") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Temperatures of the current run over a time range for charting. A resolution
+ * of zero, or none, returns every entry.
+ */
+async queryHistory(from: number | null, to: number | null, resolution: number | null) : Promise<Result<HistoryBucket[], Error>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("query_history", { from, to, resolution }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
 }
 }
 
@@ -127,15 +563,471 @@ async toggleColumnData() : Promise<Result<string, string>> {
 
 /** user-defined types **/
 
-export type DataError = { type: "EmptyDataError" } | { type: "NoMoreDataError" } | { type: "NoDataError" } | { type: "CustomError"; data: string }
-export type Error = { type: "SettingsError"; data: SettingsError } | { type: "FileError"; data: FileError } | { type: "ModbusError"; data: ModbusError } | { type: "RootError"; data: RootError } | { type: "DataError"; data: DataError } | { type: "ImportError"; data: ImportError }
+export type AlarmCondition = { type: "high"; input: AlarmInput; limit: number } | { type: "low"; input: AlarmInput; limit: number } | 
+/**
+ * Change of the input per minute, in either direction
+ */
+{ type: "rateOfChange"; input: AlarmInput; maxRate: number } | 
+/**
+ * The column could not be read, the entry only holds the last values
+ */
+{ type: "communicationLoss" }
+export type AlarmError = { type: "UnknownAlarm"; data: string } | { type: "NothingToAcknowledge"; data: string }
+/**
+ * The value of an entry a rule watches. Plates count from the bottom.
+ */
+export type AlarmInput = { kind: "temperature"; plate: number } | { kind: "composition"; plate: number; phase: Phase } | { kind: "kpi"; field: KpiField }
+export type AlarmPriority = "low" | "medium" | "high" | "critical"
+/**
+ * A rule raises its alarm once the condition held for `on_delay_ms`, and the
+ * alarm returns to normal once the value is back past the limit by `deadband`
+ * for `off_delay_ms`.
+ */
+export type AlarmRule = { id: string; name: string; condition: AlarmCondition; deadband?: number; onDelayMs?: number; offDelayMs?: number; priority?: AlarmPriority }
+export type AlarmSettings = { rules: AlarmRule[] }
+export type AlarmState = "normal" | 
+/**
+ * Raised and not acknowledged yet
+ */
+"active" | 
+/**
+ * Raised and acknowledged by the operator
+ */
+"acknowledged" | 
+/**
+ * Back to normal before it was acknowledged
+ */
+"cleared" | 
+/**
+ * Suppressed by the operator for a while
+ */
+"shelved"
+export type AlarmStatus = { ruleId: string; name: string; priority: AlarmPriority; state: AlarmState; 
+/**
+ * When the current state was entered
+ */
+since: number | null; shelvedUntil: number | null }
+/**
+ * A change of an alarm's state, as written to the alarm journal
+ */
+export type AlarmTransition = { timestamp: number; ruleId: string; name: string; priority: AlarmPriority; from: AlarmState; to: AlarmState; 
+/**
+ * The watched value when the transition was caused by the process
+ */
+value: number | null }
+/**
+ * How the runs of a comparison are lined up. A crossing aligns every run on the
+ * first sample where `plate` reaches `threshold`, the top plate when not given.
+ */
+export type Alignment = { mode: "start" } | { mode: "temperatureCrossing"; plate: number | null; threshold: number }
+/**
+ * An operator note attached to a moment of a session
+ */
+export type Annotation = { id: number; sessionId: number; timestamp: number; text: string; createdAt: number; updatedAt: number | null }
+/**
+ * Order of the two bytes within every register
+ */
+export type ByteOrder = "bigEndian" | 
+/**
+ * Bytes swapped, as some transmitters send them
+ */
+"littleEndian"
+export type ColumnEntry = { 
+/**
+ * Milliseconds since the Unix epoch, or since the start of the run for
+ * imports with relative times
+ */
+timestamp: number; temperatures: number[]; compositions: CompositionResult[]; percentageComplete: number; distilledMass: number; pressure?: number | null; quality?: EntryQuality; 
+/**
+ * The batch run a live entry was produced in
+ */
+run?: RunTag | null; 
+/**
+ * Every value of the register map, as configured
+ */
+measurements?: Measurement[] }
+/**
+ * Zero based column indices of a sheet. Temperatures are listed from the bottom
+ * plate to the top one, compositions follow the same order. The pressure column
+ * is read in the same unit as `EquationParams::p`.
+ */
+export type ColumnMapping = { timestamp: number; temperatures: number[]; pressure: number | null; compositionsX: number[] | null; compositionsY: number[] | null; 
+/**
+ * Detected from the first data row when not set
+ */
+timestampFormat?: TimestampFormat | null }
+/**
+ * Differences of every run to the reference, which is the first series.
+ */
+export type ComparisonMetrics = { reference: SeriesInfo; series: SeriesMetrics[] }
+export type CompositionResult = { x_1: number | null; y_1: number | null }
+/**
+ * A cut of the batch, in the order they are collected. The last cut has no
+ * switch condition and lasts until the end of the batch.
+ */
+export type CutDefinition = { name: string; switchWhen: SwitchCondition | null }
+export type CutDirection = "above" | "below"
+export type CutEvent = 
+/**
+ * The current cut should be switched to the next one
+ */
+{ type: "switchRecommended"; timestamp: number; from: string; to: string; value: number } | 
+/**
+ * The variable came back before the operator switched
+ */
+{ type: "recommendationWithdrawn"; timestamp: number; cut: string } | { type: "switched"; timestamp: number; from: string; to: string; recommended: boolean }
+/**
+ * The cuts of a batch in the order they are collected
+ */
+export type CutSettings = { cuts: CutDefinition[] }
+export type CutStatus = { current: string; next: string | null; recommended: boolean; yields: CutYield[] }
+export type CutVariable = 
+/**
+ * Vapour composition y_1 of the top plate
+ */
+"topComposition" | "topTemperature"
+/**
+ * What was collected in one cut. Masses come from the distillate balance of
+ * the KPIs, `end` is `None` for the cut that is still being collected.
+ */
+export type CutYield = { name: string; start: number; end: number | null; mass: number | null; 
+/**
+ * Share of all distillate collected so far
+ */
+share: number | null; meanComposition: number | null }
+export type DataError = { type: "EmptyDataError" } | { type: "NoMoreDataError" } | { type: "NoDataError" } | { type: "CustomError"; data: string } | { type: "NoNextCut" }
+export type DerivedKind = 
+/**
+ * Bottom minus top temperature
+ */
+{ kind: "columnDelta" } | 
+/**
+ * Mean temperature of all plates
+ */
+{ kind: "meanTemperature" } | 
+/**
+ * Change of a plate's temperature per minute
+ */
+{ kind: "plateRate"; plate: number }
+/**
+ * Limits the thermocouple readings are checked against. Temperatures are in
+ * °C, rates in °C per second.
+ */
+export type DiagnosticsSettings = { minTemperature: number; maxTemperature: number; 
+/**
+ * Raw register values the transmitters report for a broken thermocouple
+ */
+openCircuitCodes: number[]; 
+/**
+ * Identical raw readings in a row that make a sensor stuck
+ */
+stuckSamples: number; 
+/**
+ * A sensor flatlines when it stays within `flatline_band` for the whole
+ * window while another one moves
+ */
+flatlineWindowMs: number; flatlineBand: number; 
+/**
+ * Distance from the recent readings that makes a reading a spike
+ */
+spikeDelta: number; maxRate: number; 
+/**
+ * How much hotter the top may read than the bottom
+ */
+profileTolerance: number }
+/**
+ * Quality of every plate value of an entry. Missing qualities are good, so
+ * entries stored before they were tracked read as measured.
+ */
+export type EntryQuality = { temperatures: Quality[]; compositions: Quality[] }
+/**
+ * Antoine constants and Van Laar coefficients of the mixture, with the column
+ * pressure in the unit of the Antoine equation
+ */
+export type EquationParams = { a_1: number; b_1: number; c_1: number; a_van_1: number; a_2: number; b_2: number; c_2: number; a_van_2: number; p: number; model?: ThermoModel }
+export type Error = { type: "SettingsError"; data: SettingsError } | { type: "FileError"; data: FileError } | { type: "ModbusError"; data: ModbusError } | { type: "RootError"; data: RootError } | { type: "DataError"; data: DataError } | { type: "ImportError"; data: ImportError } | { type: "StorageError"; data: StorageError } | { type: "AlarmError"; data: AlarmError } | { type: "RunError"; data: RunError }
 export type FileError = { type: "ReadError"; data: string } | { type: "WriteError"; data: string } | { type: "EnsureFileError"; data: string } | { type: "InvalidFileType" } | { type: "ParseJsonError"; data: string } | { type: "CreateDirError"; data: string } | { type: "SerializeError"; data: string } | { type: "InvalidPathError"; data: string }
-export type ImportError = { type: "InvalidFormat"; data: string }
-export type ModbusError = { type: "ConnectionError"; data: string } | { type: "ReadCoilsError"; data: string } | { type: "WriteCoilsError"; data: string } | { type: "ReadHoldingRegistersError"; data: string } | { type: "WriteHoldingRegistersError"; data: string } | { type: "WriteSingleCoilError"; data: string } | { type: "WriteSingleRegisterError"; data: string }
-export type ModbusSettings = { usbPort: string; baudrate: number; initialAddress: number; count: number; timeout: number; unitId: number }
+export type FsyncPolicy = { mode: "everyEntry" } | { mode: "everyEntries"; value: number } | { mode: "never" }
+/**
+ * Temperatures of every plate over `from..=to`. A bucket of a single entry has
+ * the same minimum, maximum and mean.
+ */
+export type HistoryBucket = { from: number; to: number; count: number; min: number[]; max: number[]; mean: number[]; 
+/**
+ * Distilled mass of the newest entry in the bucket
+ */
+distilledMass: number }
+export type ImportError = { type: "InvalidFormat"; data: string } | { type: "InvalidMapping"; data: string } | { type: "PlateMismatch"; data: string } | { type: "InvalidRow"; data: string } | { type: "NothingPending" } | { type: "Cancelled" } | { type: "TaskError"; data: string }
+export type ImportPreview = { headers: string[]; rows: string[][]; suggestedMapping: ColumnMapping | null }
+/**
+ * A workbook to import. Without a sheet name the first sheet is read.
+ */
+export type ImportSource = { path: string; sheet: string | null }
+/**
+ * Validation report of an import, returned before the data is committed.
+ */
+export type ImportSummary = { numberPlates: number; policy: InvalidRowPolicy; rowsRead: number; rowsImported: number; rowsSkipped: number; rowsRepaired: number; diagnostics: RowDiagnostic[]; timestampFormat: TimestampFormat | null; durationMs: number }
+/**
+ * What the importer does with a row holding a missing or non-numeric temperature.
+ * Rows without a usable timestamp are always skipped, or abort the import.
+ */
+export type InvalidRowPolicy = "skip" | "fillForward" | "interpolate" | "abort"
+export type JournalSummary = { id: string; startedAt: number; numberPlates: number; entryCount: number; lastTimestamp: number | null }
+export type KpiField = "stillComposition" | "distillateComposition" | "distilledMass" | "flowRate" | "recovery" | "timeToTarget"
+export type KpiSettings = { 
+/**
+ * Mass charged into the still, the masses of the KPIs are in its unit
+ */
+initialCharge: number; 
+/**
+ * Distillate mole fraction the prediction counts down to
+ */
+targetPurity: number; 
+/**
+ * Span of the entries flow rate and trends are fitted over
+ */
+trendWindowMs: number }
+export type Measurement = { name: string; role: PointRole; value: number; unit: string }
+/**
+ * Report of a multi source import. Sources are listed in the order they were merged.
+ */
+export type MergeSummary = { numberPlates: number; sources: SourceSummary[]; entryCount: number; duplicatesDropped: number; overlaps: SourceOverlap[]; gaps: TimeRange[] }
+export type ModbusError = { type: "ConnectionError"; data: string } | { type: "HandshakeError"; data: string } | { type: "ReadCoilsError"; data: string } | { type: "WriteCoilsError"; data: string } | { type: "ReadHoldingRegistersError"; data: string } | { type: "ReadInputRegistersError"; data: string } | { type: "WriteHoldingRegistersError"; data: string } | { type: "WriteSingleCoilError"; data: string } | { type: "WriteSingleRegisterError"; data: string }
+export type ModbusSettings = { transport?: ModbusTransport; usbPort: string; baudrate: number; serial?: SerialLineSettings; retry?: RetrySettings; 
+/**
+ * Without a register map `initial_address..+count` hold the plate temperatures
+ * from the bottom up, in hundredths of °C. With fewer registers than plates the
+ * first one is the bottom plate and the last one the top.
+ */
+initialAddress: number; count: number; timeout: number; unitId: number; registerMap?: RegisterPoint[] }
+/**
+ * How the controller is reached
+ */
+export type ModbusTransport = 
+/**
+ * Serial line on `usb_port` at `baudrate`
+ */
+{ type: "rtu" } | 
+/**
+ * Modbus TCP, `host` is an IP address or a name to resolve. Serial gateways
+ * must translate to Modbus TCP, RTU frames tunnelled over TCP are not spoken.
+ */
+{ type: "tcp"; host: string; port: number }
+export type NamedColumnMapping = { name: string; mapping: ColumnMapping }
+export type Phase = "liquid" | "vapour"
+export type PhaseChange = { phase: RunPhase; timestamp: number }
+/**
+ * The entry playback emits next, with the in and out points it plays between
+ */
+export type PlaybackPosition = { index: number; total: number; timestamp: number; fraction: number; range: TimeRange; looping: boolean; reverse: boolean }
+/**
+ * What a point measures. Plates count from the bottom.
+ */
+export type PointRole = 
+/**
+ * In °C once scaled
+ */
+{ kind: "plateTemperature"; plate: number } | { kind: "pressure" } | { kind: "flow" } | { kind: "level" } | { kind: "other" }
+/**
+ * Filters and calculators applied to every sample before it is shown, in order
+ */
+export type ProcessingSettings = { stages: StageConfig[] }
+/**
+ * Quality of a single value, in the style of OPC status codes
+ */
+export type Quality = { status: "good" } | { status: "uncertain"; reason: QualityReason } | { status: "bad"; reason: QualityReason }
+export type QualityReason = 
+/**
+ * Estimated from the measured plates around it
+ */
+"plateInterpolation" | 
+/**
+ * Estimated from the readings before and after it
+ */
+"timeInterpolation" | 
+/**
+ * Repeats the previous reading
+ */
+"filledForward" | 
+/**
+ * The last known value, the sensor could not be read
+ */
+"communicationFailure" | 
+/**
+ * The equilibrium solver did not converge
+ */
+"solverFailure" | 
+/**
+ * Diagnostics found the sensor, or the profile it is part of, faulty
+ */
+"sensorFault" | "missingValue"
+export type RecordingSettings = { fsyncPolicy: FsyncPolicy }
+export type RegisterDataType = "u16" | "i16" | 
+/**
+ * Two registers
+ */
+"u32" | 
+/**
+ * Two registers, IEEE 754
+ */
+"f32"
+export type RegisterFunction = 
+/**
+ * Function code 3
+ */
+"holding" | 
+/**
+ * Function code 4
+ */
+"input"
+/**
+ * A value of the register map. The decoded number is turned into the
+ * engineering value as `raw * scale + offset`.
+ */
+export type RegisterPoint = { name: string; address: number; function?: RegisterFunction; dataType?: RegisterDataType; wordOrder?: WordOrder; byteOrder?: ByteOrder; scale: number; offset?: number; unit?: string; role: PointRole }
+/**
+ * Pause before reopening the port, reconnecting or probing the device again,
+ * doubled after every failure
+ */
+export type RetrySettings = { minDelayMs: number; maxDelayMs: number; 
+/**
+ * Probes sent when connecting before giving up
+ */
+handshakeAttempts: number }
 export type RootError = { type: "NotFoundedRoot" } | { type: "DivisionByZero" } | { type: "NegativeRootError" }
-export type Settings = { modbus: ModbusSettings }
-export type SettingsError = { type: "LoadError"; data: string } | { type: "SaveError"; data: string } | { type: "GetUsbDevicesError"; data: string }
+/**
+ * One invalid cell. `row` is one based like the sheet, `column` is zero based
+ * like `ColumnMapping`.
+ */
+export type RowDiagnostic = { row: number; column: number | null; reason: string; rawValue: string | null; resolution: RowResolution }
+export type RowResolution = "skipped" | "filledForward" | "interpolated"
+export type RunError = { type: "AlreadyActive"; data: string } | { type: "NoActiveRun" } | { type: "InvalidMetadata"; data: string }
+/**
+ * A batch from start to end. Its entries are recorded in `session_id`.
+ */
+export type RunInfo = { id: number; sessionId: number; metadata: RunMetadata; startedAt: number; endedAt: number | null; 
+/**
+ * Oldest first
+ */
+phases: PhaseChange[] }
+/**
+ * What the operator enters when starting a run
+ */
+export type RunMetadata = { batchId: string; operator: string; feedDescription?: string; 
+/**
+ * Mass charged to the still, in the unit of the distilled mass
+ */
+chargeMass: number }
+export type RunPhase = "heatUp" | "totalReflux" | "production" | "shutdown"
+/**
+ * The run an entry was produced in, as stored with the entry
+ */
+export type RunTag = { runId: number; batchId: string; phase: RunPhase | null }
+/**
+ * Where to move playback to. Fractions run from 0 at the first entry to 1 at the last.
+ */
+export type SeekTarget = { mode: "timestamp"; value: number } | { mode: "fraction"; value: number }
+export type SerialFlowControl = "none" | 
+/**
+ * XON/XOFF
+ */
+"software" | 
+/**
+ * RTS/CTS
+ */
+"hardware"
+/**
+ * Framing of the RTU serial line, 8N1 unless the device needs otherwise
+ */
+export type SerialLineSettings = { dataBits: number; parity: SerialParity; stopBits: number; flowControl: SerialFlowControl; 
+/**
+ * Extra silence before every request, for devices slower than the 3.5
+ * characters Modbus RTU asks for
+ */
+interFrameDelayMs: number }
+export type SerialParity = "none" | "even" | "odd"
+export type SeriesInfo = { sessionId: number; name: string; numberPlates: number; 
+/**
+ * Timestamp of the alignment point, elapsed times are measured from it
+ */
+alignedAt: number; entryCount: number }
+export type SeriesMetrics = { sessionId: number; name: string; 
+/**
+ * RMS temperature difference to the reference per plate, `None` without overlap
+ */
+rmsDeviation: (number | null)[]; 
+/**
+ * Shift in milliseconds that best lines the top plate purity up with the
+ * reference. Positive when this run reaches the same purity later.
+ */
+purityOffset: number | null; overlap: number }
+export type SessionInfo = { id: number; name: string; source: SessionSource; numberPlates: number; createdAt: number; startedAt: number | null; endedAt: number | null; entryCount: number; 
+/**
+ * The batch run recorded into the session
+ */
+runId: number | null; batchId: string | null }
+export type SessionSource = "live" | "imported"
+export type Settings = { modbus: ModbusSettings; recording?: RecordingSettings; importMappings?: NamedColumnMapping[]; time?: TimeSettings; diagnostics?: DiagnosticsSettings; processing?: ProcessingSettings; kpi?: KpiSettings; cuts?: CutSettings; alarms?: AlarmSettings }
+export type SettingsError = { type: "LoadError"; data: string } | { type: "SaveError"; data: string } | { type: "GetUsbDevicesError"; data: string } | { type: "InvalidTimeZone"; data: string } | { type: "InvalidPipeline"; data: string } | { type: "InvalidKpi"; data: string } | { type: "InvalidCuts"; data: string } | { type: "InvalidAlarms"; data: string } | { type: "InvalidModbus"; data: string }
+export type SourceOverlap = { first: string; second: string; range: TimeRange }
+export type SourceSummary = { source: ImportSource; range: TimeRange | null; summary: ImportSummary }
+/**
+ * One step of the pipeline. Filters work on every plate on their own, windows
+ * are counted in samples.
+ */
+export type StageConfig = { stage: "movingAverage"; window: number } | 
+/**
+ * Exponentially weighted moving average, `alpha` is the weight of the newest sample
+ */
+{ stage: "ewma"; alpha: number } | { stage: "median"; window: number } | 
+/**
+ * Holds the output until the input moves further than `band` from it
+ */
+{ stage: "deadband"; band: number } | 
+/**
+ * Replaces readings further than `max_deviation` from the median of the
+ * last `window` readings with that median
+ */
+{ stage: "outlierRejection"; window: number; maxDeviation: number } | 
+/**
+ * Converts the temperatures, stages after it work in the new unit
+ */
+{ stage: "unitConversion"; unit: TemperatureUnit } | 
+/**
+ * Adds a value computed from the temperatures at this point of the pipeline
+ */
+{ stage: "derived"; name: string; value: DerivedKind }
+export type StorageError = { type: "OpenError"; data: string } | { type: "QueryError"; data: string } | { type: "SerializeError"; data: string } | { type: "SessionNotFound"; data: number } | { type: "AnnotationNotFound"; data: number } | { type: "EmptyAnnotation" } | { type: "NoSession" } | { type: "RunNotFound"; data: number }
+/**
+ * When to leave a cut. The switch is recommended once the variable has been
+ * past `threshold` for `hold_ms`, and withdrawn only when it comes back by
+ * more than `hysteresis`.
+ */
+export type SwitchCondition = { variable: CutVariable; direction: CutDirection; threshold: number; hysteresis: number; holdMs?: number }
+export type TemperatureUnit = "celsius" | "fahrenheit" | "kelvin"
+/**
+ * How the liquid phase deviates from Raoult's law
+ */
+export type ThermoModel = "VanLaar" | 
+/**
+ * Ideal solution, activity coefficients of one
+ */
+"Ideal"
+export type TimeRange = { from: number; to: number }
+/**
+ * `time_zone` is an IANA name such as `Europe/Madrid`. It is used to read dates
+ * without an offset on import and to write dates on export.
+ */
+export type TimeSettings = { timeZone: string }
+/**
+ * How the timestamp column of a sheet is encoded. Dates without an explicit
+ * offset are read in the configured time zone.
+ */
+export type TimestampFormat = "excelDateTime" | "iso8601" | "unixSeconds" | "unixMillis" | "relativeSeconds"
+/**
+ * Which register of a two register value holds the high word
+ */
+export type WordOrder = "highFirst" | "lowFirst"
 
 /** tauri-specta globals **/
 
@@ -9,13 +9,22 @@ import {
   DialogTitle,
   DialogTrigger,
 } from "@/components/ui/dialog";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
 import { useData } from "@/hooks/useData";
-import { usePlates } from "@/hooks/usePlates";
+import { useSettings } from "@/hooks/useSettings";
 import { cn } from "@/lib/utils";
 import { FileSpreadsheet, Upload, X } from "lucide-react";
 import { useState } from "react";
 import { toast } from "sonner";
 
+const AUTO_MAPPING = "auto";
+
 export function ImportDialog({ children }: { children: React.ReactNode }) {
   const [isOpen, setIsOpen] = useState(false);
   const [isImporting, setIsImporting] = useState(false);
@@ -24,7 +33,10 @@ export function ImportDialog({ children }: { children: React.ReactNode }) {
   const connected = useData((state) => state.connected);
   const clearData = useData((state) => state.clearData);
   const setConnected = useData((state) => state.setConnected);
-  const numberPlates = usePlates((state) => state.numberPlates);
+  const { settings } = useSettings();
+  const mappings = settings?.importMappings ?? [];
+  // Without a saved mapping the columns are detected from the header row
+  const [mappingName, setMappingName] = useState(AUTO_MAPPING);
 
   const handleImport = () => {
     if (!filePath) {
@@ -35,10 +47,24 @@ export function ImportDialog({ children }: { children: React.ReactNode }) {
     setIsImporting(true);
 
     const handleFile = async () => {
-      const response = await commands.importData(filePath);
+      const mapping =
+        mappings.find(({ name }) => name === mappingName)?.mapping ?? null;
+      const response = await commands.importData(filePath, mapping, null);
       if (response.status !== "ok") {
         throw new Error("Failed to import data");
       }
+      const { rowsSkipped, rowsRepaired, numberPlates } = response.data;
+      if (rowsSkipped > 0 || rowsRepaired > 0) {
+        toast.warning(
+          `${rowsSkipped} rows skipped, ${rowsRepaired} rows repaired`,
+        );
+      }
+
+      // The import is only played back once committed
+      const committed = await commands.commitImport();
+      if (committed.status !== "ok") {
+        throw new Error("Failed to import data");
+      }
       commands.sendColumnData(numberPlates);
     };
 
@@ -126,6 +152,26 @@ export function ImportDialog({ children }: { children: React.ReactNode }) {
             </div>
           </div>
         )}
+        <div className="flex items-center justify-between gap-2">
+          <p className="text-sm text-muted-foreground">Column mapping</p>
+          <Select
+            value={mappingName}
+            onValueChange={setMappingName}
+            disabled={isImporting}
+          >
+            <SelectTrigger className="w-[200px]">
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              <SelectItem value={AUTO_MAPPING}>Detect from header</SelectItem>
+              {mappings.map(({ name }) => (
+                <SelectItem key={name} value={name}>
+                  {name}
+                </SelectItem>
+              ))}
+            </SelectContent>
+          </Select>
+        </div>
         <DialogFooter className="gap-2">
           <Button
             variant="outline"
@@ -0,0 +1,51 @@
+import { commands, JournalSummary } from "@/bindings";
+import { useEffect } from "react";
+import { toast } from "sonner";
+
+const describe = (journal: JournalSummary) =>
+  `${journal.entryCount} entries from ${new Date(journal.startedAt).toLocaleString()}`;
+
+/** Offers to recover the recordings an unexpected shutdown left behind */
+export function RecoveryPrompt() {
+  useEffect(() => {
+    const checkRecordings = async () => {
+      const response = await commands.listUnfinishedRecordings();
+      if (response.status !== "ok") {
+        toast.error("Failed to look for unfinished recordings");
+        return;
+      }
+
+      for (const journal of response.data) {
+        toast.warning("An unfinished recording was found", {
+          id: journal.id,
+          description: describe(journal),
+          duration: Infinity,
+          action: {
+            label: "Recover",
+            onClick: async () => {
+              const recovered = await commands.recoverRecording(journal.id);
+              if (recovered.status !== "ok") {
+                toast.error("Failed to recover the recording");
+                return;
+              }
+              toast.success("Recording recovered");
+            },
+          },
+          cancel: {
+            label: "Discard",
+            onClick: async () => {
+              const discarded = await commands.discardRecording(journal.id);
+              if (discarded.status !== "ok") {
+                toast.error("Failed to discard the recording");
+              }
+            },
+          },
+        });
+      }
+    };
+
+    checkRecordings();
+  }, []);
+
+  return null;
+}
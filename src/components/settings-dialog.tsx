@@ -55,6 +55,7 @@ export function SettingsDialog({ children }: { children: React.ReactNode }) {
     try {
       const newSettings: Partial<SettingsType> = {
         modbus: {
+          ...settings.modbus,
           usbPort: usbPort ?? "",
           baudrate,
           initialAddress,
@@ -12,6 +12,7 @@ interface DataState {
   filePath: string;
   fileProgress: number;
   setColumnData: (columnData: ColumnDataEntry) => void;
+  setSessionData: (columnData: ColumnDataEntry[]) => void;
   setConnected: (connected: DataMode) => void;
   setLoading: (isLoading: boolean) => void;
   setFilePath: (filePath: string) => void;
@@ -40,6 +41,14 @@ export const useData = create<DataState>((set) => ({
       };
     }, true);
   },
+  setSessionData: (columnData: ColumnDataEntry[]) =>
+    set(() => ({
+      columnData:
+        columnData.length > MAX_DATA_LENGTH + 1
+          ? [columnData[0], ...columnData.slice(-MAX_DATA_LENGTH)]
+          : columnData,
+      fileProgress: 100,
+    })),
   setConnected: (connected: DataMode) => set(() => ({ connected })),
   setLoading: (isLoading: boolean) => set((state) => ({ ...state, isLoading })),
   setFilePath: (filePath: string) => set(() => ({ filePath })),
@@ -8,7 +8,7 @@ export function formatTempPerTime(columnData: ColumnDataEntry[]) {
   const lastData = columnData.slice(-MAX_DATA_LENGTH);
 
   let formatedData = lastData.map((entry) => {
-    const transcurredTime = entry.timestamp - initialDate;
+    const transcurredTime = (entry.timestamp - initialDate) / 1000;
     const formattedTime = formatTime(transcurredTime);
     return {
       time: formattedTime,
@@ -65,7 +65,7 @@ export function formatDistillationChart(columnData: ColumnDataEntry[]) {
   const lastData = columnData.slice(-MAX_DATA_LENGTH);
 
   let formatedData = lastData.map((entry) => {
-    const transcurredTime = entry.timestamp - initialDate;
+    const transcurredTime = (entry.timestamp - initialDate) / 1000;
     const formattedTime = formatTime(transcurredTime);
     return {
       time: formattedTime,
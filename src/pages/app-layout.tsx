@@ -1,3 +1,4 @@
+import { RecoveryPrompt } from "@/components/recovery-prompt";
 import { useSettings } from "@/hooks/useSettings";
 import { useEffect } from "react";
 import { Outlet } from "react-router";
@@ -16,6 +17,7 @@ export function AppLayout() {
   return (
     <main className="flex min-h-screen">
       <Outlet />
+      <RecoveryPrompt />
     </main>
   );
 }
@@ -17,6 +17,7 @@ import { TowerSection } from "./tower-section";
 export function DashboardPage() {
   const setColumnData = useData((state) => state.setColumnData);
   const setConnected = useData((state) => state.setConnected);
+  const setSessionData = useData((state) => state.setSessionData);
 
   useEffect(() => {
     const unlisten = listen<ColumnDataEntry>("column_data", (event) => {
@@ -35,6 +36,26 @@ export function DashboardPage() {
     };
   }, []);
 
+  // Recovered recordings and reopened sessions arrive all at once
+  useEffect(() => {
+    const unlisten = listen<ColumnDataEntry[]>("session_data", (event) => {
+      setSessionData(event.payload);
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  // Acquisition carries on when part of the recording could not be written
+  useEffect(() => {
+    const unlisten = listen<string>("recording_error", (event) => {
+      toast.error(event.payload);
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
   return (
     <div className="grid h-screen w-full grid-cols-6 grid-rows-11 gap-1 p-1">
       <Header className="col-span-6 row-span-1 rounded border" />
@@ -1,12 +1,43 @@
 export type SettingsType = {
   modbus: {
+    transport?: { type: "rtu" } | { type: "tcp"; host: string; port: number };
     usbPort: string;
     baudrate: number;
+    serial?: {
+      dataBits: number;
+      parity: "none" | "even" | "odd";
+      stopBits: number;
+      flowControl: "none" | "software" | "hardware";
+      interFrameDelayMs: number;
+    };
+    retry?: { minDelayMs: number; maxDelayMs: number; handshakeAttempts?: number };
     initialAddress: number;
     count: number;
     timeout: number;
     unitId: number;
+    registerMap?: RegisterPoint[];
   };
+  importMappings?: {
+    name: string;
+    mapping: import("@/bindings").ColumnMapping;
+  }[];
+};
+
+type PointRole =
+  | { kind: "plateTemperature"; plate: number }
+  | { kind: "pressure" | "flow" | "level" | "other" };
+
+export type RegisterPoint = {
+  name: string;
+  address: number;
+  function?: "holding" | "input";
+  dataType?: "u16" | "i16" | "u32" | "f32";
+  wordOrder?: "highFirst" | "lowFirst";
+  byteOrder?: "bigEndian" | "littleEndian";
+  scale: number;
+  offset?: number;
+  unit?: string;
+  role: PointRole;
 };
 
 export type SettingsContextType = {
@@ -24,10 +55,82 @@ type CompositionResult = {
   y_1: number;
 };
 
+type Quality =
+  | { status: "good" }
+  | { status: "uncertain" | "bad"; reason: string };
+
 type ColumnDataEntry = {
   timestamp: number;
   temperatures: number[];
   compositions: CompositionResult[];
   percentageComplete: number;
   distilledMass: number;
+  quality?: {
+    temperatures: Quality[];
+    compositions: Quality[];
+  };
+  run?: { runId: number; batchId: string; phase: RunPhase | null } | null;
+  measurements?: { name: string; role: PointRole; value: number; unit: string }[];
+};
+
+type ProcessedDataEntry = {
+  timestamp: number;
+  temperatures: number[];
+  unit: "celsius" | "fahrenheit" | "kelvin";
+  derived: { name: string; value: number | null }[];
+};
+
+type KpiEntry = {
+  timestamp: number;
+  stillComposition: number | null;
+  distillateComposition: number | null;
+  stillMass: number | null;
+  distilledMass: number | null;
+  flowRate: number | null;
+  recovery: number | null;
+  timeToTarget: number | null;
+  rayleighTimeToTarget: number | null;
+  trendTimeToTarget: number | null;
+};
+
+type CutEvent =
+  | { type: "switchRecommended"; timestamp: number; from: string; to: string; value: number }
+  | { type: "recommendationWithdrawn"; timestamp: number; cut: string }
+  | { type: "switched"; timestamp: number; from: string; to: string; recommended: boolean };
+
+type AlarmState = "normal" | "active" | "acknowledged" | "cleared" | "shelved";
+
+type AlarmTransition = {
+  timestamp: number;
+  ruleId: string;
+  name: string;
+  priority: "low" | "medium" | "high" | "critical";
+  from: AlarmState;
+  to: AlarmState;
+  value: number | null;
+};
+
+type Annotation = {
+  id: number;
+  sessionId: number;
+  timestamp: number;
+  text: string;
+  createdAt: number;
+  updatedAt: number | null;
+};
+
+type RunPhase = "heatUp" | "totalReflux" | "production" | "shutdown";
+
+type RunInfo = {
+  id: number;
+  sessionId: number;
+  metadata: {
+    batchId: string;
+    operator: string;
+    feedDescription: string;
+    chargeMass: number;
+  };
+  startedAt: number;
+  endedAt: number | null;
+  phases: { phase: RunPhase; timestamp: number }[];
 };